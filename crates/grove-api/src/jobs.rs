@@ -0,0 +1,186 @@
+//! Background jobs
+//!
+//! Periodic work the server does on its own, independent of any request.
+
+use crate::routes::{
+    do_cleanup_stale, do_disk_usage, do_maintenance, do_purge_repository, maintenance_result,
+    run_operation,
+};
+use crate::AppState;
+use grove_core::{CleanupMode, MaintenanceTask, OperationKind, Requester, TrashedRepository};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant};
+
+/// How often the scheduler wakes up to look for due work
+const TICK: Duration = Duration::from_secs(15 * 60);
+
+/// How often stale worktrees are looked for
+const STALE_CHECK: Duration = Duration::from_secs(24 * 3600);
+
+/// Spawn all background jobs
+pub fn spawn(state: Arc<AppState>) {
+    if let Some(every) = state.config.maintenance_interval {
+        tokio::spawn(maintenance_loop(Arc::clone(&state), every));
+    }
+    if let Some(every) = state.config.disk_usage_interval {
+        tokio::spawn(disk_usage_loop(Arc::clone(&state), every));
+    }
+    if let Some(retention) = state.config.trash_retention {
+        tokio::spawn(trash_loop(Arc::clone(&state), retention));
+    }
+    if state.config.stale_cleanup != CleanupMode::Off {
+        tokio::spawn(stale_loop(state));
+    }
+}
+
+/// Run maintenance on every repository whose last run is older than `every`
+/// Repositories are processed one at a time to keep disk IO reasonable.
+async fn maintenance_loop(state: Arc<AppState>, every: Duration) {
+    let mut ticker = interval_at(Instant::now() + TICK, TICK);
+    let every_ms = every.as_millis() as i64;
+
+    loop {
+        ticker.tick().await;
+
+        let repos = match state.db.call(|db| db.list_repositories()).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::error!("Maintenance scheduler failed to list repositories: {}", e);
+                continue;
+            }
+        };
+
+        for repo in repos {
+            // Still cloning, or the clone failed
+            if repo.last_synced == 0 || repo.error.is_some() {
+                continue;
+            }
+
+            let repo_id = repo.id.clone();
+            let runs = state
+                .db
+                .call(move |db| db.list_maintenance_runs(&repo_id))
+                .await
+                .unwrap_or_default();
+            let now = chrono::Utc::now().timestamp_millis();
+            let due = runs.len() < MaintenanceTask::ALL.len()
+                || runs.iter().any(|run| run.finished_at + every_ms <= now);
+            if !due {
+                continue;
+            }
+
+            tracing::info!("Scheduled maintenance: {}", repo.name);
+            let run = do_maintenance(Arc::clone(&state), &repo.id, &MaintenanceTask::ALL);
+            if let Err(e) = run_operation(
+                &state,
+                OperationKind::Maintenance,
+                &repo.id,
+                Requester::Server,
+                async { maintenance_result(run.await) },
+            )
+            .await
+            {
+                tracing::warn!("Scheduled maintenance for {} failed: {}", repo.name, e);
+            }
+        }
+    }
+}
+
+/// Recompute disk usage for every repository every `every`
+/// The first pass runs shortly after startup so the cache is never empty for long.
+async fn disk_usage_loop(state: Arc<AppState>, every: Duration) {
+    let mut ticker = interval_at(Instant::now() + Duration::from_secs(60), every);
+
+    loop {
+        ticker.tick().await;
+
+        let repos = match state.db.call(|db| db.list_repositories()).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::error!("Disk usage scheduler failed to list repositories: {}", e);
+                continue;
+            }
+        };
+
+        for repo in repos {
+            // Still cloning, or the clone failed
+            if repo.last_synced == 0 || repo.error.is_some() {
+                continue;
+            }
+            if let Err(e) = do_disk_usage(Arc::clone(&state), &repo.id).await {
+                tracing::warn!("Disk usage scan for {} failed: {}", repo.name, e);
+            }
+        }
+    }
+}
+
+/// Purge repositories that have been in the trash longer than `retention`
+async fn trash_loop(state: Arc<AppState>, retention: Duration) {
+    let mut ticker = interval_at(Instant::now() + Duration::from_secs(60), TICK);
+
+    loop {
+        ticker.tick().await;
+
+        let repos = match state.db.call(|db| db.list_trashed_repositories()).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::error!("Trash purge failed to list repositories: {}", e);
+                continue;
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        for repo in repos {
+            let trashed = TrashedRepository::new(&state.config.trash_dir, repo, Some(retention));
+            if !trashed.is_expired(now) {
+                continue;
+            }
+
+            let repo = trashed.repository;
+            tracing::info!("Purging expired trash: {}", repo.name);
+            if let Err(e) = run_operation(
+                &state,
+                OperationKind::PurgeRepository,
+                &repo.id,
+                Requester::Server,
+                do_purge_repository(&state, &repo),
+            )
+            .await
+            {
+                tracing::warn!("Purging {} from the trash failed: {}", repo.name, e);
+            }
+        }
+    }
+}
+
+/// Look for stale worktrees once a day and apply `GROVE_STALE_CLEANUP`
+/// On a dry run removable worktrees are only logged.
+async fn stale_loop(state: Arc<AppState>) {
+    let mut ticker = interval_at(Instant::now() + TICK, STALE_CHECK);
+    let dry_run = state.config.stale_cleanup == CleanupMode::DryRun;
+
+    loop {
+        ticker.tick().await;
+
+        let report =
+            match do_cleanup_stale(&state, state.config.stale_after, dry_run, Requester::Server).await {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::error!("Stale worktree check failed: {}", e);
+                    continue;
+                }
+            };
+
+        for path in &report.removed {
+            if dry_run {
+                tracing::info!("Stale worktree would be removed (dry run): {}", path);
+            } else {
+                tracing::info!("Removed stale worktree: {}", path);
+            }
+        }
+        for failure in &report.failed {
+            tracing::warn!("Stale worktree {} not removed: {}", failure.path, failure.error);
+        }
+    }
+}
@@ -3,15 +3,22 @@
 //! Serves the web dashboard and API endpoints.
 //! See README.md for endpoint documentation and diagrams.
 
+mod jobs;
 pub mod mcp;
 pub mod routes;
 mod static_files;
 
 use anyhow::Result;
 use axum::Router;
-use grove_core::{Config, Database, GitOps, StateManager};
-use std::sync::Arc;
+use grove_core::{Config, Database, GitOps, LogHub, PackageManagerRegistry, StateManager};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// How long finished operations stay in the history
+const OPERATION_RETENTION: Duration = Duration::from_secs(90 * 24 * 60 * 60);
 
 /// Shared application state
 pub struct AppState {
@@ -19,6 +26,16 @@ pub struct AppState {
     pub state: Arc<StateManager>,
     pub git: Arc<GitOps>,
     pub db: Arc<Database>,
+    /// Persisted install logs and their live streams
+    pub logs: Arc<LogHub>,
+    /// Running installs by worktree path; send `true` to cancel
+    pub installs: Mutex<HashMap<String, watch::Sender<bool>>>,
+    /// Built-in and custom package manager detectors
+    pub package_managers: PackageManagerRegistry,
+    /// Repository IDs with maintenance in progress
+    pub maintenance_active: Mutex<HashSet<String>>,
+    /// Repository IDs with a disk usage scan in progress
+    pub disk_usage_active: Mutex<HashSet<String>>,
 }
 
 /// HTTP server wrapper
@@ -38,16 +55,34 @@ impl Server {
 
     /// Run the server on given port
     pub async fn run(self, port: u16) -> Result<()> {
+        // Operations still running belonged to a previous server
+        let retention = OPERATION_RETENTION.as_millis() as i64;
+        self.db
+            .call(move |db| db.close_stale_operations(retention))
+            .await?;
+
         let state_manager = StateManager::new(Arc::clone(&self.db));
         let git = Arc::new(GitOps::new());
 
+        let package_managers = PackageManagerRegistry::load(&self.config);
+
+        let logs = LogHub::new(self.config.logs_dir.clone());
+
         let app_state = AppState {
             config: self.config,
             state: state_manager,
             git,
             db: self.db,
+            logs,
+            installs: Mutex::new(HashMap::new()),
+            package_managers,
+            maintenance_active: Mutex::new(HashSet::new()),
+            disk_usage_active: Mutex::new(HashSet::new()),
         };
 
+        let app_state = Arc::new(app_state);
+        jobs::spawn(Arc::clone(&app_state));
+
         let router = router(app_state);
         let listener = TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
 
@@ -59,9 +94,7 @@ impl Server {
 }
 
 /// Build the API router
-pub fn router(state: AppState) -> Router {
-    let state = Arc::new(state);
-
+pub fn router(state: Arc<AppState>) -> Router {
     Router::new()
         // API routes
         .merge(routes::api_routes())
@@ -3,6 +3,10 @@
 //! Exposes grove operations as MCP tools for AI assistants.
 
 use crate::AppState;
+use grove_core::{
+    LabelUpdate, OperationFilter, OperationKind, OperationOutcome, ProgressPhase, ProgressUpdate, RepositoryFilter,
+    Requester, SetupStep, TrashedRepository, WorktreeConfig, WorktreeConfigUpdate, WorktreeNotes,
+};
 use rmcp::{
     handler::server::ServerHandler,
     model::*,
@@ -34,10 +38,23 @@ impl GroveMcp {
         vec![
             Tool {
                 name: "list_repositories".into(),
-                description: Some("List all tracked git repositories in grove".into()),
+                description: Some("List tracked git repositories in grove (favorites first), optionally filtered by tag, group or favorite".into()),
                 input_schema: schema(serde_json::json!({
                     "type": "object",
-                    "properties": {},
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Only repositories with this tag"
+                        },
+                        "group": {
+                            "type": "string",
+                            "description": "Only repositories in this group"
+                        },
+                        "favorites": {
+                            "type": "boolean",
+                            "description": "Only favorites (default: false)"
+                        }
+                    },
                     "required": []
                 })),
                 annotations: None,
@@ -46,6 +63,66 @@ impl GroveMcp {
                 output_schema: None,
                 title: None,
             },
+            Tool {
+                name: "label_repository".into(),
+                description: Some("Change a repository's tags, group or favorite flag".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "The repository ID"
+                        },
+                        "add_tags": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Tags to add"
+                        },
+                        "remove_tags": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Tags to remove"
+                        },
+                        "group": {
+                            "type": "string",
+                            "description": "Group to move the repository to (empty string removes it from its group)"
+                        },
+                        "favorite": {
+                            "type": "boolean",
+                            "description": "Pin (true) or unpin (false) the repository"
+                        }
+                    },
+                    "required": ["id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "search".into(),
+                description: Some("Find repositories and worktrees by name, URL, branch, commit subject, tag, group, note, issue URL or owner (fuzzy, best match first)".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Words to look for, e.g. \"login bug\""
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Most N results (default 20)"
+                        }
+                    },
+                    "required": ["query"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
             Tool {
                 name: "clone_repository".into(),
                 description: Some("Clone a git repository into grove".into()),
@@ -67,13 +144,72 @@ impl GroveMcp {
             },
             Tool {
                 name: "delete_repository".into(),
-                description: Some("Delete a repository and all its worktrees from grove".into()),
+                description: Some("Move a repository and all its worktrees to the trash (or delete it for good)".into()),
                 input_schema: schema(serde_json::json!({
                     "type": "object",
                     "properties": {
                         "id": {
                             "type": "string",
                             "description": "The repository ID to delete"
+                        },
+                        "force": {
+                            "type": "boolean",
+                            "description": "Delete even if a worktree is locked"
+                        },
+                        "purge": {
+                            "type": "boolean",
+                            "description": "Skip the trash and delete for good (default: false)"
+                        }
+                    },
+                    "required": ["id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "list_trash".into(),
+                description: Some("List repositories in the trash and when they are purged".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "restore_repository".into(),
+                description: Some("Restore a repository from the trash to its original location".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "The repository ID"
+                        }
+                    },
+                    "required": ["id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "purge_repository".into(),
+                description: Some("Delete a repository in the trash for good".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "The repository ID"
                         }
                     },
                     "required": ["id"]
@@ -116,6 +252,18 @@ impl GroveMcp {
                         "branch": {
                             "type": "string",
                             "description": "Branch name to checkout or create"
+                        },
+                        "note": {
+                            "type": "string",
+                            "description": "Why the worktree exists"
+                        },
+                        "issue_url": {
+                            "type": "string",
+                            "description": "Linked issue or ticket URL"
+                        },
+                        "owner": {
+                            "type": "string",
+                            "description": "Who is working in the worktree"
                         }
                     },
                     "required": ["repo_id", "branch"]
@@ -126,6 +274,108 @@ impl GroveMcp {
                 output_schema: None,
                 title: None,
             },
+            Tool {
+                name: "annotate_worktree".into(),
+                description: Some("Set a worktree's note, linked issue URL or owner".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The worktree path"
+                        },
+                        "note": {
+                            "type": "string",
+                            "description": "Why the worktree exists (empty string clears it)"
+                        },
+                        "issue_url": {
+                            "type": "string",
+                            "description": "Linked issue or ticket URL (empty string clears it)"
+                        },
+                        "owner": {
+                            "type": "string",
+                            "description": "Who is working in the worktree (empty string clears it)"
+                        }
+                    },
+                    "required": ["path"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "get_worktree_config".into(),
+                description: Some("Get a repository's file sharing patterns and upstream remote".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "repo_id": {
+                            "type": "string",
+                            "description": "The repository ID"
+                        }
+                    },
+                    "required": ["repo_id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "update_worktree_config".into(),
+                description: Some("Change which files new worktrees symlink or copy from .main, or the upstream remote".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "repo_id": {
+                            "type": "string",
+                            "description": "The repository ID"
+                        },
+                        "symlink_patterns": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Globs to symlink, e.g. [\".env\", \".claude/**\"] (replaces the list; [] clears it)"
+                        },
+                        "copy_patterns": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Globs to copy (replaces the list; [] clears it)"
+                        },
+                        "upstream_remote": {
+                            "type": "string",
+                            "description": "Remote new branches track (must exist in the repository)"
+                        }
+                    },
+                    "required": ["repo_id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "reapply_sharing".into(),
+                description: Some("Symlink/copy files from .main into every existing worktree again (missing files only)".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "repo_id": {
+                            "type": "string",
+                            "description": "The repository ID"
+                        }
+                    },
+                    "required": ["repo_id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
             Tool {
                 name: "delete_worktree".into(),
                 description: Some("Delete a worktree from a repository".into()),
@@ -139,9 +389,139 @@ impl GroveMcp {
                         "path": {
                             "type": "string",
                             "description": "The worktree path to delete"
+                        },
+                        "force": {
+                            "type": "boolean",
+                            "description": "Delete even if the worktree is locked"
+                        }
+                    },
+                    "required": ["repo_id", "path"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "move_worktree".into(),
+                description: Some("Move a worktree to a new directory and/or rename its branch".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The worktree path to move"
+                        },
+                        "branch": {
+                            "type": "string",
+                            "description": "New branch name (the directory follows unless name is given)"
+                        },
+                        "name": {
+                            "type": "string",
+                            "description": "New directory name within the repository"
+                        }
+                    },
+                    "required": ["path"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "lock_worktree".into(),
+                description: Some("Lock a worktree so it cannot be deleted, moved or pruned".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The worktree path to lock"
+                        },
+                        "reason": {
+                            "type": "string",
+                            "description": "Why the worktree is locked"
+                        }
+                    },
+                    "required": ["path"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "unlock_worktree".into(),
+                description: Some("Unlock a previously locked worktree".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The worktree path to unlock"
+                        }
+                    },
+                    "required": ["path"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "cancel_install".into(),
+                description: Some("Cancel the dependency install running in a worktree".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The worktree path"
+                        }
+                    },
+                    "required": ["path"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "retry_clone".into(),
+                description: Some("Retry a failed clone from the step that failed".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "repo_id": {
+                            "type": "string",
+                            "description": "The repository ID"
+                        }
+                    },
+                    "required": ["repo_id"]
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
+            Tool {
+                name: "retry_worktree".into(),
+                description: Some("Retry creating a failed worktree from the step that failed".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The worktree path"
                         }
                     },
-                    "required": ["repo_id", "path"]
+                    "required": ["path"]
                 })),
                 annotations: None,
                 icons: None,
@@ -168,6 +548,36 @@ impl GroveMcp {
                 output_schema: None,
                 title: None,
             },
+            Tool {
+                name: "list_operations".into(),
+                description: Some("List recent operations (clones, worktree changes, refreshes, ...) with their outcome and error".into()),
+                input_schema: schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "kind": {
+                            "type": "string",
+                            "description": "Only this kind (clone, delete_repository, create_worktree, delete_worktree, move_worktree, lock_worktree, unlock_worktree, refresh, maintenance)"
+                        },
+                        "target": {
+                            "type": "string",
+                            "description": "Only operations on this repository ID or worktree path"
+                        },
+                        "failed_only": {
+                            "type": "boolean",
+                            "description": "Only failed operations (default: false)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Most recent N (default: 50)"
+                        }
+                    }
+                })),
+                annotations: None,
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: None,
+            },
         ]
     }
 
@@ -182,14 +592,45 @@ impl GroveMcp {
 
     async fn handle_tool(&self, name: &str, args: serde_json::Value) -> CallToolResult {
         match name {
-            "list_repositories" => self.list_repositories().await,
+            "list_repositories" => {
+                match serde_json::from_value(args) {
+                    Ok(filter) => self.list_repositories(&filter).await,
+                    Err(e) => Self::text_result(format!("Invalid arguments: {}", e), true),
+                }
+            }
+            "label_repository" => {
+                let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                match serde_json::from_value(args) {
+                    Ok(update) => self.label_repository(&id, update).await,
+                    Err(e) => Self::text_result(format!("Invalid arguments: {}", e), true),
+                }
+            }
+            "search" => {
+                let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(grove_core::search::DEFAULT_LIMIT, |n| n as usize);
+                self.search(query, limit).await
+            }
             "clone_repository" => {
                 let url = args.get("url").and_then(|v| v.as_str()).unwrap_or("");
                 self.clone_repository(url).await
             }
             "delete_repository" => {
                 let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                self.delete_repository(id).await
+                let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+                let purge = args.get("purge").and_then(|v| v.as_bool()).unwrap_or(false);
+                self.delete_repository(id, force, purge).await
+            }
+            "list_trash" => self.list_trash().await,
+            "restore_repository" => {
+                let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                self.restore_repository(id).await
+            }
+            "purge_repository" => {
+                let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                self.purge_repository(id).await
             }
             "list_worktrees" => {
                 let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
@@ -198,17 +639,71 @@ impl GroveMcp {
             "create_worktree" => {
                 let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
                 let branch = args.get("branch").and_then(|v| v.as_str()).unwrap_or("");
-                self.create_worktree(repo_id, branch).await
+                match serde_json::from_value(args.clone()) {
+                    Ok(notes) => self.create_worktree(repo_id, branch, notes).await,
+                    Err(e) => Self::text_result(format!("Invalid arguments: {}", e), true),
+                }
+            }
+            "annotate_worktree" => {
+                let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                match serde_json::from_value(args) {
+                    Ok(notes) => self.annotate_worktree(&path, notes).await,
+                    Err(e) => Self::text_result(format!("Invalid arguments: {}", e), true),
+                }
+            }
+            "get_worktree_config" => {
+                let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
+                self.get_worktree_config(repo_id).await
+            }
+            "update_worktree_config" => {
+                let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                match serde_json::from_value(args) {
+                    Ok(update) => self.update_worktree_config(&repo_id, update).await,
+                    Err(e) => Self::text_result(format!("Invalid arguments: {}", e), true),
+                }
+            }
+            "reapply_sharing" => {
+                let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
+                self.reapply_sharing(repo_id).await
             }
             "delete_worktree" => {
                 let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
                 let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
-                self.delete_worktree(repo_id, path).await
+                let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+                self.delete_worktree(repo_id, path, force).await
+            }
+            "lock_worktree" => {
+                let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+                let reason = args.get("reason").and_then(|v| v.as_str());
+                self.lock_worktree(path, reason).await
+            }
+            "unlock_worktree" => {
+                let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+                self.unlock_worktree(path).await
+            }
+            "cancel_install" => {
+                let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+                self.cancel_install(path)
+            }
+            "retry_clone" => {
+                let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
+                self.retry_clone(repo_id).await
+            }
+            "retry_worktree" => {
+                let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+                self.retry_worktree(path).await
+            }
+            "move_worktree" => {
+                let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+                let branch = args.get("branch").and_then(|v| v.as_str());
+                let name = args.get("name").and_then(|v| v.as_str());
+                self.move_worktree(path, name, branch).await
             }
             "refresh_worktrees" => {
                 let repo_id = args.get("repo_id").and_then(|v| v.as_str()).unwrap_or("");
                 self.refresh_worktrees(repo_id).await
             }
+            "list_operations" => self.list_operations(&args).await,
             _ => Self::text_result(format!("Unknown tool: {}", name), true),
         }
     }
@@ -217,9 +712,10 @@ impl GroveMcp {
     // Tool Implementations
     // ─────────────────────────────────────────────────────────────
 
-    async fn list_repositories(&self) -> CallToolResult {
-        match self.state.db.list_repositories() {
-            Ok(repos) => {
+    async fn list_repositories(&self, filter: &RepositoryFilter) -> CallToolResult {
+        match self.state.db.call(|db| db.list_repositories()).await {
+            Ok(mut repos) => {
+                repos.retain(|repo| filter.matches(repo));
                 let text = serde_json::to_string_pretty(&repos).unwrap_or_else(|_| "[]".to_string());
                 Self::text_result(text, false)
             }
@@ -235,11 +731,14 @@ impl GroveMcp {
         };
 
         // Check if already exists
-        if let Ok(Some(existing)) = self.state.db.get_repository_by_name(
-            &parsed.provider,
-            &parsed.username,
-            &parsed.name,
-        ) {
+        let existing = {
+            let parsed = parsed.clone();
+            self.state
+                .db
+                .call(move |db| db.get_repository_by_name(&parsed.provider, &parsed.username, &parsed.name))
+                .await
+        };
+        if let Ok(Some(existing)) = existing {
             return Self::text_result(
                 format!(
                     "Repository {}/{} already exists at {}",
@@ -257,8 +756,19 @@ impl GroveMcp {
             .join(&parsed.username)
             .join(&parsed.name);
 
+        if let Some(message) = crate::routes::trashed_conflict(
+            &self.state,
+            &parsed.provider,
+            &parsed.username,
+            &parsed.name,
+            &local_path,
+        )
+        .await {
+            return Self::text_result(message, true);
+        }
+
         // Insert immediately
-        let repo_id = match self.state.db.insert_repository(&grove_core::NewRepository {
+        let new_repo = grove_core::NewRepository {
             provider: parsed.provider.clone(),
             username: parsed.username.clone(),
             name: parsed.name.clone(),
@@ -267,39 +777,195 @@ impl GroveMcp {
             repo_type: "bare".to_string(),
             default_branch: "main".to_string(),
             last_synced: 0,
-        }) {
+        };
+        let repo_id = match self.state.db.call(move |db| db.insert_repository(&new_repo)).await {
             Ok(id) => id,
             Err(e) => return Self::text_result(format!("Failed to create repository record: {}", e), true),
         };
 
-        self.state.state.set_progress(&repo_id, Some("Cloning repository..."));
+        self.state
+            .state
+            .set_progress(&repo_id, ProgressUpdate::new(ProgressPhase::Cloning, "Cloning repository..."));
         self.state.state.on_db_change();
 
         // Spawn clone task
         let state = Arc::clone(&self.state);
         let url = url.to_string();
         let repo_id_clone = repo_id.clone();
-        tokio::spawn(async move {
-            if let Err(e) = crate::routes::do_clone(state.clone(), &url, &repo_id_clone, false).await {
-                tracing::error!("MCP clone failed: {}", e);
+        let operation_id = crate::routes::spawn_operation(
+            Arc::clone(&self.state),
+            OperationKind::Clone,
+            repo_id.clone(),
+            Requester::Mcp,
+            async move { crate::routes::do_clone(state, &url, &repo_id_clone, false, SetupStep::Prepare).await },
+        )
+        .await;
+
+        Self::text_result(
+            format!(
+                "Clone started. Repository ID: {} (operation {})",
+                repo_id, operation_id
+            ),
+            false,
+        )
+    }
+
+    async fn delete_repository(&self, id: &str, force: bool, purge: bool) -> CallToolResult {
+        let repo = match self.repository(id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return Self::text_result("Repository not found", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        };
+
+        if !force
+            && let Some((path, reason)) = self
+                .state
+                .git
+                .worktree_locks(std::path::Path::new(&repo.local_path))
+                .into_iter()
+                .next()
+        {
+            return Self::text_result(crate::routes::lock_message(&path, reason.as_deref()), true);
+        }
+
+        let result = if purge {
+            crate::routes::run_operation(
+                &self.state,
+                OperationKind::PurgeRepository,
+                id,
+                Requester::Mcp,
+                crate::routes::do_purge_repository(&self.state, &repo),
+            )
+            .await
+        } else {
+            crate::routes::run_operation(
+                &self.state,
+                OperationKind::DeleteRepository,
+                id,
+                Requester::Mcp,
+                crate::routes::do_trash_repository(&self.state, &repo),
+            )
+            .await
+        };
+        self.state.state.on_db_change();
+
+        match result {
+            Ok(()) if purge => Self::text_result(format!("Repository {} deleted", id), false),
+            Ok(()) => Self::text_result(format!("Repository {} moved to the trash", id), false),
+            Err(e) => Self::text_result(format!("Failed to delete repository: {}", e), true),
+        }
+    }
+
+    async fn list_trash(&self) -> CallToolResult {
+        match self.state.db.call(|db| db.list_trashed_repositories()).await {
+            Ok(repos) => {
+                let trashed: Vec<TrashedRepository> = repos
+                    .into_iter()
+                    .map(|repo| {
+                        TrashedRepository::new(
+                            &self.state.config.trash_dir,
+                            repo,
+                            self.state.config.trash_retention,
+                        )
+                    })
+                    .collect();
+                let text = serde_json::to_string_pretty(&trashed).unwrap_or_else(|_| "[]".to_string());
+                Self::text_result(text, false)
             }
-        });
+            Err(e) => Self::text_result(format!("Failed to list trash: {}", e), true),
+        }
+    }
+
+    async fn restore_repository(&self, id: &str) -> CallToolResult {
+        let repo = match self.trashed_repository(id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return Self::text_result("Repository not in the trash", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        };
 
-        Self::text_result(format!("Clone started. Repository ID: {}", repo_id), false)
+        match crate::routes::run_operation(
+            &self.state,
+            OperationKind::RestoreRepository,
+            id,
+            Requester::Mcp,
+            crate::routes::do_restore_repository(&self.state, &repo),
+        )
+        .await
+        {
+            Ok(()) => Self::text_result(format!("Restored repository to {}", repo.local_path), false),
+            Err(e) => Self::text_result(format!("Failed to restore repository: {}", e), true),
+        }
     }
 
-    async fn delete_repository(&self, id: &str) -> CallToolResult {
-        match self.state.db.delete_repository(id) {
-            Ok(_) => {
-                self.state.state.on_db_change();
-                Self::text_result(format!("Repository {} deleted", id), false)
+    async fn purge_repository(&self, id: &str) -> CallToolResult {
+        let repo = match self.trashed_repository(id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return Self::text_result("Repository not in the trash", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        };
+
+        match crate::routes::run_operation(
+            &self.state,
+            OperationKind::PurgeRepository,
+            id,
+            Requester::Mcp,
+            crate::routes::do_purge_repository(&self.state, &repo),
+        )
+        .await
+        {
+            Ok(()) => Self::text_result(format!("Repository {} deleted", id), false),
+            Err(e) => Self::text_result(format!("Failed to purge repository: {}", e), true),
+        }
+    }
+
+    async fn label_repository(&self, id: &str, update: LabelUpdate) -> CallToolResult {
+        match self.repository(id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return Self::text_result("Repository not found", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        }
+
+        let update = match update.normalize() {
+            Ok(update) => update,
+            Err(e) => return Self::text_result(e.to_string(), true),
+        };
+        let repo_id = id.to_string();
+        if let Err(e) = self
+            .state
+            .db
+            .call(move |db| db.update_repository_labels(&repo_id, &update))
+            .await
+        {
+            return Self::text_result(format!("Failed to update repository: {}", e), true);
+        }
+        self.state.state.on_db_change();
+
+        match self.repository(id).await {
+            Ok(Some(repo)) => {
+                let text = serde_json::to_string_pretty(&repo).unwrap_or_default();
+                Self::text_result(text, false)
             }
-            Err(e) => Self::text_result(format!("Failed to delete repository: {}", e), true),
+            Ok(None) => Self::text_result("Repository not found", true),
+            Err(e) => Self::text_result(format!("Failed to get repository: {}", e), true),
+        }
+    }
+
+    async fn search(&self, query: String, limit: usize) -> CallToolResult {
+        if query.trim().is_empty() {
+            return Self::text_result("query is required", true);
+        }
+        match self.state.db.call(move |db| db.search(&query, limit)).await {
+            Ok(hits) => {
+                let text = serde_json::to_string_pretty(&hits).unwrap_or_else(|_| "[]".to_string());
+                Self::text_result(text, false)
+            }
+            Err(e) => Self::text_result(format!("Search failed: {}", e), true),
         }
     }
 
     async fn list_worktrees(&self, repo_id: &str) -> CallToolResult {
-        match self.state.db.list_worktrees(repo_id) {
+        let repo_id = repo_id.to_string();
+        match self.state.db.call(move |db| db.list_worktrees(&repo_id)).await {
             Ok(worktrees) => {
                 let text = serde_json::to_string_pretty(&worktrees).unwrap_or_else(|_| "[]".to_string());
                 Self::text_result(text, false)
@@ -308,9 +974,9 @@ impl GroveMcp {
         }
     }
 
-    async fn create_worktree(&self, repo_id: &str, branch: &str) -> CallToolResult {
+    async fn create_worktree(&self, repo_id: &str, branch: &str, notes: WorktreeNotes) -> CallToolResult {
         // Get repository
-        let repo = match self.state.db.get_repository(repo_id) {
+        let repo = match self.repository(repo_id).await {
             Ok(Some(r)) => r,
             Ok(None) => return Self::text_result("Repository not found", true),
             Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
@@ -321,14 +987,38 @@ impl GroveMcp {
         let worktree_path = local_path.join(&worktree_name);
         let worktree_display = worktree_path.display().to_string();
 
+        let notes = match notes.normalize() {
+            Ok(notes) => notes,
+            Err(e) => return Self::text_result(e.to_string(), true),
+        };
+
         // Insert worktree record
-        if let Err(e) = self.state.db.insert_worktree(&grove_core::NewWorktree {
+        let worktree = grove_core::NewWorktree {
             path: worktree_path.to_string_lossy().to_string(),
             repo_id: repo_id.to_string(),
             branch: branch.to_string(),
             status: grove_core::WorktreeStatus::Creating,
-        }) {
-            return Self::text_result(format!("Failed to create worktree record: {}", e), true);
+        };
+        let inserted = self
+            .state
+            .db
+            .call(move |db| {
+                db.insert_worktree(&worktree)?;
+                Ok(worktree)
+            })
+            .await;
+        let worktree = match inserted {
+            Ok(worktree) => worktree,
+            Err(e) => return Self::text_result(format!("Failed to create worktree record: {}", e), true),
+        };
+        if !notes.is_empty()
+            && let Err(e) = self
+                .state
+                .db
+                .call(move |db| db.update_worktree_notes(&worktree.path, &notes))
+                .await
+        {
+            return Self::text_result(format!("Failed to save worktree notes: {}", e), true);
         }
         self.state.state.on_db_change();
 
@@ -336,122 +1026,386 @@ impl GroveMcp {
         let state = Arc::clone(&self.state);
         let branch = branch.to_string();
         let repo_id = repo_id.to_string();
-        let main_path = local_path.join(".main");
-        let worktree_path_str = worktree_path.to_string_lossy().to_string();
-
-        tokio::spawn(async move {
-            let result = crate::routes::do_create_worktree(
-                state.clone(),
-                &local_path,
-                &main_path,
-                &worktree_path,
-                &branch,
-                &repo_id,
-                false,
-            )
-            .await;
 
-            if let Err(e) = result {
-                tracing::error!("MCP create worktree failed: {}", e);
-                let _ = state.db.update_worktree_status(
-                    &worktree_path_str,
-                    grove_core::WorktreeStatus::Error,
-                    None,
-                    None,
-                );
-                state.state.on_db_change();
-            }
-        });
+        crate::routes::spawn_operation(
+            Arc::clone(&self.state),
+            OperationKind::CreateWorktree,
+            worktree_path.to_string_lossy().to_string(),
+            Requester::Mcp,
+            async move {
+                crate::routes::do_create_worktree(
+                    state,
+                    &local_path,
+                    &worktree_path,
+                    &branch,
+                    &repo_id,
+                    false,
+                    SetupStep::CreateWorktree,
+                )
+                .await
+            },
+        )
+        .await;
 
         Self::text_result(format!("Creating worktree at {}", worktree_display), false)
     }
 
-    async fn delete_worktree(&self, _repo_id: &str, path: &str) -> CallToolResult {
-        // Get worktree
-        let worktree = match self.state.db.get_worktree(path) {
-            Ok(Some(w)) => w,
+    async fn annotate_worktree(&self, path: &str, notes: WorktreeNotes) -> CallToolResult {
+        match self.worktree(path).await {
+            Ok(Some(_)) => {}
             Ok(None) => return Self::text_result("Worktree not found", true),
             Err(e) => return Self::text_result(format!("Failed to get worktree: {}", e), true),
+        }
+
+        let notes = match notes.normalize() {
+            Ok(notes) => notes,
+            Err(e) => return Self::text_result(e.to_string(), true),
         };
+        let wt_path = path.to_string();
+        if let Err(e) = self
+            .state
+            .db
+            .call(move |db| db.update_worktree_notes(&wt_path, &notes))
+            .await
+        {
+            return Self::text_result(format!("Failed to update worktree: {}", e), true);
+        }
+        self.state.state.on_db_change();
 
-        // Get repository
-        let repo = match self.state.db.get_repository(&worktree.repo_id) {
+        match self.worktree(path).await {
+            Ok(Some(worktree)) => {
+                let text = serde_json::to_string_pretty(&worktree).unwrap_or_default();
+                Self::text_result(text, false)
+            }
+            Ok(None) => Self::text_result("Worktree not found", true),
+            Err(e) => Self::text_result(format!("Failed to get worktree: {}", e), true),
+        }
+    }
+
+    async fn get_worktree_config(&self, repo_id: &str) -> CallToolResult {
+        match self.repository(repo_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return Self::text_result("Repository not found", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        }
+
+        let id = repo_id.to_string();
+        match self.state.db.call(move |db| db.get_worktree_config(&id)).await {
+            Ok(config) => {
+                let config: crate::routes::WorktreeConfigResponse = config
+                    .unwrap_or_else(|| WorktreeConfig::defaults(repo_id))
+                    .into();
+                let text = serde_json::to_string_pretty(&config).unwrap_or_default();
+                Self::text_result(text, false)
+            }
+            Err(e) => Self::text_result(format!("Failed to get worktree config: {}", e), true),
+        }
+    }
+
+    async fn update_worktree_config(&self, repo_id: &str, update: WorktreeConfigUpdate) -> CallToolResult {
+        let repo = match self.repository(repo_id).await {
             Ok(Some(r)) => r,
             Ok(None) => return Self::text_result("Repository not found", true),
             Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
         };
 
-        // Update status
-        let _ = self.state.db.update_worktree_status(
-            path,
-            grove_core::WorktreeStatus::Deleting,
-            worktree.head.as_deref(),
-            worktree.commit_message.as_deref(),
-        );
-        self.state.state.on_db_change();
+        let update = match update.normalize() {
+            Ok(update) => update,
+            Err(e) => return Self::text_result(e.to_string(), true),
+        };
+        if let Some(message) = crate::routes::unknown_remote(&self.state, &repo, &update) {
+            return Self::text_result(message, true);
+        }
 
-        // Spawn delete task
-        let state = Arc::clone(&self.state);
-        let path_owned = path.to_string();
-        let local_path = std::path::PathBuf::from(&repo.local_path);
-        let worktree_path = std::path::PathBuf::from(path);
+        match crate::routes::save_worktree_config(&self.state, repo_id, update).await {
+            Ok(config) => {
+                let text = serde_json::to_string_pretty(&config).unwrap_or_default();
+                Self::text_result(text, false)
+            }
+            Err(e) => Self::text_result(format!("Failed to update worktree config: {}", e), true),
+        }
+    }
+
+    async fn reapply_sharing(&self, repo_id: &str) -> CallToolResult {
+        let repo = match self.repository(repo_id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return Self::text_result("Repository not found", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        };
 
-        tokio::spawn(async move {
-            let _ = state.git.remove_worktree(&local_path, &worktree_path).await;
-            if worktree_path.exists() {
-                let _ = tokio::fs::remove_dir_all(&worktree_path).await;
+        match crate::routes::do_reapply_sharing(&self.state, &repo, Requester::Mcp).await {
+            Ok(results) => {
+                let failed = results.iter().any(|r| r.error.is_some());
+                let text = serde_json::to_string_pretty(&results).unwrap_or_default();
+                Self::text_result(text, failed)
             }
-            let _ = state.db.delete_worktree(&path_owned);
-            state.state.on_db_change();
-        });
+            Err(e) => Self::text_result(format!("Failed to share files: {}", e), true),
+        }
+    }
+
+    async fn delete_worktree(&self, _repo_id: &str, path: &str, force: bool) -> CallToolResult {
+        let (repo, worktree) = match self.worktree_with_repo(path).await {
+            Ok(found) => found,
+            Err(result) => return result,
+        };
+
+        let removal =
+            match crate::routes::begin_delete(&self.state, repo, worktree, force).await {
+                Ok(removal) => removal,
+                Err(e) => return Self::text_result(e.to_string(), true),
+            };
+        crate::routes::spawn_operation(
+            Arc::clone(&self.state),
+            OperationKind::DeleteWorktree,
+            path.to_string(),
+            Requester::Mcp,
+            removal,
+        )
+        .await;
 
         Self::text_result(format!("Deleting worktree: {}", path), false)
     }
 
+    async fn move_worktree(
+        &self,
+        path: &str,
+        name: Option<&str>,
+        branch: Option<&str>,
+    ) -> CallToolResult {
+        if name.is_none() && branch.is_none() {
+            return Self::text_result("Provide a new name or branch", true);
+        }
+
+        let (repo, worktree) = match self.worktree_with_repo(path).await {
+            Ok(found) => found,
+            Err(result) => return result,
+        };
+
+        if worktree.status != grove_core::WorktreeStatus::Ready {
+            return Self::text_result(format!("Worktree is {}", worktree.status.as_str()), true);
+        }
+
+        let (new_path, new_branch) =
+            match crate::routes::resolve_move_target(&repo, &worktree, name, branch) {
+                Ok(target) => target,
+                Err(e) => return Self::text_result(e, true),
+            };
+
+        match crate::routes::run_operation(
+            &self.state,
+            OperationKind::MoveWorktree,
+            path,
+            Requester::Mcp,
+            crate::routes::do_move_worktree(
+                Arc::clone(&self.state),
+                &repo,
+                &worktree,
+                &new_path,
+                &new_branch,
+            ),
+        )
+        .await
+        {
+            Ok(()) => Self::text_result(
+                format!("Moved worktree to {} ({})", new_path.display(), new_branch),
+                false,
+            ),
+            Err(e) => Self::text_result(format!("Failed to move worktree: {}", e), true),
+        }
+    }
+
+    async fn lock_worktree(&self, path: &str, reason: Option<&str>) -> CallToolResult {
+        let (repo, _) = match self.worktree_with_repo(path).await {
+            Ok(found) => found,
+            Err(result) => return result,
+        };
+
+        let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+        match crate::routes::run_operation(
+            &self.state,
+            OperationKind::LockWorktree,
+            path,
+            Requester::Mcp,
+            self.state.git.lock_worktree(
+                std::path::Path::new(&repo.local_path),
+                std::path::Path::new(path),
+                reason,
+            ),
+        )
+        .await
+        {
+            Ok(()) => {
+                self.state.state.on_db_change();
+                Self::text_result(format!("Locked worktree: {}", path), false)
+            }
+            Err(e) => Self::text_result(format!("Failed to lock worktree: {}", e), true),
+        }
+    }
+
+    async fn unlock_worktree(&self, path: &str) -> CallToolResult {
+        let (repo, _) = match self.worktree_with_repo(path).await {
+            Ok(found) => found,
+            Err(result) => return result,
+        };
+
+        match crate::routes::run_operation(
+            &self.state,
+            OperationKind::UnlockWorktree,
+            path,
+            Requester::Mcp,
+            self.state.git.unlock_worktree(
+                std::path::Path::new(&repo.local_path),
+                std::path::Path::new(path),
+            ),
+        )
+        .await
+        {
+            Ok(()) => {
+                self.state.state.on_db_change();
+                Self::text_result(format!("Unlocked worktree: {}", path), false)
+            }
+            Err(e) => Self::text_result(format!("Failed to unlock worktree: {}", e), true),
+        }
+    }
+
+    fn cancel_install(&self, path: &str) -> CallToolResult {
+        if crate::routes::cancel_install(&self.state, path) {
+            Self::text_result(format!("Cancelling install in {}", path), false)
+        } else {
+            Self::text_result(format!("No install running in {}", path), true)
+        }
+    }
+
+    async fn retry_clone(&self, repo_id: &str) -> CallToolResult {
+        let repo = match self.repository(repo_id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return Self::text_result("Repository not found", true),
+            Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
+        };
+        let Some(error) = repo.error.clone() else {
+            return Self::text_result(
+                format!("{}/{} has no failed clone to retry", repo.username, repo.name),
+                true,
+            );
+        };
+
+        match crate::routes::spawn_clone_retry(&self.state, &repo, &error, Requester::Mcp).await {
+            Ok(operation_id) => Self::text_result(
+                format!(
+                    "Retrying clone from {} (operation {})",
+                    error.step.as_str(),
+                    operation_id
+                ),
+                false,
+            ),
+            Err(e) => Self::text_result(format!("Failed to retry clone: {}", e), true),
+        }
+    }
+
+    async fn retry_worktree(&self, path: &str) -> CallToolResult {
+        let (repo, worktree) = match self.worktree_with_repo(path).await {
+            Ok(found) => found,
+            Err(result) => return result,
+        };
+        if repo.error.is_some() {
+            return Self::text_result(
+                format!("Cloning {}/{} failed; retry the clone instead", repo.username, repo.name),
+                true,
+            );
+        }
+        let Some((step, skip_install)) = crate::routes::worktree_retry(&worktree) else {
+            return Self::text_result(
+                format!("Worktree {} is {}, not failed", path, worktree.status.as_str()),
+                true,
+            );
+        };
+
+        match crate::routes::spawn_worktree_retry(&self.state, &repo, &worktree, step, skip_install, Requester::Mcp).await {
+            Ok(operation_id) => Self::text_result(
+                format!("Retrying {} from {} (operation {})", path, step.as_str(), operation_id),
+                false,
+            ),
+            Err(e) => Self::text_result(format!("Failed to retry worktree: {}", e), true),
+        }
+    }
+
+    async fn repository(&self, id: &str) -> anyhow::Result<Option<grove_core::Repository>> {
+        let id = id.to_string();
+        self.state.db.call(move |db| db.get_repository(&id)).await
+    }
+
+    async fn trashed_repository(&self, id: &str) -> anyhow::Result<Option<grove_core::Repository>> {
+        let id = id.to_string();
+        self.state.db.call(move |db| db.get_trashed_repository(&id)).await
+    }
+
+    async fn worktree(&self, path: &str) -> anyhow::Result<Option<grove_core::Worktree>> {
+        let path = path.to_string();
+        self.state.db.call(move |db| db.get_worktree(&path)).await
+    }
+
+    /// Look up a worktree and its repository, or the error result to return
+    async fn worktree_with_repo(
+        &self,
+        path: &str,
+    ) -> Result<(grove_core::Repository, grove_core::Worktree), CallToolResult> {
+        let worktree = match self.worktree(path).await {
+            Ok(Some(w)) => w,
+            Ok(None) => return Err(Self::text_result("Worktree not found", true)),
+            Err(e) => return Err(Self::text_result(format!("Failed to get worktree: {}", e), true)),
+        };
+
+        match self.repository(&worktree.repo_id).await {
+            Ok(Some(r)) => Ok((r, worktree)),
+            Ok(None) => Err(Self::text_result("Repository not found", true)),
+            Err(e) => Err(Self::text_result(format!("Failed to get repository: {}", e), true)),
+        }
+    }
+
     async fn refresh_worktrees(&self, repo_id: &str) -> CallToolResult {
-        let repo = match self.state.db.get_repository(repo_id) {
+        let repo = match self.repository(repo_id).await {
             Ok(Some(r)) => r,
             Ok(None) => return Self::text_result("Repository not found", true),
             Err(e) => return Self::text_result(format!("Failed to get repository: {}", e), true),
         };
 
         let state = Arc::clone(&self.state);
-        let repo_id = repo_id.to_string();
-        let local_path = std::path::PathBuf::from(&repo.local_path);
+        crate::routes::spawn_operation(
+            Arc::clone(&self.state),
+            OperationKind::Refresh,
+            repo_id.to_string(),
+            Requester::Mcp,
+            async move { crate::routes::do_refresh(state, &repo).await },
+        )
+        .await;
 
-        tokio::spawn(async move {
-            state.state.set_progress(&repo_id, Some("Fetching..."));
-
-            if let Err(e) = state.git.fetch(&local_path, "origin").await {
-                tracing::error!("Fetch failed: {}", e);
-            }
-
-            if let Ok(worktrees) = state.db.list_worktrees(&repo_id) {
-                for wt in worktrees {
-                    let wt_path = std::path::PathBuf::from(&wt.path);
-                    if let Ok(status) = state.git.get_status(&wt_path) {
-                        let _ = state.db.update_worktree_status(
-                            &wt.path,
-                            grove_core::WorktreeStatus::Ready,
-                            status.head.as_deref(),
-                            status.commit_message.as_deref(),
-                        );
-                        let _ = state.db.update_worktree_git_status(
-                            &wt.path,
-                            status.dirty,
-                            status.ahead,
-                            status.behind,
-                        );
-                    }
-                }
-            }
+        Self::text_result("Refresh started", false)
+    }
 
-            let _ = state.db.update_repository_synced(&repo_id);
-            state.state.set_progress(&repo_id, None);
-            state.state.on_db_change();
-        });
+    async fn list_operations(&self, args: &serde_json::Value) -> CallToolResult {
+        let mut filter = OperationFilter {
+            target: args.get("target").and_then(|v| v.as_str()).map(str::to_string),
+            limit: args.get("limit").and_then(|v| v.as_u64()).map(|n| n as usize),
+            ..Default::default()
+        };
+        if let Some(kind) = args.get("kind").and_then(|v| v.as_str()) {
+            match kind.parse() {
+                Ok(kind) => filter.kind = Some(kind),
+                Err(e) => return Self::text_result(e.to_string(), true),
+            }
+        }
+        if args.get("failed_only").and_then(|v| v.as_bool()).unwrap_or(false) {
+            filter.outcome = Some(OperationOutcome::Failed);
+        }
 
-        Self::text_result("Refresh started", false)
+        match self.state.db.call(move |db| db.list_operations(&filter)).await {
+            Ok(operations) => {
+                let text =
+                    serde_json::to_string_pretty(&operations).unwrap_or_else(|_| "[]".to_string());
+                Self::text_result(text, false)
+            }
+            Err(e) => Self::text_result(format!("Failed to list operations: {}", e), true),
+        }
     }
 }
 
@@ -497,10 +1451,8 @@ impl ServerHandler for GroveMcp {
         request: CallToolRequestParam,
         _context: RequestContext<rmcp::service::RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let args = request
-            .arguments
-            .map(serde_json::Value::Object)
-            .unwrap_or(serde_json::Value::Null);
+        // No arguments parse like `{}`, so tools with all-optional arguments still run
+        let args = serde_json::Value::Object(request.arguments.unwrap_or_default());
         Ok(self.handle_tool(&request.name, args).await)
     }
 }
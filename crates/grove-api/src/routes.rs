@@ -6,22 +6,69 @@ use crate::mcp::GroveMcp;
 use crate::AppState;
 use axum::{
     body::Body,
-    extract::State,
+    extract::{Query, State},
     response::{IntoResponse, sse::{Event, KeepAlive, Sse}},
     routing::{any, delete, get, post},
     Json, Router,
 };
 use futures::stream::Stream;
 use grove_core::{
-    detect_package_managers, run_install, share_files, NewRepository, NewWorktree, WorktreeConfig,
+    disk,
+    hooks::{self, HookEnv},
+    install::{self, run_install},
+    logs::LogWriter,
+    maintenance,
+    operations::REQUESTER_HEADER,
+    process::RunError,
+    search::{self, SearchHit, SearchQuery},
+    share_files,
+    stale::{self, CleanupFailure, CleanupReport, StaleWorktree},
+    toolchain,
+    trash,
+    CargoStep,
+    Database,
+    DepsState,
+    EventFilter,
+    Hook,
+    HookEvent,
+    InstallLog,
+    InstallStatus,
+    InstallTarget,
+    LabelCounts,
+    LabelUpdate,
+    MaintenanceRun,
+    MaintenanceTask,
+    NewRepository,
+    NewWorktree,
+    Operation,
+    OperationFilter,
+    OperationKind,
+    PackageManager,
+    ProgressPhase,
+    ProgressUpdate,
+    Repository,
+    RepositoryFilter,
+    Requester,
+    SequencedEvent,
+    SetupError,
+    SetupStep,
+    Snapshot,
+    TrashedRepository,
+    Worktree,
+    WorktreeConfig,
+    WorktreeConfigUpdate,
+    WorktreeNotes,
     WorktreeStatus,
 };
 use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
 use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::Infallible;
-use std::path::PathBuf;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::watch;
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt;
 use tower_service::Service;
@@ -31,18 +78,61 @@ pub fn api_routes() -> Router<Arc<AppState>> {
     Router::new()
         // SSE state stream
         .route("/api/state", get(sse_handler))
+        .route("/api/events", get(events_handler))
         // State snapshot (non-SSE)
         .route("/api/state/snapshot", get(state_snapshot))
         // Repositories
         .route("/api/repositories", get(list_repositories))
         .route("/api/clone", post(clone_repository))
-        .route("/api/repositories/{id}", delete(delete_repository))
+        .route("/api/repositories/{id}", delete(delete_repository).patch(update_repository_labels))
+        .route("/api/repositories/{id}/retry", post(retry_clone))
+        .route("/api/labels", get(list_labels))
+        .route("/api/search", get(search))
+        // Trash
+        .route("/api/trash", get(list_trash).delete(empty_trash))
+        .route("/api/trash/{id}", delete(purge_repository))
+        .route("/api/trash/{id}/restore", post(restore_repository))
         // Worktrees
         .route("/api/worktree", post(create_worktree))
-        .route("/api/worktree/{*path}", delete(delete_worktree))
+        .route("/api/worktree/move", post(move_worktree))
+        .route("/api/worktree/lock", post(lock_worktree))
+        .route("/api/worktree/unlock", post(unlock_worktree))
+        .route("/api/worktree/cancel-install", post(cancel_worktree_install))
+        .route("/api/worktree/retry", post(retry_worktree))
+        .route("/api/worktree/{*path}", delete(delete_worktree).patch(update_worktree_notes))
         // Actions
         .route("/api/open", post(open_in_editor))
         .route("/api/refresh/{id}", post(refresh_repository))
+        // Maintenance
+        .route(
+            "/api/maintenance/{id}",
+            get(list_maintenance_runs).post(run_maintenance),
+        )
+        // Hooks
+        .route("/api/hooks/{id}", get(list_hooks).put(set_hook))
+        .route("/api/hooks/{id}/{event}", delete(delete_hook))
+        .route(
+            "/api/worktree-config/{id}",
+            get(get_worktree_config).patch(update_worktree_config),
+        )
+        .route("/api/worktree-config/{id}/share", post(reapply_sharing))
+        .route(
+            "/api/install-config/{id}",
+            get(get_install_config).put(set_install_config),
+        )
+        // Install logs
+        .route("/api/logs", get(list_install_logs))
+        .route("/api/logs/{id}", get(get_install_log))
+        .route("/api/logs/{id}/stream", get(stream_install_log))
+        // Disk usage
+        .route("/api/disk-usage", post(refresh_all_disk_usage))
+        .route("/api/disk-usage/{id}", post(refresh_disk_usage))
+        // Stale worktrees
+        .route("/api/stale", get(list_stale_worktrees))
+        .route("/api/stale/cleanup", post(cleanup_stale_worktrees))
+        // Operation history
+        .route("/api/operations", get(list_operations))
+        .route("/api/operations/{id}", get(get_operation))
         // MCP endpoint
         .route("/mcp", any(mcp_handler))
 }
@@ -53,11 +143,12 @@ pub fn api_routes() -> Router<Arc<AppState>> {
 
 async fn sse_handler(
     State(state): State<Arc<AppState>>,
+    Query(filter): Query<RepositoryFilter>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
     let rx = state.state.subscribe();
 
     // Send initial state first
-    let initial = state.state.get_full_state();
+    let initial = state.state.snapshot().state.filtered(&filter);
 
     let stream = async_stream::stream! {
         // Initial state
@@ -67,8 +158,10 @@ async fn sse_handler(
 
         // Subscribe to updates
         let mut stream = BroadcastStream::new(rx);
-        while let Some(Ok(state)) = stream.next().await {
-            if let Ok(data) = serde_json::to_string(&state) {
+        while let Some(item) = stream.next().await {
+            // A lagging client only missed states older than the next one
+            let Ok(state) = item else { continue };
+            if let Ok(data) = serde_json::to_string(&state.filtered(&filter)) {
                 yield Ok(Event::default().data(data));
             }
         }
@@ -77,10 +170,130 @@ async fn sse_handler(
     Sse::new(stream).keep_alive(KeepAlive::default())
 }
 
+/// Interval between heartbeat events on the event stream
+const HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Incremental state events
+/// Starts with a snapshot, or resumes after the `Last-Event-ID` header when the
+/// server still has the events since. Filtered streams always start with a
+/// snapshot, since what the client holds depends on its filter.
+async fn events_handler(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<RepositoryFilter>,
+    headers: axum::http::HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    // Subscribe before the snapshot or replay so no event falls between them
+    let rx = state.state.subscribe_events();
+    let replay = last_event_id
+        .filter(|_| filter.is_empty())
+        .and_then(|seq| state.state.events_since(seq));
+
+    let stream = async_stream::stream! {
+        let mut seq;
+        // None for unfiltered streams
+        let mut view = None;
+        match replay {
+            Some(replay) => {
+                seq = last_event_id.unwrap_or_default();
+                for event in replay {
+                    seq = event.seq;
+                    yield Ok(state_event(&event));
+                }
+            }
+            None => {
+                let (snapshot, event) = snapshot_event(&state, &filter);
+                seq = snapshot.seq;
+                view = (!filter.is_empty()).then(|| EventFilter::new(filter.clone(), &snapshot.state));
+                yield Ok(event);
+            }
+        }
+
+        let mut events = BroadcastStream::new(rx);
+        let mut heartbeat = tokio::time::interval_at(tokio::time::Instant::now() + HEARTBEAT, HEARTBEAT);
+        loop {
+            let item = tokio::select! {
+                item = events.next() => Some(item),
+                _ = heartbeat.tick() => None,
+            };
+            match item {
+                None => {
+                    let data = serde_json::json!({
+                        "seq": seq,
+                        "at": chrono::Utc::now().timestamp_millis(),
+                    });
+                    yield Ok(Event::default().event("heartbeat").data(data.to_string()));
+                }
+                Some(None) => break,
+                Some(Some(Ok(event))) => {
+                    // Already sent in the snapshot or replay
+                    if event.seq <= seq {
+                        continue;
+                    }
+                    seq = event.seq;
+                    let event = match &mut view {
+                        None => event,
+                        Some(view) => match view.apply(&event.event) {
+                            Some(filtered) => Arc::new(SequencedEvent { seq, event: filtered }),
+                            None => continue,
+                        },
+                    };
+                    yield Ok(state_event(&event));
+                }
+                // Missed events: replay them if still kept, else start over
+                Some(Some(Err(_))) => {
+                    let missed = view.is_none().then(|| state.state.events_since(seq)).flatten();
+                    match missed {
+                        Some(missed) => {
+                            for event in missed {
+                                seq = event.seq;
+                                yield Ok(state_event(&event));
+                            }
+                        }
+                        None => {
+                            let (snapshot, event) = snapshot_event(&state, &filter);
+                            seq = snapshot.seq;
+                            if let Some(view) = &mut view {
+                                *view = EventFilter::new(filter.clone(), &snapshot.state);
+                            }
+                            yield Ok(event);
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    Sse::new(stream)
+}
+
+/// Current state as a `snapshot` event
+fn snapshot_event(state: &AppState, filter: &RepositoryFilter) -> (Snapshot, Event) {
+    let mut snapshot = state.state.snapshot();
+    snapshot.state = snapshot.state.filtered(filter);
+    let event = Event::default()
+        .id(snapshot.seq.to_string())
+        .event("snapshot")
+        .data(serde_json::to_string(&snapshot).unwrap_or_default());
+    (snapshot, event)
+}
+
+fn state_event(event: &SequencedEvent) -> Event {
+    Event::default()
+        .id(event.seq.to_string())
+        .event(event.event.name())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
+
 async fn state_snapshot(
     State(state): State<Arc<AppState>>,
+    Query(filter): Query<RepositoryFilter>,
 ) -> Json<grove_core::FullState> {
-    Json(state.state.get_full_state())
+    Json(state.state.full_state().await.filtered(&filter))
 }
 
 // ─────────────────────────────────────────────────────────────
@@ -118,16 +331,72 @@ async fn mcp_handler(
 
 async fn list_repositories(
     State(state): State<Arc<AppState>>,
+    Query(filter): Query<RepositoryFilter>,
 ) -> Result<Json<Vec<grove_core::Repository>>, ApiError> {
-    let repos = state.db.list_repositories()?;
+    let mut repos = state.db.call(|db| db.list_repositories()).await?;
+    repos.retain(|repo| filter.matches(repo));
     Ok(Json(repos))
 }
 
+async fn update_repository_labels(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(update): Json<LabelUpdate>,
+) -> Result<Json<grove_core::Repository>, ApiError> {
+    let update = update
+        .normalize()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let repo = state
+        .db
+        .call(move |db| {
+            if db.get_repository(&id)?.is_none() {
+                return Ok(None);
+            }
+            db.update_repository_labels(&id, &update)?;
+            db.get_repository(&id)
+        })
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))?;
+    state.state.on_db_change();
+    Ok(Json(repo))
+}
+
+async fn list_labels(State(state): State<Arc<AppState>>) -> Result<Json<LabelCounts>, ApiError> {
+    Ok(Json(state.db.call(|db| db.list_labels()).await?))
+}
+
+async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchHit>>, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::BadRequest("Query parameter q is required".to_string()));
+    }
+    let limit = query.limit.unwrap_or(search::DEFAULT_LIMIT);
+    let hits = state.db.call(move |db| db.search(&query.q, limit)).await?;
+    Ok(Json(hits))
+}
+
 #[derive(Debug, Deserialize)]
 struct CloneRequest {
     url: String,
     #[serde(default)]
     skip_install: bool,
+    /// Hook commands to configure before cloning (so post-clone can run)
+    #[serde(default)]
+    hooks: HashMap<HookEvent, String>,
+    /// Project directories to install (default: discover nested projects)
+    #[serde(default)]
+    install_paths: Vec<String>,
+    /// What cargo runs on install (default: `GROVE_CARGO_STEP`)
+    #[serde(default)]
+    cargo_step: Option<CargoStep>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    favorite: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -135,10 +404,13 @@ struct CloneResponse {
     ok: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation_id: Option<String>,
 }
 
 async fn clone_repository(
     State(state): State<Arc<AppState>>,
+    Client(requester): Client,
     Json(req): Json<CloneRequest>,
 ) -> Result<Json<CloneResponse>, ApiError> {
     // Validate URL
@@ -148,22 +420,27 @@ async fn clone_repository(
             return Ok(Json(CloneResponse {
                 ok: false,
                 error: Some("Invalid Git URL".to_string()),
+                operation_id: None,
             }));
         }
     };
 
     // Check if repo already exists (by name)
-    if let Ok(Some(existing)) =
+    let existing = {
+        let parsed = parsed.clone();
         state
             .db
-            .get_repository_by_name(&parsed.provider, &parsed.username, &parsed.name)
-    {
+            .call(move |db| db.get_repository_by_name(&parsed.provider, &parsed.username, &parsed.name))
+            .await
+    };
+    if let Ok(Some(existing)) = existing {
         return Ok(Json(CloneResponse {
             ok: false,
             error: Some(format!(
                 "Repository {}/{} already exists at {}. Delete it first.",
                 parsed.username, parsed.name, existing.local_path
             )),
+            operation_id: None,
         }));
     }
 
@@ -174,8 +451,26 @@ async fn clone_repository(
         .join(&parsed.username)
         .join(&parsed.name);
 
+    // A trashed copy still holds the name and path
+    if let Some(message) = trashed_conflict(&state, &parsed.provider, &parsed.username, &parsed.name, &local_path).await {
+        return Ok(Json(CloneResponse {
+            ok: false,
+            error: Some(message),
+            operation_id: None,
+        }));
+    }
+
+    let labels = LabelUpdate {
+        tags: Some(req.tags.clone()),
+        group: req.group.clone(),
+        favorite: req.favorite.then_some(true),
+        ..Default::default()
+    }
+    .normalize()
+    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
     // Insert repository immediately so UI shows it
-    let repo_id = state.db.insert_repository(&NewRepository {
+    let new_repo = NewRepository {
         provider: parsed.provider.clone(),
         username: parsed.username.clone(),
         name: parsed.name.clone(),
@@ -184,30 +479,74 @@ async fn clone_repository(
         repo_type: "bare".to_string(),
         default_branch: "main".to_string(), // placeholder, updated after clone
         last_synced: 0, // updated after clone
-    })?;
+    };
+    let hook_commands = req.hooks;
+    let install_paths = req.install_paths;
+    let cargo_step = req.cargo_step;
+    let repo_id = state
+        .db
+        .call(move |db| {
+            let repo_id = db.insert_repository(&new_repo)?;
+            db.update_repository_labels(&repo_id, &labels)?;
+
+            for (event, command) in hook_commands {
+                db.upsert_hook(&Hook {
+                    repo_id: repo_id.clone(),
+                    event,
+                    command,
+                    timeout_secs: hooks::DEFAULT_HOOK_TIMEOUT_SECS,
+                })?;
+            }
+
+            // Install settings are needed before the first install
+            if !install_paths.is_empty() || cargo_step.is_some() {
+                db.upsert_worktree_config(&WorktreeConfig {
+                    install_paths,
+                    cargo_step,
+                    ..WorktreeConfig::defaults(&repo_id)
+                })?;
+            }
+            Ok(repo_id)
+        })
+        .await?;
 
     // Set progress and notify UI
-    state.state.set_progress(&repo_id, Some("Cloning repository..."));
+    state.state.set_progress(
+        &repo_id,
+        ProgressUpdate::new(ProgressPhase::Cloning, "Cloning repository..."),
+    );
     state.state.on_db_change();
 
     // Fire and forget - spawn background clone task
     let state_clone = Arc::clone(&state);
     let url = req.url.clone();
     let skip_install = req.skip_install;
-    tokio::spawn(async move {
-        if let Err(e) = do_clone(state_clone, &url, &repo_id, skip_install).await {
-            tracing::error!("Clone failed: {}", e);
-        }
-    });
+    let operation_id = spawn_operation(
+        Arc::clone(&state),
+        OperationKind::Clone,
+        repo_id.clone(),
+        requester,
+        async move { do_clone(state_clone, &url, &repo_id, skip_install, SetupStep::Prepare).await },
+    )
+    .await;
 
     Ok(Json(CloneResponse {
         ok: true,
         error: None,
+        operation_id: Some(operation_id),
     }))
 }
 
 /// Perform the actual clone operation (runs in background)
-pub async fn do_clone(state: Arc<AppState>, url: &str, repo_id: &str, skip_install: bool) -> anyhow::Result<()> {
+/// Starts at `from` (`Prepare` for a new clone, the failed step on retry). A
+/// failure is recorded on the repository, which is kept so it can be retried.
+pub async fn do_clone(
+    state: Arc<AppState>,
+    url: &str,
+    repo_id: &str,
+    skip_install: bool,
+    from: SetupStep,
+) -> anyhow::Result<()> {
     let parsed = state
         .git
         .parse_url(url)
@@ -220,129 +559,221 @@ pub async fn do_clone(state: Arc<AppState>, url: &str, repo_id: &str, skip_insta
         .join(&parsed.name);
     let bare_path = local_path.join(".bare");
     let main_path = local_path.join(".main");
+    let main_path_str = main_path.to_string_lossy().to_string();
 
     let repo_id = repo_id.to_string();
+    // Nothing to resume if the bare clone is gone
+    let from = if from > SetupStep::Clone && !bare_path.exists() {
+        SetupStep::Prepare
+    } else {
+        from
+    };
+    let mut step = from;
 
-    // Wrap in closure to handle cleanup on error
+    // Wrap in closure to record the failed step on error
     let result: anyhow::Result<()> = async {
-        // Check if directory exists and remove it
-        if local_path.exists() {
-            state
-                .state
-                .set_progress(&repo_id, Some("Cleaning up existing directory..."));
-            tokio::fs::remove_dir_all(&local_path).await?;
-        }
+        if from <= SetupStep::Prepare {
+            // Check if directory exists and remove it (never wipe locked worktrees)
+            if local_path.exists() {
+                if let Some((path, reason)) = state.git.worktree_locks(&local_path).into_iter().next() {
+                    anyhow::bail!(lock_message(&path, reason.as_deref()));
+                }
+                state.state.set_progress(
+                    &repo_id,
+                    ProgressUpdate::new(ProgressPhase::Preparing, "Cleaning up existing directory..."),
+                );
+                tokio::fs::remove_dir_all(&local_path).await?;
+            }
 
-        // Create parent directory
-        tokio::fs::create_dir_all(&local_path).await?;
+            // Create parent directory
+            tokio::fs::create_dir_all(&local_path).await?;
+        }
 
-        // 1. Clone as bare
-        state
-            .state
-            .set_progress(&repo_id, Some("Cloning repository..."));
-        state.git.clone_bare(url, &bare_path, |_msg| {}).await?;
+        // 1. Clone as bare (a failed clone may have left a partial one)
+        if from <= SetupStep::Clone {
+            step = SetupStep::Clone;
+            state.state.set_progress(
+                &repo_id,
+                ProgressUpdate::new(ProgressPhase::Cloning, "Cloning repository..."),
+            );
+            if bare_path.exists() {
+                tokio::fs::remove_dir_all(&bare_path).await?;
+            }
+            state.git.clone_bare(url, &bare_path, |_msg| {}).await?;
+        }
 
         // 2. Create .git file pointing to bare repo
-        state
-            .state
-            .set_progress(&repo_id, Some("Configuring repository..."));
-        tokio::fs::write(local_path.join(".git"), "gitdir: ./.bare\n").await?;
-
-        // 3. Configure remote fetch
-        state.git.config(
-            &local_path,
-            "remote.origin.fetch",
-            "+refs/heads/*:refs/remotes/origin/*",
-        )?;
+        if from <= SetupStep::Configure {
+            step = SetupStep::Configure;
+            state.state.set_progress(
+                &repo_id,
+                ProgressUpdate::new(ProgressPhase::Configuring, "Configuring repository..."),
+            );
+            tokio::fs::write(local_path.join(".git"), "gitdir: ./.bare\n").await?;
+
+            // 3. Configure remote fetch
+            state.git.config(
+                &local_path,
+                "remote.origin.fetch",
+                "+refs/heads/*:refs/remotes/origin/*",
+            )?;
+        }
 
         // 4. Fetch all branches
-        state
-            .state
-            .set_progress(&repo_id, Some("Fetching branches..."));
-        state.git.fetch(&local_path, "origin").await?;
+        if from <= SetupStep::Fetch {
+            step = SetupStep::Fetch;
+            state.state.set_progress(
+                &repo_id,
+                ProgressUpdate::new(ProgressPhase::Fetching, "Fetching branches..."),
+            );
+            state.git.fetch(&local_path, "origin").await?;
+        }
 
         // 5. Detect default branch and update repo
-        state
-            .state
-            .set_progress(&repo_id, Some("Detecting default branch..."));
+        state.state.set_progress(
+            &repo_id,
+            ProgressUpdate::new(ProgressPhase::Configuring, "Detecting default branch..."),
+        );
         let default_branch = state
             .git
             .detect_default_branch(&local_path)
             .unwrap_or_else(|_| "main".to_string());
 
         // Update repo with detected default branch
-        state.db.update_repository_default_branch(&repo_id, &default_branch)?;
-        state.db.update_repository_synced(&repo_id)?;
+        {
+            let (repo_id, default_branch) = (repo_id.clone(), default_branch.clone());
+            state
+                .db
+                .call(move |db| {
+                    db.update_repository_default_branch(&repo_id, &default_branch)?;
+                    db.update_repository_synced(&repo_id)
+                })
+                .await?;
+        }
         state.state.on_db_change();
 
-        // 6. Insert worktree in DB (status=creating)
-        state
-            .state
-            .set_progress(&repo_id, Some("Creating main worktree..."));
-        state.db.insert_worktree(&NewWorktree {
-            path: main_path.to_string_lossy().to_string(),
-            repo_id: repo_id.clone(),
-            branch: default_branch.clone(),
-            status: WorktreeStatus::Creating,
-        })?;
-        state.state.on_db_change();
+        if from <= SetupStep::CreateWorktree {
+            step = SetupStep::CreateWorktree;
 
-        // 7. Delete local branch (bare clone creates it) and create worktree with tracking
-        let _ = std::process::Command::new("git")
-            .args(["branch", "-D", &default_branch])
-            .current_dir(&local_path)
-            .output();
+            // 6. Insert worktree in DB (status=creating); a retry reuses it
+            state.state.set_progress(
+                &repo_id,
+                ProgressUpdate::new(ProgressPhase::CreatingWorktree, "Creating main worktree..."),
+            );
+            let worktree = NewWorktree {
+                path: main_path_str.clone(),
+                repo_id: repo_id.clone(),
+                branch: default_branch.clone(),
+                status: WorktreeStatus::Creating,
+            };
+            state
+                .db
+                .call(move |db| {
+                    if db.get_worktree(&worktree.path)?.is_none() {
+                        db.insert_worktree(&worktree)?;
+                    }
+                    Ok(())
+                })
+                .await?;
+            state.state.on_db_change();
+
+            // 7. Delete local branch (bare clone creates it) and create worktree with tracking
+            let _ = std::process::Command::new("git")
+                .args(["branch", "-D", &default_branch])
+                .current_dir(&local_path)
+                .output();
 
-        state
-            .git
-            .create_worktree(&local_path, &main_path, &default_branch, "origin")
-            .await?;
+            state
+                .git
+                .create_worktree(&local_path, &main_path, &default_branch, "origin")
+                .await?;
+        }
 
         // 8. Install dependencies (unless skip_install)
-        if !skip_install {
-            let managers = detect_package_managers(&main_path);
-            for pm in managers {
+        if from <= SetupStep::Install && !skip_install {
+            step = SetupStep::Install;
+            let managers = detect_managers(&state, &repo_id, &main_path).await;
+            if let Some(status) =
+                install_dependencies(&state, &repo_id, &main_path, None, &managers, &repo_id, "Installing")
+                    .await
+                && status != InstallStatus::Succeeded
+            {
                 state.state.set_progress(
                     &repo_id,
-                    Some(&format!("Installing ({})...", pm.command())),
+                    ProgressUpdate::warning(
+                        ProgressPhase::Installing,
+                        format!("install {}", status.as_str()),
+                    ),
+                );
+            }
+        }
+
+        // 9. Post-clone hook (failure is reported, not fatal)
+        if from <= SetupStep::Hook {
+            step = SetupStep::Hook;
+            let repo = {
+                let repo_id = repo_id.clone();
+                state.db.call(move |db| db.get_repository(&repo_id)).await?
+            };
+            if let Some(repo) = repo
+                && let Err(e) = run_repo_hook(
+                    &state,
+                    &repo,
+                    HookEvent::PostClone,
+                    &main_path,
+                    &default_branch,
+                    &repo_id,
+                )
+                .await
+            {
+                tracing::warn!("{}", e);
+                state.state.set_progress(
+                    &repo_id,
+                    ProgressUpdate::warning(ProgressPhase::Hook, "post-clone hook failed"),
                 );
-                if let Err(e) = run_install(&main_path, pm) {
-                    tracing::warn!("Install {} failed: {}", pm.command(), e);
-                    state
-                        .state
-                        .set_progress(&repo_id, Some(&format!("Warning: {} install failed", pm.command())));
-                }
             }
         }
 
-        // 9. Get git status and update worktree to ready
-        state.state.set_progress(&repo_id, Some("Getting status..."));
+        // 10. Get git status and toolchain versions, update worktree to ready
+        step = SetupStep::Status;
+        state.state.set_progress(
+            &repo_id,
+            ProgressUpdate::new(ProgressPhase::Status, "Getting status..."),
+        );
+        check_toolchains(&state, &main_path_str).await;
         let git_status = state.git.get_status(&main_path)?;
 
-        state.db.update_worktree_status(
-            &main_path.to_string_lossy(),
-            WorktreeStatus::Ready,
-            git_status.head.as_deref(),
-            git_status.commit_message.as_deref(),
-        )?;
-
-        state.db.update_worktree_git_status(
-            &main_path.to_string_lossy(),
-            git_status.dirty,
-            git_status.ahead,
-            git_status.behind,
-        )?;
-
-        // 10. Save worktree config
-        state.db.upsert_worktree_config(&WorktreeConfig {
-            repo_id: repo_id.clone(),
-            symlink_patterns: Some(".env,.env.*,.claude/**".to_string()),
-            copy_patterns: Some(String::new()),
-            upstream_remote: "origin".to_string(),
-        })?;
+        {
+            let (repo_id, main_path_str) = (repo_id.clone(), main_path_str.clone());
+            state
+                .db
+                .call(move |db| {
+                    db.update_worktree_status(
+                        &main_path_str,
+                        WorktreeStatus::Ready,
+                        git_status.head.as_deref(),
+                        git_status.commit_message.as_deref(),
+                    )?;
+                    db.update_worktree_error(&main_path_str, None)?;
+
+                    db.update_worktree_git_status(
+                        &main_path_str,
+                        git_status.dirty,
+                        git_status.ahead,
+                        git_status.behind,
+                    )?;
+
+                    // 11. Save default worktree config (unless set up with the clone request)
+                    if db.get_worktree_config(&repo_id)?.is_none() {
+                        db.upsert_worktree_config(&WorktreeConfig::defaults(&repo_id))?;
+                    }
+                    Ok(())
+                })
+                .await?;
+        }
 
         // Clear progress and push final state
-        state.state.set_progress(&repo_id, None);
+        state.state.clear_progress(&repo_id);
         state.state.on_db_change();
 
         tracing::info!("Clone complete: {} -> {}", url, local_path.display());
@@ -350,56 +781,292 @@ pub async fn do_clone(state: Arc<AppState>, url: &str, repo_id: &str, skip_insta
     }
     .await;
 
-    // Handle cleanup on error
-    if let Err(e) = result {
-        // Clear progress
-        state.state.set_progress(&repo_id, None);
-        // Delete repo from DB
-        let _ = state.db.delete_repository(&repo_id);
-
-        // Remove directory
-        if local_path.exists() {
-            let _ = tokio::fs::remove_dir_all(&local_path).await;
+    // Keep the repository (and what was cloned) so the failure shows and can be retried
+    if let Err(e) = &result {
+        let error = SetupError::new(step, e, skip_install);
+        state.state.clear_progress(&repo_id);
+        let repo_id = repo_id.clone();
+        let recorded = state
+            .db
+            .call(move |db| {
+                if let Err(e) = db.update_repository_error(&repo_id, Some(&error)) {
+                    tracing::warn!("Failed to record clone error: {}", e);
+                }
+                if let Ok(Some(main)) = db.get_worktree(&main_path_str) {
+                    let _ = db.update_worktree_status(&main.path, WorktreeStatus::Error, None, None);
+                    let _ = db.update_worktree_error(&main.path, Some(&error));
+                }
+                Ok(())
+            })
+            .await;
+        if let Err(e) = recorded {
+            tracing::warn!("Failed to record clone error: {}", e);
         }
-
         state.state.on_db_change();
-        return Err(e);
     }
 
-    Ok(())
+    result
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DeleteRepositoryQuery {
+    /// Override the lock guard
+    #[serde(default)]
+    force: bool,
+    /// Delete for good instead of moving to the trash
+    #[serde(default)]
+    purge: bool,
 }
 
 async fn delete_repository(
     State(state): State<Arc<AppState>>,
+    Client(requester): Client,
     axum::extract::Path(id): axum::extract::Path<String>,
+    Query(query): Query<DeleteRepositoryQuery>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
     // Get repository to find local path
-    let repo = state
-        .db
-        .get_repository(&id)?
-        .ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))?;
+    let repo = find_repository(&state, &id).await?;
 
-    let local_path = PathBuf::from(&repo.local_path);
+    // Refuse if any worktree is locked unless forced
+    if !query.force
+        && let Some((path, reason)) = state
+            .git
+            .worktree_locks(Path::new(&repo.local_path))
+            .into_iter()
+            .next()
+    {
+        return Err(ApiError::Conflict(lock_message(&path, reason.as_deref())));
+    }
 
     // Show deleting state
-    state.state.set_progress(&id, Some("Deleting..."));
+    state.state.set_progress(&id, ProgressUpdate::new(ProgressPhase::Deleting, "Deleting..."));
     state.state.on_db_change();
 
-    // Delete directory from disk
-    if local_path.exists() {
-        tokio::fs::remove_dir_all(&local_path)
-            .await
-            .map_err(|e| ApiError::Internal(format!("Failed to delete directory: {}", e)))?;
+    let result = if query.purge {
+        run_operation(&state, OperationKind::PurgeRepository, &id, requester, do_purge_repository(&state, &repo)).await
+    } else {
+        run_operation(&state, OperationKind::DeleteRepository, &id, requester, do_trash_repository(&state, &repo)).await
+    };
+
+    // Clear progress
+    state.state.clear_progress(&id);
+    state.state.on_db_change();
+    result?;
+
+    Ok(Json(serde_json::json!({ "success": true, "trashed": !query.purge })))
+}
+
+/// Move a repository to the trash: stop its installs, move its directory and
+/// soft-delete its rows (hooks, logs and history stay until purged)
+pub async fn do_trash_repository(state: &AppState, repo: &Repository) -> anyhow::Result<()> {
+    let repo_id = repo.id.clone();
+    for wt in state.db.call(move |db| db.list_worktrees(&repo_id)).await? {
+        cancel_install(state, &wt.path);
+    }
+
+    let local_path = PathBuf::from(&repo.local_path);
+    let trash_path = trash::trash_path(&state.config.trash_dir, &repo.id);
+    let (from, to) = (local_path.clone(), trash_path.clone());
+    tokio::task::spawn_blocking(move || trash::move_to_trash(&from, &to)).await??;
+
+    let repo_id = repo.id.clone();
+    if let Err(e) = state.db.call(move |db| db.trash_repository(&repo_id)).await {
+        // Put the directory back so the live row doesn't point at nothing
+        if trash_path.exists()
+            && let Err(undo) =
+                tokio::task::spawn_blocking(move || trash::restore(&trash_path, &local_path)).await?
+        {
+            tracing::error!("Failed to move {} back out of the trash: {}", repo.name, undo);
+        }
+        return Err(e);
     }
+    tracing::info!("Moved {} to the trash", repo.name);
+    Ok(())
+}
+
+/// Delete a repository for good, from its original location or the trash
+pub async fn do_purge_repository(state: &AppState, repo: &Repository) -> anyhow::Result<()> {
+    let path = if repo.deleted_at.is_some() {
+        trash::trash_path(&state.config.trash_dir, &repo.id)
+    } else {
+        let repo_id = repo.id.clone();
+        for wt in state.db.call(move |db| db.list_worktrees(&repo_id)).await? {
+            cancel_install(state, &wt.path);
+        }
+        PathBuf::from(&repo.local_path)
+    };
+    tokio::task::spawn_blocking(move || trash::purge(&path)).await??;
 
     // Delete from database (cascades to worktrees)
-    state.db.delete_repository(&id)?;
+    remove_repository_logs(state, &repo.id).await;
+    let repo_id = repo.id.clone();
+    state.db.call(move |db| db.delete_repository(&repo_id)).await?;
+    tracing::info!("Purged {}", repo.name);
+    Ok(())
+}
 
-    // Clear progress
-    state.state.set_progress(&id, None);
+/// Move a repository out of the trash back to its original location
+pub async fn do_restore_repository(state: &AppState, repo: &Repository) -> anyhow::Result<()> {
+    if let Some(conflict) = restore_conflict(state, repo).await {
+        anyhow::bail!(conflict);
+    }
+
+    let local_path = PathBuf::from(&repo.local_path);
+    let trash_path = trash::trash_path(&state.config.trash_dir, &repo.id);
+    let (from, to) = (trash_path.clone(), local_path.clone());
+    tokio::task::spawn_blocking(move || trash::restore(&from, &to)).await??;
+
+    let repo_id = repo.id.clone();
+    if let Err(e) = state.db.call(move |db| db.restore_repository(&repo_id)).await {
+        // Keep the directory in the trash while the row still says it's there
+        if let Err(undo) =
+            tokio::task::spawn_blocking(move || trash::move_to_trash(&local_path, &trash_path)).await?
+        {
+            tracing::error!("Failed to move {} back into the trash: {}", repo.name, undo);
+        }
+        return Err(e);
+    }
     state.state.on_db_change();
+    tracing::info!("Restored {} from the trash", repo.name);
+    Ok(())
+}
+
+/// Why a trashed repository can't go back (its name or path was taken since)
+pub async fn restore_conflict(state: &AppState, repo: &Repository) -> Option<String> {
+    let (provider, username, name) = (repo.provider.clone(), repo.username.clone(), repo.name.clone());
+    if let Ok(Some(existing)) = state
+        .db
+        .call(move |db| db.get_repository_by_name(&provider, &username, &name))
+        .await
+    {
+        return Some(format!(
+            "Repository {}/{} was cloned again at {}. Delete it first.",
+            repo.username, repo.name, existing.local_path
+        ));
+    }
+    if Path::new(&repo.local_path).exists() {
+        return Some(format!("{} already exists", repo.local_path));
+    }
+    None
+}
+
+/// Why a repository can't be cloned while a trashed copy exists
+pub async fn trashed_conflict(
+    state: &AppState,
+    provider: &str,
+    username: &str,
+    name: &str,
+    local_path: &Path,
+) -> Option<String> {
+    let (provider, username, name) = (provider.to_string(), username.to_string(), name.to_string());
+    let local_path = local_path.to_string_lossy().to_string();
+    let trashed = state
+        .db
+        .call(move |db| db.find_trashed_repository(&provider, &username, &name, &local_path))
+        .await
+        .ok()??;
+    Some(format!(
+        "Repository {}/{} is in the trash ({}). Restore or purge it first.",
+        trashed.username, trashed.name, trashed.id
+    ))
+}
+
+// ─────────────────────────────────────────────────────────────
+// Trash
+// ─────────────────────────────────────────────────────────────
+
+async fn list_trash(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<TrashedRepository>>, ApiError> {
+    let trash_dir = state.config.trash_dir.clone();
+    let retention = state.config.trash_retention;
+    let repos = state.db.call(|db| db.list_trashed_repositories()).await?;
+
+    Ok(Json(
+        repos
+            .into_iter()
+            .map(|repo| TrashedRepository::new(&trash_dir, repo, retention))
+            .collect(),
+    ))
+}
 
-    Ok(Json(serde_json::json!({ "success": true })))
+async fn restore_repository(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repo = state
+        .db
+        .call({
+            let id = id.clone();
+            move |db| db.get_trashed_repository(&id)
+        })
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Repository not in the trash".to_string()))?;
+
+    if let Some(conflict) = restore_conflict(&state, &repo).await {
+        return Err(ApiError::Conflict(conflict));
+    }
+
+    run_operation(
+        &state,
+        OperationKind::RestoreRepository,
+        &id,
+        requester,
+        do_restore_repository(&state, &repo),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "ok": true, "local_path": repo.local_path })))
+}
+
+async fn purge_repository(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repo = state
+        .db
+        .call({
+            let id = id.clone();
+            move |db| db.get_trashed_repository(&id)
+        })
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Repository not in the trash".to_string()))?;
+
+    run_operation(
+        &state,
+        OperationKind::PurgeRepository,
+        &id,
+        requester,
+        do_purge_repository(&state, &repo),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+async fn empty_trash(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repos = state.db.call(|db| db.list_trashed_repositories()).await?;
+    let mut purged = 0;
+    for repo in &repos {
+        let result = run_operation(
+            &state,
+            OperationKind::PurgeRepository,
+            &repo.id,
+            requester,
+            do_purge_repository(&state, repo),
+        )
+        .await;
+        if result.is_ok() {
+            purged += 1;
+        }
+    }
+
+    Ok(Json(serde_json::json!({ "ok": purged == repos.len(), "purged": purged })))
 }
 
 // ─────────────────────────────────────────────────────────────
@@ -412,6 +1079,14 @@ struct CreateWorktreeRequest {
     branch: String,
     #[serde(default)]
     skip_install: bool,
+    /// Why the worktree exists
+    #[serde(default)]
+    note: Option<String>,
+    /// Linked issue or ticket
+    #[serde(default)]
+    issue_url: Option<String>,
+    #[serde(default)]
+    owner: Option<String>,
 }
 
 /// Sanitize branch name for directory name
@@ -433,13 +1108,11 @@ pub fn sanitize_branch_name(branch: &str, default_branch: &str) -> String {
 
 async fn create_worktree(
     State(state): State<Arc<AppState>>,
+    Client(requester): Client,
     Json(req): Json<CreateWorktreeRequest>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
     // Get repository
-    let repo = state
-        .db
-        .get_repository(&req.repo_id)?
-        .ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))?;
+    let repo = find_repository(&state, &req.repo_id).await?;
 
     // Validate branch name
     let branch = req.branch.trim();
@@ -451,7 +1124,6 @@ async fn create_worktree(
     let local_path = PathBuf::from(&repo.local_path);
     let worktree_name = sanitize_branch_name(branch, &repo.default_branch);
     let worktree_path = local_path.join(&worktree_name);
-    let main_path = local_path.join(".main");
 
     // Ensure worktree path is within repo path (defense in depth)
     if !worktree_path.starts_with(&local_path) {
@@ -459,17 +1131,40 @@ async fn create_worktree(
     }
 
     // Check if worktree already exists
-    if let Ok(Some(_)) = state.db.get_worktree(&worktree_path.to_string_lossy()) {
+    let worktree_path_str = worktree_path.to_string_lossy().to_string();
+    let existing = {
+        let path = worktree_path_str.clone();
+        state.db.call(move |db| db.get_worktree(&path)).await
+    };
+    if let Ok(Some(_)) = existing {
         return Err(ApiError::BadRequest("Worktree already exists".to_string()));
     }
 
+    let notes = WorktreeNotes {
+        note: req.note.clone(),
+        issue_url: req.issue_url.clone(),
+        owner: req.owner.clone(),
+    }
+    .normalize()
+    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
     // Insert worktree in DB (status=creating)
-    state.db.insert_worktree(&NewWorktree {
-        path: worktree_path.to_string_lossy().to_string(),
+    let worktree = NewWorktree {
+        path: worktree_path_str.clone(),
         repo_id: req.repo_id.clone(),
         branch: branch.to_string(),
         status: WorktreeStatus::Creating,
-    })?;
+    };
+    state
+        .db
+        .call(move |db| {
+            db.insert_worktree(&worktree)?;
+            if !notes.is_empty() {
+                db.update_worktree_notes(&worktree.path, &notes)?;
+            }
+            Ok(())
+        })
+        .await?;
     state.state.on_db_change();
 
     // Spawn background task to create worktree
@@ -477,36 +1172,31 @@ async fn create_worktree(
     let branch_owned = branch.to_string();
     let repo_id = req.repo_id.clone();
     let skip_install = req.skip_install;
-    let worktree_path_str = worktree_path.to_string_lossy().to_string();
-
-    tokio::spawn(async move {
-        let result = do_create_worktree(
-            state_clone.clone(),
-            &local_path,
-            &main_path,
-            &worktree_path,
-            &branch_owned,
-            &repo_id,
-            skip_install,
-        )
-        .await;
 
-        if let Err(e) = result {
-            tracing::error!("Failed to create worktree: {}", e);
-            // Update status to error
-            let _ = state_clone.db.update_worktree_status(
-                &worktree_path_str,
-                WorktreeStatus::Error,
-                None,
-                None,
-            );
-            state_clone.state.on_db_change();
-        }
-    });
+    let operation_id = spawn_operation(
+        Arc::clone(&state),
+        OperationKind::CreateWorktree,
+        worktree_path_str.clone(),
+        requester,
+        async move {
+            do_create_worktree(
+                state_clone,
+                &local_path,
+                &worktree_path,
+                &branch_owned,
+                &repo_id,
+                skip_install,
+                SetupStep::CreateWorktree,
+            )
+            .await
+        },
+    )
+    .await;
 
     Ok(Json(serde_json::json!({
         "ok": true,
-        "message": format!("Creating worktree {}", req.branch)
+        "message": format!("Creating worktree {}", req.branch),
+        "operation_id": operation_id
     })))
 }
 
@@ -517,262 +1207,2276 @@ async fn create_worktree(
 async fn sync_main_worktree(
     state: Arc<AppState>,
     repo_id: &str,
-    local_path: &PathBuf,
-    main_path: &PathBuf,
+    local_path: &Path,
+    main_path: &Path,
 ) {
     // Fetch from remote
-    state.state.set_progress(repo_id, Some("Fetching..."));
+    state.state.set_progress(repo_id, ProgressUpdate::new(ProgressPhase::Fetching, "Fetching..."));
     if let Err(e) = state.git.fetch(local_path, "origin").await {
         tracing::warn!("Fetch failed during main sync: {}", e);
     }
 
     // Pull main worktree
-    state.state.set_progress(repo_id, Some("Pulling main..."));
+    state.state.set_progress(
+        repo_id,
+        ProgressUpdate::new(ProgressPhase::Pulling, "Pulling main..."),
+    );
     if let Err(e) = state.git.pull(main_path).await {
         tracing::warn!("Pull main failed: {}", e);
     }
 
-    // Install dependencies to warm cache
-    let managers = detect_package_managers(main_path);
-    for pm in managers {
-        state
-            .state
-            .set_progress(repo_id, Some(&format!("Installing main ({})...", pm.command())));
-        if let Err(e) = run_install(main_path, pm) {
-            tracing::warn!("Install main {} failed: {}", pm.command(), e);
-        }
+    // Flag a pull that changed dependencies, then install (skipped if unchanged)
+    let main_path_str = main_path.to_string_lossy().to_string();
+    if let Ok(Some(main)) = state.db.call(move |db| db.get_worktree(&main_path_str)).await {
+        check_dependencies(&state, &main).await;
+        check_toolchains(&state, &main.path).await;
     }
+    let managers = detect_managers(&state, repo_id, main_path).await;
+    install_dependencies(&state, repo_id, main_path, None, &managers, repo_id, "Installing main").await;
 
-    state.state.set_progress(repo_id, None);
+    state.state.clear_progress(repo_id);
 }
 
-pub async fn do_create_worktree(
-    state: Arc<AppState>,
-    local_path: &PathBuf,
-    main_path: &PathBuf,
-    worktree_path: &PathBuf,
+/// Run a repository's hook for `event` if one is configured
+/// Output lines are shown as progress under `progress_key`; the caller clears it.
+pub async fn run_repo_hook(
+    state: &AppState,
+    repo: &Repository,
+    event: HookEvent,
+    worktree_path: &Path,
     branch: &str,
-    repo_id: &str,
-    skip_install: bool,
+    progress_key: &str,
 ) -> anyhow::Result<()> {
-    let worktree_path_str = worktree_path.to_string_lossy().to_string();
+    let repo_id = repo.id.clone();
+    let Some(hook) = state.db.call(move |db| db.get_hook(&repo_id, event)).await? else {
+        return Ok(());
+    };
 
-    // 1. Sync main worktree first (fetch, pull, install to warm cache)
-    sync_main_worktree(state.clone(), repo_id, local_path, main_path).await;
+    state.state.set_progress(
+        progress_key,
+        ProgressUpdate::new(ProgressPhase::Hook, format!("Running {} hook...", event.as_str())),
+    );
 
-    // 2. Create git worktree
+    let repo_path = Path::new(&repo.local_path);
+    let cwd = if worktree_path.exists() {
+        worktree_path
+    } else {
+        repo_path
+    };
+    let env = HookEnv {
+        repo_id: &repo.id,
+        repo_name: &repo.name,
+        repo_path,
+        worktree_path,
+        branch,
+    };
+
+    hooks::run_hook(&hook, cwd, &env, |line| {
+        state.state.set_progress(
+            progress_key,
+            ProgressUpdate::new(ProgressPhase::Hook, format!("{}: {}", event.as_str(), line)),
+        );
+    })
+    .await
+}
+
+/// Install targets (root and nested projects) for a worktree of a repository
+async fn install_targets(state: &AppState, repo_id: &str, worktree_path: &Path) -> Vec<InstallTarget> {
+    let repo_id = repo_id.to_string();
+    let config = state.db.call(move |db| db.get_worktree_config(&repo_id)).await.ok().flatten();
+    discover_targets(state, config, worktree_path)
+}
+
+/// Install targets of a worktree under a repository's install settings
+fn discover_targets(state: &AppState, config: Option<WorktreeConfig>, worktree_path: &Path) -> Vec<InstallTarget> {
+    let paths = config.as_ref().map(|c| c.install_paths.as_slice()).unwrap_or_default();
+    let cargo_step = config
+        .as_ref()
+        .and_then(|c| c.cargo_step)
+        .unwrap_or(state.config.cargo_step);
+
+    state
+        .package_managers
+        .discover(worktree_path, paths, cargo_step)
+}
+
+/// Discover install targets for a worktree and record them on its row
+async fn detect_managers(state: &AppState, repo_id: &str, worktree_path: &Path) -> Vec<InstallTarget> {
+    let targets = install_targets(state, repo_id, worktree_path).await;
+    let labels: Vec<String> = targets.iter().map(InstallTarget::label).collect();
+    let path = worktree_path.to_string_lossy().to_string();
+    if let Err(e) = state
+        .db
+        .call(move |db| db.update_worktree_package_managers(&path, &labels))
+        .await
+    {
+        tracing::warn!("Failed to record package managers: {}", e);
+    }
+    targets
+}
+
+/// Install with every detected manager, streaming output to a persisted log
+/// The outcome is recorded on the worktree; None when there was nothing to install.
+/// Stops at the first cancel; a failed or timed out manager doesn't stop the rest.
+/// With `seed_from`, dependencies are reused from that worktree when lockfiles match.
+async fn install_dependencies(
+    state: &AppState,
+    repo_id: &str,
+    worktree_path: &Path,
+    seed_from: Option<&Path>,
+    targets: &[InstallTarget],
+    progress_key: &str,
+    label: &str,
+) -> Option<InstallStatus> {
+    if targets.is_empty() {
+        return None;
+    }
+
+    let path_str = worktree_path.to_string_lossy().to_string();
+
+    // Nothing changed since the last successful install
+    let current = install::fingerprint(worktree_path, targets);
+    let path = path_str.clone();
+    if let Ok(Some(worktree)) = state.db.call(move |db| db.get_worktree(&path)).await
+        && worktree.install_status == Some(InstallStatus::Succeeded)
+        && worktree.deps_fingerprint.is_some()
+        && worktree.deps_fingerprint == current
+    {
+        tracing::debug!("Dependencies up to date in {}", path_str);
+        if worktree.deps_state != Some(DepsState::UpToDate) {
+            let path = path_str.clone();
+            let _ = state
+                .db
+                .call(move |db| db.update_worktree_deps_state(&path, DepsState::UpToDate))
+                .await;
+            state.state.on_db_change();
+        }
+        return Some(InstallStatus::Succeeded);
+    }
+    let log = InstallLog {
+        id: uuid::Uuid::new_v4().to_string(),
+        repo_id: repo_id.to_string(),
+        worktree_path: path_str.clone(),
+        started_at: chrono::Utc::now().timestamp_millis(),
+        finished_at: None,
+        success: None,
+    };
+
+    // Create the file before the record so a listed log is always streamable
+    let mut writer = state
+        .logs
+        .create(&log.id)
+        .inspect_err(|e| tracing::warn!("Failed to create install log: {}", e))
+        .ok();
+    let log_id = log.id.clone();
+    let path = path_str.clone();
+    let recorded = state
+        .db
+        .call(move |db| {
+            if let Err(e) = db.insert_install_log(&log) {
+                tracing::warn!("Failed to record install log: {}", e);
+            }
+            db.update_worktree_install_status(&path, InstallStatus::Running)
+        })
+        .await;
+
+    // Register for cancellation (a newer install of the same worktree replaces it)
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    state
+        .installs
+        .lock()
+        .unwrap()
+        .insert(path_str.clone(), cancel_tx);
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record install status: {}", e);
+    }
+    state.state.on_db_change();
+
+    let mut status = InstallStatus::Succeeded;
+    for (done, target) in targets.iter().enumerate() {
+        let pm = &target.manager;
+        let name = target.label();
+        let project = worktree_path.join(&target.dir);
+
+        // Seed from .main when lockfiles match, then only verify
+        let seeded = match seed_from {
+            Some(main) => {
+                state.state.set_progress(
+                    progress_key,
+                    ProgressUpdate::new(
+                        ProgressPhase::Installing,
+                        format!("Reusing dependencies ({})...", name),
+                    )
+                    .steps(done, targets.len()),
+                );
+                seed_dependencies(pm, &main.join(&target.dir), &project, &mut writer).await
+            }
+            None => Vec::new(),
+        };
+        let step = if seeded.is_empty() { pm.clone() } else { pm.verifier() };
+
+        // Run under the pinned toolchain versions when a version manager is available
+        let manager = state
+            .config
+            .use_version_manager
+            .then(|| toolchain::version_manager(&project, &pm.ecosystem))
+            .flatten();
+        let via_manager = |pm: &PackageManager| match manager {
+            Some(manager) => toolchain::wrap(pm, manager, &project),
+            None => pm.clone(),
+        };
+
+        state.state.set_progress(
+            progress_key,
+            ProgressUpdate::new(ProgressPhase::Installing, format!("{} ({})...", label, name))
+                .steps(done, targets.len()),
+        );
+        let mut result =
+            run_logged(state, &project, &via_manager(&step), &cancel_rx, &mut writer).await;
+
+        if !seeded.is_empty() && matches!(result, Err(RunError::Failed { .. })) {
+            if let Some(w) = writer.as_mut() {
+                w.line(&format!("{} verify failed, running full install", name));
+            }
+            // Start clean rather than installing over what the verify left behind
+            for dir in &seeded {
+                let _ = tokio::fs::remove_dir_all(project.join(dir)).await;
+            }
+            result = run_logged(state, &project, &via_manager(pm), &cancel_rx, &mut writer).await;
+        }
+
+        let Err(e) = result else { continue };
+        tracing::warn!("{} {} {}", label, name, e);
+        if let Some(w) = writer.as_mut() {
+            w.line(&format!("{} install {}", name, e));
+        }
+        match e {
+            RunError::Cancelled => {
+                status = InstallStatus::Cancelled;
+                break;
+            }
+            RunError::TimedOut(_) => status = InstallStatus::TimedOut,
+            _ if status == InstallStatus::Succeeded => status = InstallStatus::Failed,
+            _ => {}
+        }
+    }
+
+    {
+        let mut installs = state.installs.lock().unwrap();
+        if installs.get(&path_str).is_some_and(|tx| tx.subscribe().same_channel(&cancel_rx)) {
+            installs.remove(&path_str);
+        }
+    }
+    // Hash after installing: installs may rewrite lockfiles
+    let fingerprint = (status == InstallStatus::Succeeded)
+        .then(|| install::fingerprint(worktree_path, targets))
+        .flatten();
+    let _ = state
+        .db
+        .call(move |db| {
+            if let Err(e) = db.finish_install_log(&log_id, status == InstallStatus::Succeeded) {
+                tracing::warn!("Failed to record install log: {}", e);
+            }
+            let _ = db.update_worktree_install_status(&path_str, status);
+            if let Some(fingerprint) = fingerprint {
+                let _ = db.update_worktree_deps_fingerprint(&path_str, &fingerprint);
+            }
+            Ok(())
+        })
+        .await;
+    state.state.on_db_change();
+
+    Some(status)
+}
+
+/// Run one install step, writing the command line and its output to the log
+async fn run_logged(
+    state: &AppState,
+    worktree_path: &Path,
+    pm: &PackageManager,
+    cancel_rx: &watch::Receiver<bool>,
+    writer: &mut Option<LogWriter>,
+) -> Result<(), RunError> {
+    if let Some(w) = writer.as_mut() {
+        w.line(&format!("$ {} {}", pm.command(), pm.install_args().join(" ")));
+    }
+    run_install(
+        worktree_path,
+        pm,
+        state.config.install_timeout,
+        Some(cancel_rx.clone()),
+        |line| {
+            if let Some(w) = writer.as_mut() {
+                w.line(line);
+            }
+        },
+    )
+    .await
+}
+
+/// Seed a manager's dependency directories from `.main`, returning the seeded ones
+async fn seed_dependencies(
+    pm: &PackageManager,
+    main_path: &Path,
+    worktree_path: &Path,
+    writer: &mut Option<LogWriter>,
+) -> Vec<String> {
+    let (pm_c, main, worktree) = (pm.clone(), main_path.to_path_buf(), worktree_path.to_path_buf());
+    let seeded = tokio::task::spawn_blocking(move || pm_c.seed_from(&main, &worktree))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match seeded {
+        Ok(seeded) => {
+            for (dir, method) in &seeded {
+                if let Some(w) = writer.as_mut() {
+                    w.line(&format!("seeded {} from .main ({})", dir, method.as_str()));
+                }
+            }
+            seeded.into_iter().map(|(dir, _)| dir).collect()
+        }
+        Err(e) => {
+            tracing::warn!("Reusing {} dependencies failed: {}", pm.name, e);
+            if let Some(w) = writer.as_mut() {
+                w.line(&format!("{} reuse failed ({}), installing", pm.name, e));
+            }
+            Vec::new()
+        }
+    }
+}
+
+/// Compare a worktree's lockfiles and manifests with its last successful install
+/// and record whether its dependencies are stale (no-op if never installed)
+pub async fn check_dependencies(state: &AppState, worktree: &Worktree) {
+    let Some(recorded) = &worktree.deps_fingerprint else {
+        return;
+    };
+    let path = Path::new(&worktree.path);
+    let targets = install_targets(state, &worktree.repo_id, path).await;
+    let deps_state = match install::fingerprint(path, &targets) {
+        Some(current) if &current == recorded => DepsState::UpToDate,
+        _ => DepsState::Stale,
+    };
+    if worktree.deps_state != Some(deps_state) {
+        let path = worktree.path.clone();
+        let _ = state
+            .db
+            .call(move |db| db.update_worktree_deps_state(&path, deps_state))
+            .await;
+    }
+}
+
+/// Compare the toolchain versions pinned in a worktree (`.nvmrc`,
+/// `rust-toolchain.toml`, ...) with the installed ones and record the result
+pub async fn check_toolchains(state: &AppState, worktree_path: &str) {
+    let path = PathBuf::from(worktree_path);
+    let toolchains = tokio::task::spawn_blocking(move || toolchain::check(&path))
+        .await
+        .unwrap_or_default();
+    for t in toolchains.iter().filter(|t| t.is_mismatch()) {
+        tracing::debug!(
+            "{} wants {} {} ({}), found {}",
+            worktree_path,
+            t.tool,
+            t.required,
+            t.source,
+            t.installed.as_deref().unwrap_or("none")
+        );
+    }
+    let path = worktree_path.to_string();
+    if let Err(e) = state
+        .db
+        .call(move |db| db.update_worktree_toolchains(&path, &toolchains))
+        .await
+    {
+        tracing::warn!("Failed to record toolchains: {}", e);
+    }
+}
+
+/// Cancel a running install; returns false if none is running for the path
+pub fn cancel_install(state: &AppState, worktree_path: &str) -> bool {
+    match state.installs.lock().unwrap().get(worktree_path) {
+        Some(tx) => tx.send(true).is_ok(),
+        None => false,
+    }
+}
+
+/// Symlink/copy shared files from .main according to the repo's worktree config
+/// Returns how many files were shared (existing files are left alone).
+async fn share_from_main(
+    state: &AppState,
+    repo_id: &str,
+    main_path: &Path,
+    worktree_path: &Path,
+) -> anyhow::Result<usize> {
+    let repo_id = repo_id.to_string();
+    let (main_path, worktree_path) = (main_path.to_path_buf(), worktree_path.to_path_buf());
+    state
+        .db
+        .call(move |db| {
+            let Some(config) = db.get_worktree_config(&repo_id)? else {
+                return Ok(0);
+            };
+            if config.symlink_patterns.is_empty() && config.copy_patterns.is_empty() {
+                return Ok(0);
+            }
+
+            let symlink_patterns: Vec<&str> = config.symlink_patterns.iter().map(String::as_str).collect();
+            let copy_patterns: Vec<&str> = config.copy_patterns.iter().map(String::as_str).collect();
+            share_files(&main_path, &worktree_path, &symlink_patterns, &copy_patterns)
+        })
+        .await
+}
+
+/// Create a worktree, share files, install and run the post-create hook (runs in background)
+/// Starts at `from` (`CreateWorktree` for a new worktree, the failed step on
+/// retry). A failure sets the worktree to `error` with what went wrong.
+pub async fn do_create_worktree(
+    state: Arc<AppState>,
+    local_path: &Path,
+    worktree_path: &Path,
+    branch: &str,
+    repo_id: &str,
+    skip_install: bool,
+    from: SetupStep,
+) -> anyhow::Result<()> {
+    let main_path = &local_path.join(".main");
+    let worktree_path_str = worktree_path.to_string_lossy().to_string();
+    let mut step = from;
+
+    let result: anyhow::Result<()> = async {
+        if from <= SetupStep::CreateWorktree {
+            // 1. Sync main worktree first (fetch, pull, install to warm cache)
+            sync_main_worktree(state.clone(), repo_id, local_path, main_path).await;
+
+            // 2. Create git worktree
+            step = SetupStep::CreateWorktree;
+            state.state.set_progress(
+                &worktree_path_str,
+                ProgressUpdate::new(ProgressPhase::CreatingWorktree, "Creating worktree..."),
+            );
+            state
+                .git
+                .create_worktree(local_path, worktree_path, branch, "origin")
+                .await?;
+        }
+
+        // 3. Share files from .main
+        if from <= SetupStep::Share {
+            state.state.set_progress(
+                &worktree_path_str,
+                ProgressUpdate::new(ProgressPhase::Sharing, "Sharing files..."),
+            );
+            if let Err(e) = share_from_main(&state, repo_id, main_path, worktree_path).await {
+                tracing::warn!("Failed to share files: {}", e);
+            }
+        }
+
+        // 4. Install dependencies (unless skip_install)
+        if from <= SetupStep::Install && !skip_install {
+            let managers = detect_managers(&state, repo_id, worktree_path).await;
+            if let Some(status) = install_dependencies(
+                &state,
+                repo_id,
+                worktree_path,
+                Some(main_path),
+                &managers,
+                &worktree_path_str,
+                "Installing",
+            )
+            .await
+                && status != InstallStatus::Succeeded
+            {
+                state.state.set_progress(
+                    &worktree_path_str,
+                    ProgressUpdate::warning(
+                        ProgressPhase::Installing,
+                        format!("install {}", status.as_str()),
+                    ),
+                );
+            }
+        }
+
+        // 5. Post-create hook (failure is reported, not fatal)
+        if from <= SetupStep::Hook {
+            step = SetupStep::Hook;
+            let repo = {
+                let repo_id = repo_id.to_string();
+                state.db.call(move |db| db.get_repository(&repo_id)).await?
+            };
+            if let Some(repo) = repo
+                && let Err(e) = run_repo_hook(
+                    &state,
+                    &repo,
+                    HookEvent::PostCreate,
+                    worktree_path,
+                    branch,
+                    &worktree_path_str,
+                )
+                .await
+            {
+                tracing::warn!("{}", e);
+                state.state.set_progress(
+                    &worktree_path_str,
+                    ProgressUpdate::warning(ProgressPhase::Hook, "post-create hook failed"),
+                );
+            }
+        }
+
+        // 6. Get git status and toolchain versions, update to ready
+        step = SetupStep::Status;
+        state.state.set_progress(
+            &worktree_path_str,
+            ProgressUpdate::new(ProgressPhase::Status, "Getting status..."),
+        );
+        check_toolchains(&state, &worktree_path_str).await;
+        let git_status = state.git.get_status(worktree_path)?;
+
+        let path = worktree_path_str.clone();
+        state
+            .db
+            .call(move |db| {
+                db.update_worktree_status(
+                    &path,
+                    WorktreeStatus::Ready,
+                    git_status.head.as_deref(),
+                    git_status.commit_message.as_deref(),
+                )?;
+
+                db.update_worktree_git_status(
+                    &path,
+                    git_status.dirty,
+                    git_status.ahead,
+                    git_status.behind,
+                )
+            })
+            .await?;
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = &result {
+        let error = SetupError::new(step, e, skip_install);
+        let path = worktree_path_str.clone();
+        let recorded = state
+            .db
+            .call(move |db| {
+                let _ = db.update_worktree_status(&path, WorktreeStatus::Error, None, None);
+                db.update_worktree_error(&path, Some(&error))
+            })
+            .await;
+        if let Err(e) = recorded {
+            tracing::warn!("Failed to record worktree error: {}", e);
+        }
+    }
+
+    // Clear progress
+    state.state.clear_progress(&worktree_path_str);
+    state.state.clear_progress(repo_id);
+    state.state.on_db_change();
+
+    result
+}
+
+async fn find_repository(state: &AppState, id: &str) -> Result<Repository, ApiError> {
+    let id = id.to_string();
+    state
+        .db
+        .call(move |db| db.get_repository(&id))
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))
+}
+
+/// A worktree and its repository
+async fn find_worktree(state: &AppState, path: &str) -> Result<(Worktree, Repository), ApiError> {
+    let path = path.to_string();
+    let (worktree, repo) = state
+        .db
+        .call(move |db| {
+            let Some(worktree) = db.get_worktree(&path)? else {
+                return Ok((None, None));
+            };
+            let repo = db.get_repository(&worktree.repo_id)?;
+            Ok((Some(worktree), repo))
+        })
+        .await?;
+    let worktree = worktree.ok_or_else(|| ApiError::NotFound("Worktree not found".to_string()))?;
+    let repo = repo.ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))?;
+    Ok((worktree, repo))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ForceQuery {
+    /// Override the lock guard
+    #[serde(default)]
+    force: bool,
+}
+
+async fn delete_worktree(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(path): axum::extract::Path<String>,
+    Query(query): Query<ForceQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (worktree, repo) = find_worktree(&state, &path).await?;
+    let removal = begin_delete(&state, repo, worktree, query.force).await?;
+    let operation_id = spawn_operation(
+        Arc::clone(&state),
+        OperationKind::DeleteWorktree,
+        path,
+        requester,
+        removal,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "ok": true, "operation_id": operation_id })))
+}
+
+/// The pre-delete hook refused to let a worktree go
+#[derive(Debug)]
+pub struct DeleteVetoed(pub String);
+
+impl std::fmt::Display for DeleteVetoed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Delete vetoed: {}", self.0)
+    }
+}
+
+impl std::error::Error for DeleteVetoed {}
+
+/// A locked worktree can't be deleted without `force`
+#[derive(Debug)]
+pub struct WorktreeLocked(pub String);
+
+impl std::fmt::Display for WorktreeLocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for WorktreeLocked {}
+
+/// Check the lock, run the pre-delete hook and mark a worktree `deleting`
+/// Returns the removal, to run as a `DeleteWorktree` operation. Fails with
+/// `WorktreeLocked` unless `force`, or `DeleteVetoed` if the hook refused
+/// (it runs for at most `PRE_DELETE_TIMEOUT_SECS`), leaving the worktree as it was.
+pub async fn begin_delete(
+    state: &Arc<AppState>,
+    repo: Repository,
+    worktree: Worktree,
+    force: bool,
+) -> anyhow::Result<impl Future<Output = anyhow::Result<()>> + Send + 'static> {
+    let locks = state.git.worktree_locks(Path::new(&repo.local_path));
+    if let Some(reason) = locks.get(&worktree.path)
+        && !force
+    {
+        return Err(WorktreeLocked(lock_message(&worktree.path, reason.as_deref())).into());
+    }
+
+    let hook = run_repo_hook(
+        state,
+        &repo,
+        HookEvent::PreDelete,
+        Path::new(&worktree.path),
+        &worktree.branch,
+        &worktree.path,
+    )
+    .await;
+    state.state.clear_progress(&worktree.path);
+    if let Err(e) = hook {
+        return Err(DeleteVetoed(e.to_string()).into());
+    }
+
+    mark_deleting(state, &worktree).await?;
+    state.state.on_db_change();
+
+    Ok(do_remove_worktree(Arc::clone(state), repo, worktree, force))
+}
+
+/// Set a worktree to `deleting`, keeping its head
+async fn mark_deleting(state: &AppState, worktree: &Worktree) -> anyhow::Result<()> {
+    let worktree = worktree.clone();
+    state
+        .db
+        .call(move |db| {
+            db.update_worktree_status(
+                &worktree.path,
+                WorktreeStatus::Deleting,
+                worktree.head.as_deref(),
+                worktree.commit_message.as_deref(),
+            )
+        })
+        .await
+}
+
+/// Remove a worktree from git, disk and the database, then run the post-delete hook
+/// (runs in background; the pre-delete hook has already passed)
+/// The record is always removed; a leftover directory or failed hook is returned as an error.
+pub async fn do_remove_worktree(
+    state: Arc<AppState>,
+    repo: Repository,
+    worktree: Worktree,
+    force: bool,
+) -> anyhow::Result<()> {
+    let local_path = PathBuf::from(&repo.local_path);
+    let worktree_path = PathBuf::from(&worktree.path);
+
+    // Stop any install still running in the worktree
+    cancel_install(&state, &worktree.path);
+
+    // Try to remove git worktree
+    let result = state
+        .git
+        .remove_worktree(&local_path, &worktree_path, force)
+        .await;
+
+    if let Err(e) = &result {
+        // Log but continue - worktree might not exist in git
+        tracing::warn!("git worktree remove failed (may be orphaned): {}", e);
+    }
+
+    // Clean up directory if it exists
+    let mut failure = None;
+    if worktree_path.exists()
+        && let Err(e) = tokio::fs::remove_dir_all(&worktree_path).await
+    {
+        tracing::warn!("Failed to remove worktree directory: {}", e);
+        failure = Some(format!("Failed to remove worktree directory: {}", e));
+    }
+
+    // Always delete from DB (cleanup orphaned records)
+    let path = worktree.path.clone();
+    let logs = state
+        .db
+        .call(move |db| {
+            let _ = db.delete_worktree(&path);
+            Ok(db.delete_install_logs(&path).unwrap_or_default())
+        })
+        .await
+        .unwrap_or_default();
+    for id in logs {
+        state.logs.remove(&id);
+    }
+    state.state.on_db_change();
+
+    // Post-delete hook runs in the repository directory (progress keyed by repo)
+    if let Err(e) = run_repo_hook(
+        &state,
+        &repo,
+        HookEvent::PostDelete,
+        &worktree_path,
+        &worktree.branch,
+        &repo.id,
+    )
+    .await
+    {
+        tracing::warn!("{}", e);
+        failure.get_or_insert(e.to_string());
+    }
+    state.state.clear_progress(&repo.id);
+
+    match failure {
+        Some(failure) => anyhow::bail!("{}", failure),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveWorktreeRequest {
+    path: String,
+    /// New directory name (defaults to the sanitized branch name)
+    #[serde(default)]
+    name: Option<String>,
+    /// New branch name (renames the branch when different)
+    #[serde(default)]
+    branch: Option<String>,
+}
+
+async fn move_worktree(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    Json(req): Json<MoveWorktreeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if req.name.is_none() && req.branch.is_none() {
+        return Err(ApiError::BadRequest(
+            "Provide a new name or branch".to_string(),
+        ));
+    }
+
+    let (worktree, repo) = find_worktree(&state, &req.path).await?;
+
+    if worktree.status != WorktreeStatus::Ready {
+        return Err(ApiError::BadRequest(format!(
+            "Worktree is {}",
+            worktree.status.as_str()
+        )));
+    }
+
+    let (new_path, new_branch) = resolve_move_target(
+        &repo,
+        &worktree,
+        req.name.as_deref(),
+        req.branch.as_deref(),
+    )
+    .map_err(ApiError::BadRequest)?;
+
+    run_operation(
+        &state,
+        OperationKind::MoveWorktree,
+        &req.path,
+        requester,
+        do_move_worktree(Arc::clone(&state), &repo, &worktree, &new_path, &new_branch),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "path": new_path.to_string_lossy(),
+        "branch": new_branch,
+    })))
+}
+
+/// Compute destination path and branch for a worktree move
+/// - `branch` renames the branch (directory follows unless `name` is given)
+/// - `name` overrides the directory name
+pub fn resolve_move_target(
+    repo: &Repository,
+    worktree: &Worktree,
+    name: Option<&str>,
+    branch: Option<&str>,
+) -> Result<(PathBuf, String), String> {
+    let local_path = PathBuf::from(&repo.local_path);
+
+    if Path::new(&worktree.path) == local_path.join(".main") {
+        return Err("The main worktree cannot be moved".to_string());
+    }
+
+    let branch = match branch.map(str::trim) {
+        Some(b) if !b.is_empty() => b,
+        Some(_) => return Err("Invalid branch name".to_string()),
+        None => worktree.branch.as_str(),
+    };
+    if branch.chars().all(|c| c == '.') {
+        return Err("Invalid branch name".to_string());
+    }
+    if branch != worktree.branch && branch == repo.default_branch {
+        return Err(format!(
+            "Cannot rename to the default branch ({})",
+            repo.default_branch
+        ));
+    }
+
+    let dir_name = match name.map(str::trim) {
+        Some(n) => n.to_string(),
+        None => sanitize_branch_name(branch, &repo.default_branch),
+    };
+    let reserved = [".main", ".bare", ".git"];
+    if dir_name.is_empty()
+        || dir_name.chars().all(|c| c == '.')
+        || dir_name.contains('/')
+        || reserved.contains(&dir_name.as_str())
+    {
+        return Err("Invalid worktree name".to_string());
+    }
+
+    let new_path = local_path.join(&dir_name);
+    if !new_path.starts_with(&local_path) {
+        return Err("Invalid worktree path".to_string());
+    }
+    if new_path == Path::new(&worktree.path) && branch == worktree.branch {
+        return Err("Worktree is already at that location".to_string());
+    }
+
+    Ok((new_path, branch.to_string()))
+}
+
+/// Move a worktree and/or rename its branch, rolling back on failure
+/// 1. git worktree move
+/// 2. git branch -m (+ retarget upstream)
+/// 3. Re-link shared files from .main
+/// 4. Update DB row in a transaction
+pub async fn do_move_worktree(
+    state: Arc<AppState>,
+    repo: &Repository,
+    worktree: &Worktree,
+    new_path: &Path,
+    new_branch: &str,
+) -> anyhow::Result<()> {
+    let local_path = PathBuf::from(&repo.local_path);
+    let main_path = local_path.join(".main");
+    let old_path = PathBuf::from(&worktree.path);
+    let moving = new_path != old_path;
+    let renaming = new_branch != worktree.branch;
+
+    if moving && new_path.exists() {
+        anyhow::bail!("{} already exists", new_path.display());
+    }
+
+    let repo_id = repo.id.clone();
+    let remote = state
+        .db
+        .call(move |db| db.get_worktree_config(&repo_id))
+        .await?
+        .map(|c| c.upstream_remote)
+        .unwrap_or_else(|| "origin".to_string());
+
+    state.state.set_progress(
+        &worktree.path,
+        ProgressUpdate::new(ProgressPhase::Moving, "Moving worktree..."),
+    );
+
+    let result: anyhow::Result<()> = async {
+        // 1. Move directory
+        if moving {
+            state
+                .git
+                .move_worktree(&local_path, &old_path, new_path)
+                .await?;
+        }
+
+        // 2. Rename branch
+        if renaming {
+            state.state.set_progress(
+                &worktree.path,
+                ProgressUpdate::new(ProgressPhase::Moving, "Renaming branch..."),
+            );
+            if let Err(e) = state
+                .git
+                .rename_branch(new_path, &worktree.branch, new_branch, &remote)
+                .await
+            {
+                if moving {
+                    let _ = state.git.move_worktree(&local_path, new_path, &old_path).await;
+                }
+                return Err(e);
+            }
+        }
+
+        // 3. Update DB
+        let renamed = {
+            let (old, new, branch) = (worktree.path.clone(), new_path.to_string_lossy().to_string(), new_branch.to_string());
+            state.db.call(move |db| db.rename_worktree(&old, &new, &branch)).await
+        };
+        if let Err(e) = renamed {
+            if renaming {
+                let _ = state
+                    .git
+                    .rename_branch(new_path, new_branch, &worktree.branch, &remote)
+                    .await;
+            }
+            if moving {
+                let _ = state.git.move_worktree(&local_path, new_path, &old_path).await;
+            }
+            return Err(e);
+        }
+
+        // 4. Re-link shared files (symlinks are absolute, so only missing ones are recreated)
+        if moving
+            && let Err(e) = share_from_main(&state, &repo.id, &main_path, new_path).await
+        {
+            tracing::warn!("Failed to share files: {}", e);
+        }
+
+        Ok(())
+    }
+    .await;
+
+    state.state.clear_progress(&worktree.path);
+    state.state.on_db_change();
+
+    if result.is_ok() {
+        tracing::info!(
+            "Moved worktree {} -> {} ({})",
+            old_path.display(),
+            new_path.display(),
+            new_branch
+        );
+    }
+    result
+}
+
+async fn update_worktree_notes(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(path): axum::extract::Path<String>,
+    Json(notes): Json<WorktreeNotes>,
+) -> Result<Json<Worktree>, ApiError> {
+    let notes = notes
+        .normalize()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let worktree = state
+        .db
+        .call(move |db| {
+            if db.get_worktree(&path)?.is_none() {
+                return Ok(None);
+            }
+            db.update_worktree_notes(&path, &notes)?;
+            db.get_worktree(&path)
+        })
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Worktree not found".to_string()))?;
+    state.state.on_db_change();
+    Ok(Json(worktree))
+}
+
+#[derive(Debug, Deserialize)]
+struct LockWorktreeRequest {
+    path: String,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+async fn lock_worktree(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    Json(req): Json<LockWorktreeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (_, repo) = find_worktree(&state, &req.path).await?;
+
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    run_operation(
+        &state,
+        OperationKind::LockWorktree,
+        &req.path,
+        requester,
+        state
+            .git
+            .lock_worktree(Path::new(&repo.local_path), Path::new(&req.path), reason),
+    )
+    .await?;
+    state.state.on_db_change();
+
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+#[derive(Debug, Deserialize)]
+struct UnlockWorktreeRequest {
+    path: String,
+}
+
+async fn unlock_worktree(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    Json(req): Json<UnlockWorktreeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (_, repo) = find_worktree(&state, &req.path).await?;
+
+    run_operation(
+        &state,
+        OperationKind::UnlockWorktree,
+        &req.path,
+        requester,
+        state
+            .git
+            .unlock_worktree(Path::new(&repo.local_path), Path::new(&req.path)),
+    )
+    .await?;
+    state.state.on_db_change();
+
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+#[derive(Debug, Deserialize)]
+struct CancelInstallRequest {
+    path: String,
+}
+
+async fn cancel_worktree_install(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CancelInstallRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !cancel_install(&state, &req.path) {
+        return Err(ApiError::NotFound(format!(
+            "No install running in {}",
+            req.path
+        )));
+    }
+
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+/// Error message for operations refused because a worktree is locked
+pub fn lock_message(path: &str, reason: Option<&str>) -> String {
+    match reason {
+        Some(reason) => format!("Worktree {} is locked: {}", path, reason),
+        None => format!("Worktree {} is locked", path),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────
+// Retry
+// ─────────────────────────────────────────────────────────────
+
+async fn retry_clone(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repo = find_repository(&state, &id).await?;
+    let Some(error) = repo.error.clone() else {
+        return Err(ApiError::Conflict(format!(
+            "{}/{} has no failed clone to retry",
+            repo.username, repo.name
+        )));
+    };
+
+    let operation_id = spawn_clone_retry(&state, &repo, &error, requester).await?;
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "step": error.step,
+        "operation_id": operation_id
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct RetryWorktreeRequest {
+    path: String,
+}
+
+async fn retry_worktree(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    Json(req): Json<RetryWorktreeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (worktree, repo) = find_worktree(&state, &req.path).await?;
+    if repo.error.is_some() {
+        return Err(ApiError::Conflict(format!(
+            "Cloning {}/{} failed; retry the clone instead",
+            repo.username, repo.name
+        )));
+    }
+    let Some((step, skip_install)) = worktree_retry(&worktree) else {
+        return Err(ApiError::Conflict(format!(
+            "Worktree {} is {}, not failed",
+            req.path,
+            worktree.status.as_str()
+        )));
+    };
+
+    let operation_id = spawn_worktree_retry(&state, &repo, &worktree, step, skip_install, requester).await?;
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "step": step,
+        "operation_id": operation_id
+    })))
+}
+
+/// Resume a failed clone at the step that failed (runs in background)
+/// Returns the operation ID.
+pub async fn spawn_clone_retry(
+    state: &Arc<AppState>,
+    repo: &Repository,
+    error: &SetupError,
+    requester: Requester,
+) -> anyhow::Result<String> {
+    let repo_id = repo.id.clone();
+    let main_path = PathBuf::from(&repo.local_path).join(".main");
+    let main_path = main_path.to_string_lossy().to_string();
+    state
+        .db
+        .call(move |db| {
+            db.update_repository_error(&repo_id, None)?;
+            if let Some(main) = db.get_worktree(&main_path)? {
+                db.update_worktree_status(&main.path, WorktreeStatus::Creating, None, None)?;
+                db.update_worktree_error(&main.path, None)?;
+            }
+            Ok(())
+        })
+        .await?;
+    state.state.set_progress(
+        &repo.id,
+        ProgressUpdate::new(
+            ProgressPhase::Retrying,
+            format!("Retrying from {}...", error.step.as_str()),
+        ),
+    );
+    state.state.on_db_change();
+
+    let (state_clone, url, repo_id) = (Arc::clone(state), repo.clone_url.clone(), repo.id.clone());
+    let (skip_install, step) = (error.skip_install, error.step);
+    Ok(spawn_operation(
+        Arc::clone(state),
+        OperationKind::Retry,
+        repo.id.clone(),
+        requester,
+        async move { do_clone(state_clone, &url, &repo_id, skip_install, step).await },
+    )
+    .await)
+}
+
+/// Where a failed worktree resumes and whether it skips installing; None if it
+/// isn't failed. Worktrees that failed before errors were recorded start over,
+/// or at sharing files if git already created them.
+pub fn worktree_retry(worktree: &Worktree) -> Option<(SetupStep, bool)> {
+    if worktree.status != WorktreeStatus::Error {
+        return None;
+    }
+    Some(match &worktree.error {
+        Some(error) => (error.step, error.skip_install),
+        None if Path::new(&worktree.path).exists() => (SetupStep::Share, false),
+        None => (SetupStep::CreateWorktree, false),
+    })
+}
+
+/// Resume a failed worktree creation at `step` (runs in background)
+/// Returns the operation ID.
+pub async fn spawn_worktree_retry(
+    state: &Arc<AppState>,
+    repo: &Repository,
+    worktree: &Worktree,
+    step: SetupStep,
+    skip_install: bool,
+    requester: Requester,
+) -> anyhow::Result<String> {
+    let path = worktree.path.clone();
     state
-        .state
-        .set_progress(&worktree_path_str, Some("Creating worktree..."));
+        .db
+        .call(move |db| {
+            db.update_worktree_status(&path, WorktreeStatus::Creating, None, None)?;
+            db.update_worktree_error(&path, None)
+        })
+        .await?;
+    state.state.set_progress(
+        &worktree.path,
+        ProgressUpdate::new(ProgressPhase::Retrying, format!("Retrying from {}...", step.as_str())),
+    );
+    state.state.on_db_change();
+
+    let state_clone = Arc::clone(state);
+    let local_path = PathBuf::from(&repo.local_path);
+    let (worktree_path, branch) = (PathBuf::from(&worktree.path), worktree.branch.clone());
+    let repo_id = repo.id.clone();
+    Ok(spawn_operation(
+        Arc::clone(state),
+        OperationKind::Retry,
+        worktree.path.clone(),
+        requester,
+        async move {
+            do_create_worktree(
+                state_clone,
+                &local_path,
+                &worktree_path,
+                &branch,
+                &repo_id,
+                skip_install,
+                step,
+            )
+            .await
+        },
+    )
+    .await)
+}
+
+// ─────────────────────────────────────────────────────────────
+// Actions
+// ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct OpenRequest {
+    path: String,
+}
+
+async fn open_in_editor(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<OpenRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    // Open in VS Code
+    std::process::Command::new("code")
+        .arg(&req.path)
+        .spawn()
+        .map_err(|e| ApiError::Internal(format!("Failed to open editor: {}", e)))?;
+
+    // Counts as activity for stale detection (any path can be opened)
+    if state.db.call(move |db| db.touch_worktree_opened(&req.path)).await? {
+        state.state.on_db_change();
+    }
+
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+async fn refresh_repository(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    // Get repository
+    let repo = find_repository(&state, &id).await?;
+
+    // Spawn background task to fetch and update status
+    let state_clone = Arc::clone(&state);
+    let operation_id = spawn_operation(
+        Arc::clone(&state),
+        OperationKind::Refresh,
+        id.clone(),
+        requester,
+        async move { do_refresh(state_clone, &repo).await },
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "ok": true, "repo_id": id, "operation_id": operation_id })))
+}
+
+/// Fetch a repository and update every worktree's status (runs in background)
+/// Worktree statuses are refreshed even when the fetch fails; the fetch error
+/// is returned afterwards.
+pub async fn do_refresh(state: Arc<AppState>, repo: &Repository) -> anyhow::Result<()> {
+    let local_path = PathBuf::from(&repo.local_path);
+
+    // Fetch from remote
+    state.state.set_progress(&repo.id, ProgressUpdate::new(ProgressPhase::Fetching, "Fetching..."));
+    let fetched = state.git.fetch(&local_path, "origin").await;
+    // Deleted upstream branches show up in stale detection
+    if fetched.is_ok()
+        && let Err(e) = state.git.prune_remote(&local_path, "origin").await
+    {
+        tracing::warn!("Pruning origin for {} failed: {}", repo.name, e);
+    }
+
+    // Update git status of ready worktrees (creating/deleting/error keep theirs)
+    let repo_id = repo.id.clone();
+    if let Ok(worktrees) = state.db.call(move |db| db.list_worktrees(&repo_id)).await {
+        for wt in worktrees {
+            let wt_path = PathBuf::from(&wt.path);
+            if wt.status == WorktreeStatus::Ready
+                && let Ok(status) = state.git.get_status(&wt_path)
+            {
+                let path = wt.path.clone();
+                let _ = state
+                    .db
+                    .call(move |db| {
+                        if db.refresh_worktree_head(
+                            &path,
+                            status.head.as_deref(),
+                            status.commit_message.as_deref(),
+                        )? {
+                            db.update_worktree_git_status(
+                                &path,
+                                status.dirty,
+                                status.ahead,
+                                status.behind,
+                            )?;
+                        }
+                        Ok(())
+                    })
+                    .await;
+            }
+            check_dependencies(&state, &wt).await;
+            check_toolchains(&state, &wt.path).await;
+        }
+    }
+
+    // Update last_synced
+    let repo_id = repo.id.clone();
+    let _ = state.db.call(move |db| db.update_repository_synced(&repo_id)).await;
+
+    state.state.clear_progress(&repo.id);
+    state.state.on_db_change();
+
+    fetched.map_err(|e| anyhow::anyhow!("Fetch failed: {}", e))
+}
+
+// ─────────────────────────────────────────────────────────────
+// Maintenance
+// ─────────────────────────────────────────────────────────────
+
+async fn list_maintenance_runs(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<MaintenanceRun>>, ApiError> {
+    find_repository(&state, &id).await?;
+
+    Ok(Json(state.db.call(move |db| db.list_maintenance_runs(&id)).await?))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MaintenanceRequest {
+    /// Tasks to run (defaults to all)
+    #[serde(default)]
+    tasks: Vec<MaintenanceTask>,
+}
+
+async fn run_maintenance(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    body: Option<Json<MaintenanceRequest>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repo = find_repository(&state, &id).await?;
+
+    if state.maintenance_active.lock().unwrap().contains(&id) {
+        return Err(ApiError::Conflict(format!(
+            "Maintenance already running for {}",
+            repo.name
+        )));
+    }
+
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let tasks = if req.tasks.is_empty() {
+        MaintenanceTask::ALL.to_vec()
+    } else {
+        req.tasks
+    };
+
+    let state_clone = Arc::clone(&state);
+    let operation_id = spawn_operation(
+        Arc::clone(&state),
+        OperationKind::Maintenance,
+        id.clone(),
+        requester,
+        async move { maintenance_result(do_maintenance(state_clone, &repo.id, &tasks).await) },
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "ok": true, "repo_id": id, "operation_id": operation_id })))
+}
+
+/// Run maintenance tasks against a repository's `.bare` (runs in background)
+/// Each task's timing and before/after size is recorded even when it fails.
+pub async fn do_maintenance(
+    state: Arc<AppState>,
+    repo_id: &str,
+    tasks: &[MaintenanceTask],
+) -> anyhow::Result<Vec<MaintenanceRun>> {
+    let id = repo_id.to_string();
+    let repo = state
+        .db
+        .call(move |db| db.get_repository(&id))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Repository not found"))?;
+
+    if !state
+        .maintenance_active
+        .lock()
+        .unwrap()
+        .insert(repo_id.to_string())
+    {
+        anyhow::bail!("Maintenance already running for {}", repo.name);
+    }
+
+    let bare_path = PathBuf::from(&repo.local_path).join(".bare");
+
+    let result: anyhow::Result<Vec<MaintenanceRun>> = async {
+        let mut runs = Vec::new();
+        for (done, &task) in tasks.iter().enumerate() {
+            state.state.set_progress(
+                repo_id,
+                ProgressUpdate::new(
+                    ProgressPhase::Maintenance,
+                    format!("Maintenance ({})...", task.as_str()),
+                )
+                .steps(done, tasks.len()),
+            );
+
+            let id = repo_id.to_string();
+            let path = bare_path.clone();
+            let run = tokio::task::spawn_blocking(move || maintenance::run_task(&id, &path, task))
+                .await?;
+
+            if let Some(error) = &run.error {
+                tracing::warn!("Maintenance {} failed for {}: {}", task.as_str(), repo.name, error);
+            }
+            let run = state
+                .db
+                .call(move |db| db.record_maintenance_run(&run).map(|_| run))
+                .await?;
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+    .await;
+
+    state.maintenance_active.lock().unwrap().remove(repo_id);
+    state.state.clear_progress(repo_id);
+    state.state.on_db_change();
+
+    if let Ok(runs) = &result
+        && let (Some(first), Some(last)) = (runs.first(), runs.last())
+    {
+        tracing::info!(
+            "Maintenance complete: {} ({} -> {} bytes)",
+            repo.name,
+            first.size_before,
+            last.size_after
+        );
+    }
+
+    result
+}
+
+/// Collapse maintenance runs into one outcome (failed if any task failed)
+pub fn maintenance_result(result: anyhow::Result<Vec<MaintenanceRun>>) -> anyhow::Result<()> {
+    let failed: Vec<String> = result?
+        .into_iter()
+        .filter_map(|run| run.error.map(|e| format!("{}: {}", run.task.as_str(), e)))
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!("{}", failed.join("; "))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────
+// Hooks
+// ─────────────────────────────────────────────────────────────
+
+async fn list_hooks(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<Hook>>, ApiError> {
+    find_repository(&state, &id).await?;
+
+    Ok(Json(state.db.call(move |db| db.list_hooks(&id)).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetHookRequest {
+    event: HookEvent,
+    command: String,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+async fn set_hook(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(req): Json<SetHookRequest>,
+) -> Result<Json<Hook>, ApiError> {
+    find_repository(&state, &id).await?;
+
+    if req.command.trim().is_empty() {
+        return Err(ApiError::BadRequest("Hook command is empty".to_string()));
+    }
+    if req.timeout_secs == Some(0) {
+        return Err(ApiError::BadRequest("Hook timeout must be positive".to_string()));
+    }
+
+    let hook = Hook {
+        repo_id: id,
+        event: req.event,
+        command: req.command,
+        timeout_secs: req.timeout_secs.unwrap_or(hooks::DEFAULT_HOOK_TIMEOUT_SECS),
+    };
+    let hook = state
+        .db
+        .call(move |db| db.upsert_hook(&hook).map(|_| hook))
+        .await?;
+
+    Ok(Json(hook))
+}
+
+async fn delete_hook(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((id, event)): axum::extract::Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let event: HookEvent = event
+        .parse()
+        .map_err(|e: anyhow::Error| ApiError::BadRequest(e.to_string()))?;
+
+    state.db.call(move |db| db.delete_hook(&id, event)).await?;
+
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+// ─────────────────────────────────────────────────────────────
+// Worktree Config
+// ─────────────────────────────────────────────────────────────
+
+/// File sharing and upstream remote of a repository
+#[derive(Debug, Serialize)]
+pub struct WorktreeConfigResponse {
+    pub repo_id: String,
+    pub symlink_patterns: Vec<String>,
+    pub copy_patterns: Vec<String>,
+    pub upstream_remote: String,
+}
+
+impl From<WorktreeConfig> for WorktreeConfigResponse {
+    fn from(config: WorktreeConfig) -> Self {
+        Self {
+            repo_id: config.repo_id,
+            symlink_patterns: config.symlink_patterns,
+            copy_patterns: config.copy_patterns,
+            upstream_remote: config.upstream_remote,
+        }
+    }
+}
+
+/// Sharing result for one worktree
+#[derive(Debug, Serialize)]
+pub struct ShareResult {
+    pub path: String,
+    /// Files linked or copied
+    pub shared: usize,
+    pub error: Option<String>,
+}
+
+async fn get_worktree_config(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<WorktreeConfigResponse>, ApiError> {
+    find_repository(&state, &id).await?;
+
+    let config = state
+        .db
+        .call(move |db| {
+            let config = db.get_worktree_config(&id)?;
+            Ok(config.unwrap_or_else(|| WorktreeConfig::defaults(&id)))
+        })
+        .await?;
+    Ok(Json(config.into()))
+}
+
+async fn update_worktree_config(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(update): Json<WorktreeConfigUpdate>,
+) -> Result<Json<WorktreeConfigResponse>, ApiError> {
+    let repo = find_repository(&state, &id).await?;
+
+    let update = update
+        .normalize()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    if let Some(message) = unknown_remote(&state, &repo, &update) {
+        return Err(ApiError::BadRequest(message));
+    }
+
+    Ok(Json(save_worktree_config(&state, &id, update).await?))
+}
+
+/// Error message if the update names a remote the repository doesn't have
+pub fn unknown_remote(state: &AppState, repo: &Repository, update: &WorktreeConfigUpdate) -> Option<String> {
+    let remote = update.upstream_remote.as_deref()?;
     state
         .git
-        .create_worktree(local_path, worktree_path, branch, "origin")
+        .remote_url(Path::new(&repo.local_path), remote)
+        .is_none()
+        .then(|| format!("Remote {} is not configured in {}", remote, repo.name))
+}
+
+/// Apply a normalized worktree config change; new worktrees use it, existing
+/// ones only after `do_reapply_sharing`
+pub async fn save_worktree_config(
+    state: &AppState,
+    id: &str,
+    update: WorktreeConfigUpdate,
+) -> anyhow::Result<WorktreeConfigResponse> {
+    let id = id.to_string();
+    let config = state
+        .db
+        .call(move |db| {
+            let existing = db
+                .get_worktree_config(&id)?
+                .unwrap_or_else(|| WorktreeConfig::defaults(&id));
+            let config = WorktreeConfig {
+                symlink_patterns: update
+                    .symlink_patterns
+                    .unwrap_or(existing.symlink_patterns.clone()),
+                copy_patterns: update.copy_patterns.unwrap_or(existing.copy_patterns.clone()),
+                upstream_remote: update
+                    .upstream_remote
+                    .unwrap_or(existing.upstream_remote.clone()),
+                ..existing
+            };
+            db.upsert_worktree_config(&config)?;
+            Ok(config)
+        })
         .await?;
 
-    // 3. Share files from .main
-    state
-        .state
-        .set_progress(&worktree_path_str, Some("Sharing files..."));
-    if let Ok(Some(config)) = state.db.get_worktree_config(repo_id) {
-        let symlink_patterns: Vec<&str> = config
-            .symlink_patterns
-            .as_deref()
-            .unwrap_or("")
-            .split(',')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-        let copy_patterns: Vec<&str> = config
-            .copy_patterns
-            .as_deref()
-            .unwrap_or("")
-            .split(',')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        if !symlink_patterns.is_empty() || !copy_patterns.is_empty() {
-            if let Err(e) = share_files(main_path, worktree_path, &symlink_patterns, &copy_patterns)
-            {
-                tracing::warn!("Failed to share files: {}", e);
+    Ok(config.into())
+}
+
+async fn reapply_sharing(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<ShareResult>>, ApiError> {
+    let repo = find_repository(&state, &id).await?;
+
+    let results = do_reapply_sharing(&state, &repo, requester).await?;
+    Ok(Json(results))
+}
+
+/// Share files from `.main` into every other worktree again, e.g. after the
+/// patterns changed; files already present are left alone
+pub async fn do_reapply_sharing(
+    state: &Arc<AppState>,
+    repo: &Repository,
+    requester: Requester,
+) -> anyhow::Result<Vec<ShareResult>> {
+    let main_path = PathBuf::from(&repo.local_path).join(".main");
+    let repo_id = repo.id.clone();
+    let worktrees = state.db.call(move |db| db.list_worktrees(&repo_id)).await?;
+
+    let mut results = Vec::new();
+    let outcome = run_operation(state, OperationKind::ShareFiles, &repo.id, requester, async {
+        for wt in worktrees {
+            let path = PathBuf::from(&wt.path);
+            if path == main_path || !path.is_dir() {
+                continue;
             }
+            let shared = share_from_main(state, &repo.id, &main_path, &path).await;
+            results.push(match shared {
+                Ok(shared) => ShareResult {
+                    path: wt.path,
+                    shared,
+                    error: None,
+                },
+                Err(e) => ShareResult {
+                    path: wt.path,
+                    shared: 0,
+                    error: Some(e.to_string()),
+                },
+            });
         }
-    }
 
-    // 4. Install dependencies (unless skip_install)
-    if !skip_install {
-        let managers = detect_package_managers(worktree_path);
-        for pm in managers {
-            state.state.set_progress(
-                &worktree_path_str,
-                Some(&format!("Installing ({})...", pm.command())),
-            );
-            if let Err(e) = run_install(worktree_path, pm) {
-                tracing::warn!("Install {} failed: {}", pm.command(), e);
-            }
+        let failed = results.iter().filter(|r| r.error.is_some()).count();
+        if failed > 0 {
+            anyhow::bail!("Sharing failed in {} worktrees", failed);
         }
+        Ok(())
+    })
+    .await;
+
+    // Per-worktree errors are in the results; the operation records the failure
+    match outcome {
+        Err(e) if results.is_empty() => Err(e),
+        _ => Ok(results),
     }
+}
 
-    // 5. Get git status and update to ready
-    state
-        .state
-        .set_progress(&worktree_path_str, Some("Getting status..."));
-    let git_status = state.git.get_status(worktree_path)?;
-
-    state.db.update_worktree_status(
-        &worktree_path_str,
-        WorktreeStatus::Ready,
-        git_status.head.as_deref(),
-        git_status.commit_message.as_deref(),
-    )?;
-
-    state.db.update_worktree_git_status(
-        &worktree_path_str,
-        git_status.dirty,
-        git_status.ahead,
-        git_status.behind,
-    )?;
+// ─────────────────────────────────────────────────────────────
+// Install Settings
+// ─────────────────────────────────────────────────────────────
 
-    // Clear progress
-    state.state.set_progress(&worktree_path_str, None);
-    state.state.set_progress(repo_id, None);
-    state.state.on_db_change();
+#[derive(Debug, Serialize)]
+struct InstallConfigResponse {
+    /// Declared project directories (empty = discover)
+    install_paths: Vec<String>,
+    /// Repository override (None = `GROVE_CARGO_STEP`)
+    cargo_step: Option<CargoStep>,
+    /// What would be installed in `.main` with these settings
+    targets: Vec<InstallTarget>,
+}
 
-    Ok(())
+async fn get_install_config(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<InstallConfigResponse>, ApiError> {
+    let repo = find_repository(&state, &id).await?;
+
+    let config = state.db.call(move |db| db.get_worktree_config(&id)).await?;
+    let main_path = PathBuf::from(&repo.local_path).join(".main");
+    let targets = {
+        let (state, config) = (Arc::clone(&state), config.clone());
+        tokio::task::spawn_blocking(move || discover_targets(&state, config, &main_path))
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+    };
+
+    Ok(Json(InstallConfigResponse {
+        install_paths: config.as_ref().map(|c| c.install_paths.clone()).unwrap_or_default(),
+        cargo_step: config.and_then(|c| c.cargo_step),
+        targets,
+    }))
 }
 
-async fn delete_worktree(
+#[derive(Debug, Deserialize)]
+struct SetInstallConfigRequest {
+    #[serde(default)]
+    install_paths: Vec<String>,
+    #[serde(default)]
+    cargo_step: Option<CargoStep>,
+}
+
+async fn set_install_config(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(path): axum::extract::Path<String>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(req): Json<SetInstallConfigRequest>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    // Get worktree to find repo
-    let worktree = state
+    find_repository(&state, &id).await?;
+
+    let mut paths = Vec::new();
+    for path in &req.install_paths {
+        let path = path.trim().trim_end_matches('/');
+        if path.is_empty() {
+            continue;
+        }
+        if Path::new(path).is_absolute()
+            || Path::new(path).components().any(|c| c == std::path::Component::ParentDir)
+        {
+            return Err(ApiError::BadRequest(format!(
+                "Install path must be relative to the worktree: {}",
+                path
+            )));
+        }
+        paths.push(path.to_string());
+    }
+
+    state
         .db
-        .get_worktree(&path)?
-        .ok_or_else(|| ApiError::NotFound("Worktree not found".to_string()))?;
+        .call(move |db| {
+            let existing = db
+                .get_worktree_config(&id)?
+                .unwrap_or_else(|| WorktreeConfig::defaults(&id));
+            db.upsert_worktree_config(&WorktreeConfig {
+                install_paths: paths,
+                cargo_step: req.cargo_step,
+                ..existing
+            })
+        })
+        .await?;
 
-    // Get repository for bare path
-    let repo = state
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+// ─────────────────────────────────────────────────────────────
+// Install Logs
+// ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct LogsQuery {
+    #[serde(default)]
+    repo_id: Option<String>,
+    #[serde(default)]
+    worktree: Option<String>,
+}
+
+async fn list_install_logs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Json<Vec<InstallLog>>, ApiError> {
+    let logs = state
         .db
-        .get_repository(&worktree.repo_id)?
-        .ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))?;
+        .call(move |db| db.list_install_logs(query.repo_id.as_deref(), query.worktree.as_deref()))
+        .await?;
+    Ok(Json(logs))
+}
 
-    // Update status to deleting
-    state.db.update_worktree_status(
-        &path,
-        WorktreeStatus::Deleting,
-        worktree.head.as_deref(),
-        worktree.commit_message.as_deref(),
-    )?;
-    state.state.on_db_change();
+#[derive(Debug, Deserialize)]
+struct TailQuery {
+    /// Only return the last N lines
+    #[serde(default)]
+    tail: Option<usize>,
+}
 
-    // Spawn background task
-    let state_clone = Arc::clone(&state);
-    let path_clone = path.clone();
-    tokio::spawn(async move {
-        let local_path = PathBuf::from(&repo.local_path);
-        let worktree_path = PathBuf::from(&path_clone);
+async fn get_install_log(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(query): Query<TailQuery>,
+) -> Result<String, ApiError> {
+    find_install_log(&state, &id).await?;
 
-        // Try to remove git worktree
-        let result = state_clone
-            .git
-            .remove_worktree(&local_path, &worktree_path)
-            .await;
+    state
+        .logs
+        .read(&id, query.tail)
+        .map_err(|e| ApiError::NotFound(format!("Log file unavailable: {}", e)))
+}
 
-        if let Err(e) = &result {
-            // Log but continue - worktree might not exist in git
-            tracing::warn!("git worktree remove failed (may be orphaned): {}", e);
+/// Stream a log: existing lines first, then live lines until the run finishes
+/// Each event's id is its line number; a final `end` event carries the log record.
+async fn stream_install_log(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    find_install_log(&state, &id).await?;
+
+    // Subscribe before reading so no line falls between the two
+    let rx = state.logs.subscribe(&id);
+    let existing = state.logs.read(&id, None).unwrap_or_default();
+
+    let stream = async_stream::stream! {
+        let mut seen = 0;
+        for line in existing.lines() {
+            yield Ok(Event::default().id(seen.to_string()).data(line));
+            seen += 1;
         }
 
-        // Clean up directory if it exists
-        if worktree_path.exists() {
-            if let Err(e) = tokio::fs::remove_dir_all(&worktree_path).await {
-                tracing::warn!("Failed to remove worktree directory: {}", e);
+        if let Some(rx) = rx {
+            let mut live = BroadcastStream::new(rx);
+            while let Some(item) = live.next().await {
+                // Lagged receivers skip ahead; the full log stays on disk
+                let Ok((n, line)) = item else { continue };
+                if n >= seen {
+                    yield Ok(Event::default().id(n.to_string()).data(line));
+                    seen = n + 1;
+                }
             }
         }
 
-        // Always delete from DB (cleanup orphaned records)
-        let _ = state_clone.db.delete_worktree(&path_clone);
-        state_clone.state.on_db_change();
-    });
+        if let Ok(Some(log)) = state.db.call(move |db| db.get_install_log(&id)).await
+            && let Ok(data) = serde_json::to_string(&log)
+        {
+            yield Ok(Event::default().event("end").data(data));
+        }
+    };
 
-    Ok(Json(serde_json::json!({ "ok": true })))
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
-// ─────────────────────────────────────────────────────────────
-// Actions
-// ─────────────────────────────────────────────────────────────
+async fn find_install_log(state: &AppState, id: &str) -> Result<InstallLog, ApiError> {
+    let id = id.to_string();
+    state
+        .db
+        .call(move |db| db.get_install_log(&id))
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Log not found".to_string()))
+}
 
-#[derive(Debug, Deserialize)]
-struct OpenRequest {
-    path: String,
+/// Delete a repository's log files (records go with the repository row)
+pub async fn remove_repository_logs(state: &AppState, repo_id: &str) {
+    let repo_id = repo_id.to_string();
+    for log in state
+        .db
+        .call(move |db| db.list_install_logs(Some(&repo_id), None))
+        .await
+        .unwrap_or_default()
+    {
+        state.logs.remove(&log.id);
+    }
 }
 
-async fn open_in_editor(
-    State(_state): State<Arc<AppState>>,
-    Json(req): Json<OpenRequest>,
+// ─────────────────────────────────────────────────────────────
+// Disk Usage
+// ─────────────────────────────────────────────────────────────
+
+async fn refresh_all_disk_usage(
+    State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    // Open in VS Code
-    std::process::Command::new("code")
-        .arg(&req.path)
-        .spawn()
-        .map_err(|e| ApiError::Internal(format!("Failed to open editor: {}", e)))?;
+    let repos = state.db.call(|db| db.list_repositories()).await?;
+    let count = repos.len();
 
-    Ok(Json(serde_json::json!({ "ok": true })))
+    tokio::spawn(async move {
+        for repo in repos {
+            if let Err(e) = do_disk_usage(Arc::clone(&state), &repo.id).await {
+                tracing::warn!("Disk usage scan failed for {}: {}", repo.name, e);
+            }
+        }
+    });
+
+    Ok(Json(serde_json::json!({ "ok": true, "repositories": count })))
 }
 
-async fn refresh_repository(
+async fn refresh_disk_usage(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    // Get repository
-    let repo = state
-        .db
-        .get_repository(&id)?
-        .ok_or_else(|| ApiError::NotFound("Repository not found".to_string()))?;
+    let repo = find_repository(&state, &id).await?;
+
+    if state.disk_usage_active.lock().unwrap().contains(&id) {
+        return Err(ApiError::Conflict(format!(
+            "Disk usage scan already running for {}",
+            repo.name
+        )));
+    }
 
-    // Spawn background task to fetch and update status
     let state_clone = Arc::clone(&state);
     tokio::spawn(async move {
-        let local_path = PathBuf::from(&repo.local_path);
-
-        // Fetch from remote
-        state_clone
-            .state
-            .set_progress(&repo.id, Some("Fetching..."));
-        if let Err(e) = state_clone.git.fetch(&local_path, "origin").await {
-            tracing::error!("Fetch failed: {}", e);
-        }
-
-        // Update worktree statuses
-        if let Ok(worktrees) = state_clone.db.list_worktrees(&repo.id) {
-            for wt in worktrees {
-                let wt_path = PathBuf::from(&wt.path);
-                if let Ok(status) = state_clone.git.get_status(&wt_path) {
-                    let _ = state_clone.db.update_worktree_status(
-                        &wt.path,
-                        WorktreeStatus::Ready,
-                        status.head.as_deref(),
-                        status.commit_message.as_deref(),
-                    );
-                    let _ = state_clone.db.update_worktree_git_status(
-                        &wt.path,
-                        status.dirty,
-                        status.ahead,
-                        status.behind,
-                    );
-                }
+        if let Err(e) = do_disk_usage(state_clone, &repo.id).await {
+            tracing::error!("Disk usage scan failed for {}: {}", repo.name, e);
+        }
+    });
+
+    Ok(Json(serde_json::json!({ "ok": true, "repo_id": id })))
+}
+
+/// Recompute cached disk usage for a repository's `.bare` and each worktree
+/// Walking happens on the blocking pool; clients see results on the next state push.
+pub async fn do_disk_usage(state: Arc<AppState>, repo_id: &str) -> anyhow::Result<()> {
+    let id = repo_id.to_string();
+    let repo = state
+        .db
+        .call(move |db| db.get_repository(&id))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Repository not found"))?;
+
+    if !state
+        .disk_usage_active
+        .lock()
+        .unwrap()
+        .insert(repo_id.to_string())
+    {
+        anyhow::bail!("Disk usage scan already running for {}", repo.name);
+    }
+
+    let result: anyhow::Result<()> = async {
+        let bare_path = PathBuf::from(&repo.local_path).join(".bare");
+        let usage = {
+            let path = bare_path.clone();
+            tokio::task::spawn_blocking(move || disk::repository_usage(&path)).await?
+        };
+        let id = repo_id.to_string();
+        let bare_path = bare_path.to_string_lossy().to_string();
+        let worktrees = state
+            .db
+            .call(move |db| {
+                db.record_disk_usage(&id, &bare_path, &usage)?;
+                db.list_worktrees(&id)
+            })
+            .await?;
+
+        for wt in worktrees {
+            let path = PathBuf::from(&wt.path);
+            if !path.exists() {
+                continue;
             }
+            let usage = tokio::task::spawn_blocking(move || disk::worktree_usage(&path)).await?;
+            let id = repo_id.to_string();
+            state
+                .db
+                .call(move |db| db.record_disk_usage(&id, &wt.path, &usage))
+                .await?;
+        }
+        Ok(())
+    }
+    .await;
+
+    state.disk_usage_active.lock().unwrap().remove(repo_id);
+    state.state.on_db_change();
+
+    result
+}
+
+// ─────────────────────────────────────────────────────────────
+// Stale Worktrees
+// ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StaleQuery {
+    /// Idle days before a worktree is stale (default: `GROVE_STALE_DAYS`)
+    #[serde(default)]
+    pub days: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CleanupRequest {
+    #[serde(default)]
+    pub days: Option<u64>,
+    /// Only report what would be removed (the default)
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+impl Default for CleanupRequest {
+    fn default() -> Self {
+        Self {
+            days: None,
+            dry_run: true,
+        }
+    }
+}
+
+/// Stale threshold from a request, falling back to the configured one
+fn stale_after(state: &AppState, days: Option<u64>) -> Result<std::time::Duration, ApiError> {
+    match days {
+        Some(0) => Err(ApiError::BadRequest("days must be at least 1".to_string())),
+        Some(days) => Ok(std::time::Duration::from_secs(days * 86400)),
+        None => Ok(state.config.stale_after),
+    }
+}
+
+async fn list_stale_worktrees(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StaleQuery>,
+) -> Result<Json<Vec<StaleWorktree>>, ApiError> {
+    let after = stale_after(&state, query.days)?;
+    let git = Arc::clone(&state.git);
+    let found = state
+        .db
+        .call(move |db| stale::find_stale(db, &git, after))
+        .await?;
+    Ok(Json(found))
+}
+
+async fn cleanup_stale_worktrees(
+    State(state): State<Arc<AppState>>,
+    Client(requester): Client,
+    body: Option<Json<CleanupRequest>>,
+) -> Result<Json<CleanupReport>, ApiError> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let after = stale_after(&state, req.days)?;
+    let report = do_cleanup_stale(&state, after, req.dry_run, requester).await?;
+    Ok(Json(report))
+}
+
+/// Remove stale worktrees that are unlocked, clean and fully pushed
+/// Each one is checked again right before removal and goes through the normal
+/// delete path (pre-delete hook, operation record, post-delete hook).
+pub async fn do_cleanup_stale(
+    state: &Arc<AppState>,
+    stale_after: std::time::Duration,
+    dry_run: bool,
+    requester: Requester,
+) -> anyhow::Result<CleanupReport> {
+    let git = Arc::clone(&state.git);
+    let found = state
+        .db
+        .call(move |db| stale::find_stale(db, &git, stale_after))
+        .await?;
+
+    let mut report = CleanupReport {
+        dry_run,
+        ..Default::default()
+    };
+    for candidate in found.iter().filter(|s| s.removable) {
+        if dry_run {
+            report.removed.push(candidate.path.clone());
+            continue;
+        }
+        match remove_stale_worktree(state, &candidate.path, requester).await {
+            Ok(()) => report.removed.push(candidate.path.clone()),
+            Err(e) => report.failed.push(CleanupFailure {
+                path: candidate.path.clone(),
+                error: e.to_string(),
+            }),
         }
+    }
+    report.stale = found;
+
+    Ok(report)
+}
+
+async fn remove_stale_worktree(
+    state: &Arc<AppState>,
+    path: &str,
+    requester: Requester,
+) -> anyhow::Result<()> {
+    let wt_path = path.to_string();
+    let (worktree, repo) = state
+        .db
+        .call(move |db| {
+            let worktree = db
+                .get_worktree(&wt_path)?
+                .ok_or_else(|| anyhow::anyhow!("Worktree not found"))?;
+            let repo = db
+                .get_repository(&worktree.repo_id)?
+                .ok_or_else(|| anyhow::anyhow!("Repository not found"))?;
+            Ok((worktree, repo))
+        })
+        .await?;
+
+    // It may have been locked or touched since the scan
+    let locks = state.git.worktree_locks(Path::new(&repo.local_path));
+    if let Some(kept) = stale::keep_reason(&state.git, &worktree, &locks) {
+        anyhow::bail!("Kept: {}", kept);
+    }
+
+    let removal = begin_delete(state, repo, worktree, false).await?;
+    run_operation(
+        state,
+        OperationKind::DeleteWorktree,
+        path,
+        requester,
+        removal,
+    )
+    .await
+}
+
+// ─────────────────────────────────────────────────────────────
+// Operations
+// ─────────────────────────────────────────────────────────────
+
+/// Who sent a request, from the `X-Grove-Client` header (plain HTTP otherwise)
+/// MCP and the server's own jobs record themselves and can't be claimed.
+pub struct Client(pub Requester);
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for Client {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let requester = parts
+            .headers
+            .get(REQUESTER_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .filter(|r| matches!(r, Requester::Cli | Requester::Tui))
+            .unwrap_or_default();
+        Ok(Client(requester))
+    }
+}
+
+/// Run an operation to completion, recording it in the operation history
+pub async fn run_operation<T>(
+    state: &AppState,
+    kind: OperationKind,
+    target: &str,
+    requester: Requester,
+    fut: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let operation = Operation::start(kind, target, requester);
+    let id = operation.id.clone();
+    if let Err(e) = state.db.call(move |db| db.insert_operation(&operation)).await {
+        tracing::warn!("Failed to record operation: {}", e);
+    }
+
+    // Progress of the target belongs to this operation
+    state.state.start_operation(target, &id);
+    let result = fut.await;
+    state.state.finish_operation(target, &id);
+    record_result(&state.db, id, result.as_ref().err()).await;
+    result
+}
+
+/// Record how an operation ended in the history
+async fn record_result(db: &Arc<Database>, id: String, error: Option<&anyhow::Error>) {
+    let error = error.map(|e| format!("{:#}", e));
+    if let Err(e) = db.call(move |db| db.finish_operation(&id, error.as_deref())).await {
+        tracing::warn!("Failed to record operation result: {}", e);
+    }
+}
 
-        // Update last_synced
-        let _ = state_clone.db.update_repository_synced(&repo.id);
+/// Run an operation in the background, recording it in the operation history
+/// Returns the operation ID so clients can look up the outcome later.
+pub async fn spawn_operation(
+    state: Arc<AppState>,
+    kind: OperationKind,
+    target: String,
+    requester: Requester,
+    fut: impl Future<Output = anyhow::Result<()>> + Send + 'static,
+) -> String {
+    let operation = Operation::start(kind, &target, requester);
+    let id = operation.id.clone();
+    if let Err(e) = state.db.call(move |db| db.insert_operation(&operation)).await {
+        tracing::warn!("Failed to record operation: {}", e);
+    }
 
-        state_clone.state.set_progress(&repo.id, None);
-        state_clone.state.on_db_change();
+    state.state.start_operation(&target, &id);
+    let operation_id = id.clone();
+    tokio::spawn(async move {
+        let result = fut.await;
+        state.state.finish_operation(&target, &operation_id);
+        if let Err(e) = &result {
+            tracing::error!("{} failed for {}: {:#}", kind.as_str(), target, e);
+        }
+        record_result(&state.db, operation_id, result.as_ref().err()).await;
     });
+    id
+}
 
-    Ok(Json(serde_json::json!({ "ok": true, "repo_id": id })))
+async fn list_operations(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<OperationFilter>,
+) -> Result<Json<Vec<Operation>>, ApiError> {
+    let operations = state.db.call(move |db| db.list_operations(&filter)).await?;
+    Ok(Json(operations))
+}
+
+async fn get_operation(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Operation>, ApiError> {
+    state
+        .db
+        .call(move |db| db.get_operation(&id))
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound("Operation not found".to_string()))
 }
 
 // ─────────────────────────────────────────────────────────────
@@ -784,10 +3488,17 @@ enum ApiError {
     Internal(String),
     NotFound(String),
     BadRequest(String),
+    Conflict(String),
 }
 
 impl From<anyhow::Error> for ApiError {
     fn from(e: anyhow::Error) -> Self {
+        if let Some(vetoed) = e.downcast_ref::<DeleteVetoed>() {
+            return ApiError::Conflict(vetoed.to_string());
+        }
+        if let Some(locked) = e.downcast_ref::<WorktreeLocked>() {
+            return ApiError::Conflict(locked.to_string());
+        }
         ApiError::Internal(e.to_string())
     }
 }
@@ -798,9 +3509,97 @@ impl axum::response::IntoResponse for ApiError {
             ApiError::Internal(msg) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, msg),
             ApiError::NotFound(msg) => (axum::http::StatusCode::NOT_FOUND, msg),
             ApiError::BadRequest(msg) => (axum::http::StatusCode::BAD_REQUEST, msg),
+            ApiError::Conflict(msg) => (axum::http::StatusCode::CONFLICT, msg),
         };
 
         let body = serde_json::json!({ "error": message });
         (status, Json(body)).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository() -> Repository {
+        serde_json::from_value(serde_json::json!({
+            "id": "r1",
+            "provider": "github",
+            "username": "user",
+            "name": "repo",
+            "clone_url": "https://github.com/user/repo.git",
+            "local_path": "/code/user/repo",
+            "type": "bare",
+            "default_branch": "main",
+            "last_synced": 1,
+            "created_at": 1,
+        }))
+        .unwrap()
+    }
+
+    fn worktree(dir: &str, branch: &str) -> Worktree {
+        serde_json::from_value(serde_json::json!({
+            "path": format!("/code/user/repo/{}", dir),
+            "repo_id": "r1",
+            "branch": branch,
+            "status": "ready",
+            "dirty": false,
+            "ahead": 0,
+            "behind": 0,
+            "created_at": 1,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn renaming_the_branch_moves_the_directory_along() {
+        let target = resolve_move_target(
+            &repository(),
+            &worktree("feature--login", "feature/login"),
+            None,
+            Some("fix/login"),
+        );
+        assert_eq!(
+            target,
+            Ok((PathBuf::from("/code/user/repo/fix--login"), "fix/login".to_string()))
+        );
+    }
+
+    #[test]
+    fn name_overrides_the_directory() {
+        let wt = worktree("feature--login", "feature/login");
+
+        assert_eq!(
+            resolve_move_target(&repository(), &wt, Some(" login "), Some("fix/login")),
+            Ok((PathBuf::from("/code/user/repo/login"), "fix/login".to_string()))
+        );
+        assert_eq!(
+            resolve_move_target(&repository(), &wt, Some("login"), None),
+            Ok((PathBuf::from("/code/user/repo/login"), "feature/login".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        let repo = repository();
+        let wt = worktree("feature--login", "feature/login");
+        let err = |name: Option<&str>, branch: Option<&str>| {
+            resolve_move_target(&repo, &wt, name, branch).unwrap_err()
+        };
+
+        assert_eq!(
+            resolve_move_target(&repo, &worktree(".main", "main"), Some("x"), None).unwrap_err(),
+            "The main worktree cannot be moved"
+        );
+        assert_eq!(err(None, Some("  ")), "Invalid branch name");
+        assert_eq!(err(None, Some("..")), "Invalid branch name");
+        assert_eq!(err(None, Some("main")), "Cannot rename to the default branch (main)");
+        for name in ["", "..", ".main", ".bare", ".git", "a/b", "../other"] {
+            assert_eq!(err(Some(name), None), "Invalid worktree name", "{:?}", name);
+        }
+        assert_eq!(
+            err(Some("feature--login"), None),
+            "Worktree is already at that location"
+        );
+    }
+}
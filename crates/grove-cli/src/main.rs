@@ -7,8 +7,13 @@ mod updater;
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use grove_api::Server;
-use grove_core::{Config, Database};
+use grove_core::{
+    migrations, operations::REQUESTER_HEADER, rebuild, CargoStep, CleanupReport, Config, Database, DepsState, FullState, GitOps, HookEvent, LogHub,
+    LabelUpdate, MaintenanceTask, Operation, OperationFilter, OperationKind, OperationOutcome, Repository, RepositoryFilter,
+    Requester, StaleWorktree, Worktree, WorktreeConfigUpdate, WorktreeNotes,
+};
 use grove_tui::{ChatApp, Command};
+use std::path::Path;
 use std::process::Stdio;
 use std::time::Duration;
 use tokio::time::sleep;
@@ -38,19 +43,251 @@ enum Commands {
         repo: String,
         /// Branch name
         branch: String,
+        /// Why the worktree exists
+        #[arg(long)]
+        note: Option<String>,
+        /// Linked issue or ticket URL
+        #[arg(long)]
+        issue: Option<String>,
+        /// Who is working in it
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Set a worktree's note, linked issue or owner ("" clears a field)
+    Note {
+        /// Worktree path
+        path: String,
+        /// Why the worktree exists
+        note: Option<String>,
+        /// Linked issue or ticket URL
+        #[arg(long)]
+        issue: Option<String>,
+        /// Who is working in it
+        #[arg(long)]
+        owner: Option<String>,
     },
     /// Delete a worktree
     Delete {
         /// Worktree path
         path: String,
+        /// Delete even if the worktree is locked
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Lock a worktree so it cannot be deleted, moved or pruned
+    Lock {
+        /// Worktree path
+        path: String,
+        /// Why the worktree is locked
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+    /// Unlock a worktree
+    Unlock {
+        /// Worktree path
+        path: String,
+    },
+    /// Cancel the dependency install running in a worktree
+    Cancel {
+        /// Worktree path
+        path: String,
+    },
+    /// Retry a failed clone or worktree creation from the step that failed
+    Retry {
+        /// Repository ID or worktree path
+        target: String,
+    },
+    /// Move a worktree and/or rename its branch
+    Move {
+        /// Worktree path
+        path: String,
+        /// New branch name (directory follows unless --name is given)
+        #[arg(short, long)]
+        branch: Option<String>,
+        /// New directory name
+        #[arg(short, long)]
+        name: Option<String>,
     },
     /// Open worktree in editor
     Open {
         /// Path to open
         path: String,
     },
-    /// List repositories
-    List,
+    /// List repositories (favorites first)
+    List {
+        /// Only repositories with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        /// Only repositories in this group
+        #[arg(short, long)]
+        group: Option<String>,
+        /// Only favorites
+        #[arg(long)]
+        favorites: bool,
+    },
+    /// Find repositories and worktrees (names, URLs, branches, commit subjects, tags, notes)
+    Search {
+        /// Words to look for (typos are tolerated)
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Show at most N results (default: 20)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Add or remove tags on a repository
+    Tag {
+        /// Repository ID
+        repo: String,
+        /// Tags to add
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Remove the tags instead
+        #[arg(short, long)]
+        remove: bool,
+    },
+    /// Put a repository in a group
+    Group {
+        /// Repository ID
+        repo: String,
+        /// Group name (omit to remove the repository from its group)
+        group: Option<String>,
+    },
+    /// Mark a repository as a favorite (listed first)
+    Pin {
+        /// Repository ID
+        repo: String,
+    },
+    /// Remove a repository from the favorites
+    Unpin {
+        /// Repository ID
+        repo: String,
+    },
+    /// Run repository maintenance (gc, repack, commit-graph, multi-pack-index)
+    Maintenance {
+        /// Repository ID
+        repo: String,
+        /// Tasks to run (default: all)
+        #[arg(short, long = "task")]
+        tasks: Vec<String>,
+        /// Show the last run of each task instead of running
+        #[arg(long)]
+        status: bool,
+    },
+    /// Show or configure lifecycle hooks for a repository
+    Hook {
+        /// Repository ID
+        repo: String,
+        /// Event: post-clone, post-create, pre-delete, post-delete (omit to list all)
+        event: Option<String>,
+        /// Shell command to run (omit to show the hook)
+        command: Option<String>,
+        /// Timeout in seconds (default: 300)
+        #[arg(short, long)]
+        timeout: Option<u64>,
+        /// Remove the hook for EVENT
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Show or configure which projects are installed and what cargo runs
+    InstallConfig {
+        /// Repository ID
+        repo: String,
+        /// Project directory to install, relative to the worktree (repeatable; replaces discovery)
+        #[arg(short, long = "path")]
+        paths: Vec<String>,
+        /// Cargo step: fetch, check, build or none
+        #[arg(long)]
+        cargo: Option<String>,
+        /// Discover nested projects again and use the default cargo step
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Show or configure which files worktrees share with .main, and the upstream remote
+    WorktreeConfig {
+        /// Repository ID
+        repo: String,
+        /// Glob to symlink from .main, e.g. '.env.*' (repeatable; replaces the list)
+        #[arg(short, long = "symlink", conflicts_with = "no_symlinks")]
+        symlinks: Vec<String>,
+        /// Glob to copy from .main (repeatable; replaces the list)
+        #[arg(short, long = "copy", conflicts_with = "no_copies")]
+        copies: Vec<String>,
+        /// Symlink nothing
+        #[arg(long)]
+        no_symlinks: bool,
+        /// Copy nothing
+        #[arg(long)]
+        no_copies: bool,
+        /// Remote new branches track
+        #[arg(short, long)]
+        remote: Option<String>,
+        /// Share missing files into every existing worktree (after any change)
+        #[arg(long)]
+        share: bool,
+    },
+    /// Show the latest install log for a worktree
+    Logs {
+        /// Worktree path
+        path: String,
+        /// Only show the last N lines
+        #[arg(short = 'n', long)]
+        lines: Option<usize>,
+        /// Keep printing new lines until the install finishes
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Show disk usage per repository and worktree, largest first
+    Du {
+        /// Recompute in the background instead of showing cached sizes
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// List stale worktrees (idle, merged or upstream deleted), or clean them up
+    Stale {
+        /// Idle days before a worktree is stale (default: GROVE_STALE_DAYS or 30)
+        #[arg(short, long)]
+        days: Option<u64>,
+        /// Remove stale worktrees that are unlocked, clean and fully pushed (dry run unless --apply)
+        #[arg(long)]
+        cleanup: bool,
+        /// Actually remove them
+        #[arg(long, requires = "cleanup")]
+        apply: bool,
+    },
+    /// List deleted repositories, or restore or purge them
+    Trash {
+        /// Repository ID (omit to list the trash)
+        repo: Option<String>,
+        /// Move REPO back to its original location
+        #[arg(long, conflicts_with = "purge")]
+        restore: bool,
+        /// Delete REPO for good (without REPO: empty the trash)
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Show recent operations and how they ended
+    History {
+        /// Only this kind (clone, create_worktree, delete_worktree, refresh, ...)
+        #[arg(short, long)]
+        kind: Option<String>,
+        /// Only operations on this repository ID or worktree path
+        #[arg(short, long)]
+        target: Option<String>,
+        /// Only failed operations
+        #[arg(long)]
+        failed: bool,
+        /// Show the most recent N (default: 50)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// Show the database schema version and migrations
+    Schema,
+    /// Rebuild the database from the repositories under the code directory
+    Rebuild {
+        /// Only report differences (exit code 2 if there are any)
+        #[arg(long)]
+        check: bool,
+    },
     /// Start server only (no TUI)
     Server,
     /// Show server status
@@ -81,6 +318,15 @@ async fn main() -> Result<()> {
 
     // Initialize config and database
     let config = Config::from_env();
+
+    // Before opening: opening migrates, and refuses a newer database
+    if matches!(cli.command, Some(Commands::Schema)) {
+        return show_schema(&config);
+    }
+    // Before opening: a damaged database is moved aside instead
+    if let Some(Commands::Rebuild { check }) = cli.command {
+        return rebuild_database(&config, cli.port, check);
+    }
     let db = Database::open(&config)?;
 
     match cli.command {
@@ -102,24 +348,280 @@ async fn main() -> Result<()> {
             clone_repository(port, &url).await?;
         }
 
-        Some(Commands::Worktree { repo, branch }) => {
+        Some(Commands::Worktree {
+            repo,
+            branch,
+            note,
+            issue,
+            owner,
+        }) => {
+            let notes = WorktreeNotes {
+                note,
+                issue_url: issue,
+                owner,
+            };
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            create_worktree(port, &repo, &branch, &notes).await?;
+        }
+
+        Some(Commands::Note {
+            path,
+            note,
+            issue,
+            owner,
+        }) => {
+            let notes = WorktreeNotes {
+                note,
+                issue_url: issue,
+                owner,
+            };
+            if notes.is_empty() {
+                anyhow::bail!("Nothing to change: give a note, --issue or --owner");
+            }
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            annotate_worktree(port, &path, &notes).await?;
+        }
+
+        Some(Commands::Delete { path, force }) => {
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            delete_worktree(port, &path, force).await?;
+        }
+
+        Some(Commands::Lock { path, reason }) => {
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            lock_worktree(port, &path, reason.as_deref()).await?;
+        }
+
+        Some(Commands::Unlock { path }) => {
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            unlock_worktree(port, &path).await?;
+        }
+
+        Some(Commands::Cancel { path }) => {
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            cancel_install(port, &path).await?;
+        }
+
+        Some(Commands::Retry { target }) => {
             let port = ensure_server_running(cli.port, &config, &db).await?;
-            create_worktree(port, &repo, &branch).await?;
+            retry(port, &db, &target).await?;
         }
 
-        Some(Commands::Delete { path }) => {
+        Some(Commands::Move { path, branch, name }) => {
             let port = ensure_server_running(cli.port, &config, &db).await?;
-            delete_worktree(port, &path).await?;
+            move_worktree(port, &path, branch.as_deref(), name.as_deref()).await?;
         }
 
         Some(Commands::Open { path }) => {
-            open_in_editor(&path)?;
+            open_in_editor(&db, &path)?;
+        }
+
+        Some(Commands::List {
+            tag,
+            group,
+            favorites,
+        }) => {
+            let filter = RepositoryFilter {
+                tag,
+                group,
+                favorites,
+            };
+            list_repositories(&db, &filter)?;
+        }
+
+        Some(Commands::Tag { repo, tags, remove }) => {
+            let update = if remove {
+                LabelUpdate {
+                    remove_tags: tags,
+                    ..Default::default()
+                }
+            } else {
+                LabelUpdate {
+                    add_tags: tags,
+                    ..Default::default()
+                }
+            };
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            label_repository(port, &repo, &update).await?;
+        }
+
+        Some(Commands::Group { repo, group }) => {
+            let update = LabelUpdate {
+                group: Some(group.unwrap_or_default()),
+                ..Default::default()
+            };
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            label_repository(port, &repo, &update).await?;
+        }
+
+        Some(Commands::Pin { repo }) => {
+            let update = LabelUpdate {
+                favorite: Some(true),
+                ..Default::default()
+            };
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            label_repository(port, &repo, &update).await?;
+        }
+
+        Some(Commands::Unpin { repo }) => {
+            let update = LabelUpdate {
+                favorite: Some(false),
+                ..Default::default()
+            };
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            label_repository(port, &repo, &update).await?;
+        }
+
+        Some(Commands::Maintenance {
+            repo,
+            tasks,
+            status,
+        }) => {
+            if status {
+                maintenance_status(&db, &repo)?;
+            } else {
+                let port = ensure_server_running(cli.port, &config, &db).await?;
+                run_maintenance(port, &repo, &tasks).await?;
+            }
+        }
+
+        Some(Commands::Hook {
+            repo,
+            event,
+            command,
+            timeout,
+            remove,
+        }) => {
+            let event = event.map(|e| e.parse::<HookEvent>()).transpose()?;
+            match (event, command) {
+                (Some(event), _) if remove => {
+                    let port = ensure_server_running(cli.port, &config, &db).await?;
+                    remove_hook(port, &repo, event).await?;
+                }
+                (Some(event), Some(command)) => {
+                    let port = ensure_server_running(cli.port, &config, &db).await?;
+                    set_hook(port, &repo, event, &command, timeout).await?;
+                }
+                (event, _) => list_hooks(&db, &repo, event)?,
+            }
+        }
+
+        Some(Commands::InstallConfig {
+            repo,
+            paths,
+            cargo,
+            reset,
+        }) => {
+            let cargo = cargo.map(|c| c.parse::<CargoStep>()).transpose()?;
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            if paths.is_empty() && cargo.is_none() && !reset {
+                show_install_config(port, &repo).await?;
+            } else {
+                set_install_config(port, &repo, paths, cargo, reset).await?;
+            }
+        }
+
+        Some(Commands::WorktreeConfig {
+            repo,
+            symlinks,
+            copies,
+            no_symlinks,
+            no_copies,
+            remote,
+            share,
+        }) => {
+            let port = ensure_server_running(cli.port, &config, &db).await?;
+            let update = WorktreeConfigUpdate {
+                symlink_patterns: (no_symlinks || !symlinks.is_empty()).then_some(symlinks),
+                copy_patterns: (no_copies || !copies.is_empty()).then_some(copies),
+                upstream_remote: remote,
+            };
+            if update.is_empty() {
+                if !share {
+                    show_worktree_config(port, &repo).await?;
+                }
+            } else if !set_worktree_config(port, &repo, &update).await? {
+                return Ok(());
+            }
+            if share {
+                reapply_sharing(port, &repo).await?;
+            }
+        }
+
+        Some(Commands::Logs {
+            path,
+            lines,
+            follow,
+        }) => {
+            show_install_log(&config, &db, &path, lines, follow).await?;
+        }
+
+        Some(Commands::Du { refresh }) => {
+            if refresh {
+                let port = ensure_server_running(cli.port, &config, &db).await?;
+                refresh_disk_usage(port).await?;
+            } else {
+                disk_usage_report(&db)?;
+            }
+        }
+
+        Some(Commands::Stale {
+            days,
+            cleanup,
+            apply,
+        }) => {
+            if days == Some(0) {
+                anyhow::bail!("--days must be at least 1");
+            }
+            if cleanup {
+                let port = ensure_server_running(cli.port, &config, &db).await?;
+                cleanup_stale(port, days, !apply).await?;
+            } else {
+                let after = days
+                    .map(|d| Duration::from_secs(d * 86400))
+                    .unwrap_or(config.stale_after);
+                stale_report(&db, after)?;
+            }
+        }
+
+        Some(Commands::Trash {
+            repo,
+            restore,
+            purge,
+        }) => match (repo, restore, purge) {
+            (Some(repo), true, _) => {
+                let port = ensure_server_running(cli.port, &config, &db).await?;
+                restore_repository(port, &repo).await?;
+            }
+            (repo, _, true) => {
+                let port = ensure_server_running(cli.port, &config, &db).await?;
+                purge_trash(port, repo.as_deref()).await?;
+            }
+            (None, true, _) => anyhow::bail!("Which repository? grove trash <ID> --restore"),
+            (_, false, false) => list_trash(&config, &db)?,
+        },
+
+        Some(Commands::Search { query, limit }) => {
+            search(&db, &query.join(" "), limit.unwrap_or(grove_core::search::DEFAULT_LIMIT))?;
         }
 
-        Some(Commands::List) => {
-            list_repositories(&db)?;
+        Some(Commands::History {
+            kind,
+            target,
+            failed,
+            limit,
+        }) => {
+            let filter = OperationFilter {
+                kind: kind.map(|k| k.parse::<OperationKind>()).transpose()?,
+                target,
+                outcome: failed.then_some(OperationOutcome::Failed),
+                limit,
+            };
+            show_history(&db, &filter)?;
         }
 
+        Some(Commands::Schema | Commands::Rebuild { .. }) => unreachable!("handled before opening the database"),
+
         Some(Commands::Server) => {
             // Check for updates in background (ignore receiver for headless mode)
             let (applied, _) = updater::check_for_updates_background();
@@ -227,7 +729,7 @@ async fn run_tui(
     // Spawn command handler
     let system_tx_cmd = system_tx.clone();
     let handle = tokio::spawn(async move {
-        let client = reqwest::Client::new();
+        let client = api_client(Requester::Tui);
         let base_url = format!("http://localhost:{}", port);
 
         while let Some(cmd) = command_rx.recv().await {
@@ -285,44 +787,46 @@ async fn run_tui(
                         }
                     }
                 }
+                Command::Logs(path) => {
+                    let output = match fetch_latest_log(&client, &base_url, &path).await {
+                        Ok(Some(text)) => text,
+                        Ok(None) => format!("No install logs for {}", path),
+                        Err(e) => format!("Failed to fetch logs: {}", e),
+                    };
+                    let _ = system_tx_cmd.send(output).await;
+                }
+                Command::CancelInstall(path) => {
+                    let output = match client
+                        .post(format!("{}/api/worktree/cancel-install", base_url))
+                        .json(&serde_json::json!({ "path": path }))
+                        .send()
+                        .await
+                    {
+                        Ok(resp) if resp.status().is_success() => {
+                            format!("Cancelling install in {}", path)
+                        }
+                        Ok(_) => format!("No install running in {}", path),
+                        Err(e) => format!("Failed to cancel install: {}", e),
+                    };
+                    let _ = system_tx_cmd.send(output).await;
+                }
                 Command::Harvest(file) => {
                     let _ = system_tx_cmd.send(format!("Exporting to {}...", file)).await;
                     match client.get(format!("{}/api/state", base_url)).send().await {
                         Ok(resp) if resp.status().is_success() => {
-                            if let Ok(state) = resp.json::<serde_json::Value>().await {
-                                let mut lines = Vec::new();
-                                if let Some(repos) = state.get("repositories").and_then(|v| v.as_array()) {
-                                    for repo in repos {
-                                        let url = repo.get("clone_url").and_then(|v| v.as_str()).unwrap_or("");
-                                        let branches: Vec<String> = repo
-                                            .get("worktrees")
-                                            .and_then(|v| v.as_array())
-                                            .map(|wts| {
-                                                wts.iter()
-                                                    .filter_map(|wt| {
-                                                        let path = wt.get("path").and_then(|v| v.as_str())?;
-                                                        if path.ends_with("/.main") {
-                                                            None
-                                                        } else {
-                                                            wt.get("branch").and_then(|v| v.as_str()).map(String::from)
-                                                        }
-                                                    })
-                                                    .collect()
-                                            })
-                                            .unwrap_or_default();
-                                        let entry = serde_json::json!({
-                                            "url": url,
-                                            "worktrees": branches
-                                        });
-                                        lines.push(serde_json::to_string(&entry).unwrap_or_default());
+                            if let Ok(state) = resp.json::<FullState>().await {
+                                let lines: Vec<String> = state
+                                    .repositories
+                                    .iter()
+                                    .map(|r| SeedEntry::new(&r.repo, &r.worktrees))
+                                    .filter_map(|entry| serde_json::to_string(&entry).ok())
+                                    .collect();
+                                match std::fs::write(&file, lines.join("\n") + "\n") {
+                                    Ok(_) => {
+                                        let _ = system_tx_cmd.send(format!("Exported {} repos to {}", lines.len(), file)).await;
                                     }
-                                    match std::fs::write(&file, lines.join("\n") + "\n") {
-                                        Ok(_) => {
-                                            let _ = system_tx_cmd.send(format!("Exported {} repos to {}", lines.len(), file)).await;
-                                        }
-                                        Err(e) => {
-                                            let _ = system_tx_cmd.send(format!("Failed to write file: {}", e)).await;
-                                        }
+                                    Err(e) => {
+                                        let _ = system_tx_cmd.send(format!("Failed to write file: {}", e)).await;
                                     }
                                 }
                             }
@@ -336,7 +840,7 @@ async fn run_tui(
                     let _ = system_tx_cmd.send(format!("Importing from {}...", file)).await;
                     match std::fs::read_to_string(&file) {
                         Ok(content) => {
-                            let entries: Vec<serde_json::Value> = content
+                            let entries: Vec<SeedEntry> = content
                                 .lines()
                                 .filter(|line| !line.trim().is_empty())
                                 .filter_map(|line| serde_json::from_str(line).ok())
@@ -350,14 +854,12 @@ async fn run_tui(
                             let _ = system_tx_cmd.send(format!("Importing {} repositories...", entries.len())).await;
 
                             for entry in &entries {
-                                if let Some(url) = entry.get("url").and_then(|v| v.as_str()) {
-                                    let _ = system_tx_cmd.send(format!("Cloning {}...", url)).await;
-                                    let _ = client
-                                        .post(format!("{}/api/clone", base_url))
-                                        .json(&serde_json::json!({ "url": url }))
-                                        .send()
-                                        .await;
-                                }
+                                let _ = system_tx_cmd.send(format!("Cloning {}...", entry.url)).await;
+                                let _ = client
+                                    .post(format!("{}/api/clone", base_url))
+                                    .json(&entry.clone_request())
+                                    .send()
+                                    .await;
                             }
 
                             let _ = system_tx_cmd.send(format!("Started {} clones", entries.len())).await;
@@ -389,9 +891,22 @@ async fn run_server(port: u16, config: Config, db: Database) -> Result<()> {
     server.run(port).await
 }
 
+/// HTTP client that identifies itself to the server (recorded in operation history)
+fn api_client(requester: Requester) -> reqwest::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        REQUESTER_HEADER,
+        reqwest::header::HeaderValue::from_static(requester.as_str()),
+    );
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap_or_default()
+}
+
 /// Clone a repository via API
 async fn clone_repository(port: u16, url: &str) -> Result<()> {
-    let client = reqwest::Client::new();
+    let client = api_client(Requester::Cli);
     let resp = client
         .post(format!("http://localhost:{}/api/clone", port))
         .json(&serde_json::json!({ "url": url }))
@@ -409,11 +924,17 @@ async fn clone_repository(port: u16, url: &str) -> Result<()> {
 }
 
 /// Create a worktree via API
-async fn create_worktree(port: u16, repo: &str, branch: &str) -> Result<()> {
-    let client = reqwest::Client::new();
+async fn create_worktree(port: u16, repo: &str, branch: &str, notes: &WorktreeNotes) -> Result<()> {
+    let client = api_client(Requester::Cli);
     let resp = client
         .post(format!("http://localhost:{}/api/worktree", port))
-        .json(&serde_json::json!({ "repo_id": repo, "branch": branch }))
+        .json(&serde_json::json!({
+            "repo_id": repo,
+            "branch": branch,
+            "note": notes.note,
+            "issue_url": notes.issue_url,
+            "owner": notes.owner,
+        }))
         .send()
         .await?;
 
@@ -428,10 +949,11 @@ async fn create_worktree(port: u16, repo: &str, branch: &str) -> Result<()> {
 }
 
 /// Delete a worktree via API
-async fn delete_worktree(port: u16, path: &str) -> Result<()> {
-    let client = reqwest::Client::new();
+async fn delete_worktree(port: u16, path: &str, force: bool) -> Result<()> {
+    let client = api_client(Requester::Cli);
     let resp = client
         .delete(format!("http://localhost:{}/api/worktree/{}", port, path))
+        .query(&[("force", force)])
         .send()
         .await?;
 
@@ -445,152 +967,1253 @@ async fn delete_worktree(port: u16, path: &str) -> Result<()> {
     Ok(())
 }
 
-/// Open path in VS Code
-fn open_in_editor(path: &str) -> Result<()> {
-    std::process::Command::new("code")
-        .arg(path)
-        .spawn()
-        .context("Failed to open VS Code")?;
-    println!("Opened: {}", path);
+/// Lock a worktree via API
+async fn lock_worktree(port: u16, path: &str, reason: Option<&str>) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/worktree/lock", port))
+        .json(&serde_json::json!({ "path": path, "reason": reason }))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        println!("Worktree locked: {}", path);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
     Ok(())
 }
 
-/// List repositories from database
-fn list_repositories(db: &Database) -> Result<()> {
-    let repos = db.list_repositories()?;
-
-    if repos.is_empty() {
-        println!("No repositories. Use `grove clone <url>` to add one.");
-        return Ok(());
-    }
+/// Retry a failed clone (repository ID) or worktree creation (path) via API
+async fn retry(port: u16, db: &Database, target: &str) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let request = if db.get_repository(target)?.is_some() {
+        client.post(format!("http://localhost:{}/api/repositories/{}/retry", port, target))
+    } else {
+        // Worktrees are stored by absolute path
+        let path = std::fs::canonicalize(target).unwrap_or_else(|_| target.into());
+        client
+            .post(format!("http://localhost:{}/api/worktree/retry", port))
+            .json(&serde_json::json!({ "path": path }))
+    };
+    let resp = request.send().await?;
 
-    for repo in repos {
-        println!("{} - {}", repo.name, repo.clone_url);
-        let worktrees = db.list_worktrees(&repo.id)?;
-        for (i, wt) in worktrees.iter().enumerate() {
-            let marker = if i == 0 { "●" } else { "○" };
-            println!("  {} {} ({})", marker, wt.branch, wt.path);
-        }
+    if resp.status().is_success() {
+        let result: serde_json::Value = resp.json().await?;
+        println!(
+            "Retrying {} from {} (operation {})",
+            target,
+            result["step"].as_str().unwrap_or("the start"),
+            result["operation_id"].as_str().unwrap_or("")
+        );
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
     }
 
     Ok(())
 }
 
-/// Check server status
-fn check_status(port: u16) -> Result<()> {
-    if is_server_running(port) {
-        println!("Server running on http://localhost:{}", port);
+/// Change a worktree's note, issue link or owner via API
+async fn annotate_worktree(port: u16, path: &str, notes: &WorktreeNotes) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .patch(format!("http://localhost:{}/api/worktree/{}", port, path))
+        .json(notes)
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        let worktree: grove_core::Worktree = resp.json().await?;
+        println!("{} ({})", worktree.branch, worktree.path);
+        print_worktree_notes(&worktree);
     } else {
-        println!("Server not running");
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
     }
+
     Ok(())
 }
 
-/// Seed entry for harvest/grow
-#[derive(serde::Serialize, serde::Deserialize)]
-struct SeedEntry {
-    url: String,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    worktrees: Vec<String>,
+/// Owner, issue link and note under a worktree line
+fn print_worktree_notes(worktree: &grove_core::Worktree) {
+    if let Some(owner) = &worktree.owner {
+        println!("      owner: {}", owner);
+    }
+    if let Some(issue) = &worktree.issue_url {
+        println!("      issue: {}", issue);
+    }
+    if let Some(note) = &worktree.note {
+        for line in note.lines() {
+            println!("      {}", line);
+        }
+    }
 }
 
-/// Export repositories to seed.jsonl
-fn harvest_repositories(db: &Database, file: &str) -> Result<()> {
-    let repos = db.list_repositories()?;
+/// Change a repository's tags, group or favorite flag via API
+async fn label_repository(port: u16, repo: &str, update: &LabelUpdate) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .patch(format!("http://localhost:{}/api/repositories/{}", port, repo))
+        .json(update)
+        .send()
+        .await?;
 
-    if repos.is_empty() {
-        println!("No repositories to export.");
-        return Ok(());
+    if resp.status().is_success() {
+        let repo: grove_core::Repository = resp.json().await?;
+        let star = if repo.favorite { "★ " } else { "" };
+        let group = repo.group.as_deref().unwrap_or("no group");
+        let tags = if repo.tags.is_empty() {
+            "no tags".to_string()
+        } else {
+            repo.tags.join(", ")
+        };
+        println!("{}{} ({}; {})", star, repo.name, group, tags);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
     }
 
-    let mut lines = Vec::new();
-    for repo in &repos {
-        let worktrees = db.list_worktrees(&repo.id)?;
-        // Exclude .main worktree, only include additional branches
-        let branches: Vec<String> = worktrees
-            .iter()
-            .filter(|wt| !wt.path.ends_with("/.main"))
-            .map(|wt| wt.branch.clone())
-            .collect();
+    Ok(())
+}
 
-        let entry = SeedEntry {
-            url: repo.clone_url.clone(),
-            worktrees: branches,
-        };
-        lines.push(serde_json::to_string(&entry)?);
-    }
+/// Unlock a worktree via API
+async fn unlock_worktree(port: u16, path: &str) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/worktree/unlock", port))
+        .json(&serde_json::json!({ "path": path }))
+        .send()
+        .await?;
 
-    std::fs::write(file, lines.join("\n") + "\n")?;
-    println!("Exported {} repositories to {}", repos.len(), file);
+    if resp.status().is_success() {
+        println!("Worktree unlocked: {}", path);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
 
     Ok(())
 }
 
-/// Wait for a repository's main worktree to be ready via SSE stream
-async fn wait_for_worktree_ready(
-    client: &reqwest::Client,
-    base_url: &str,
-    clone_url: &str,
-) -> Result<Option<String>> {
-    use futures_util::StreamExt;
-    use tokio::time::timeout;
-
-    let response = client
-        .get(format!("{}/api/state", base_url))
-        .header("Accept", "text/event-stream")
+/// Cancel a running install via API
+async fn cancel_install(port: u16, path: &str) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/worktree/cancel-install", port))
+        .json(&serde_json::json!({ "path": path }))
         .send()
         .await?;
 
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    if resp.status().is_success() {
+        println!("Cancelling install in {}", path);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
 
-    // 3 minute timeout for clone
-    let result = timeout(Duration::from_secs(180), async {
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            buffer.push_str(&String::from_utf8_lossy(&chunk));
+    Ok(())
+}
 
-            // Process complete SSE events
-            while let Some(pos) = buffer.find("\n\n") {
-                let event = buffer[..pos].to_string();
-                buffer = buffer[pos + 2..].to_string();
+/// Move a worktree via API
+async fn move_worktree(
+    port: u16,
+    path: &str,
+    branch: Option<&str>,
+    name: Option<&str>,
+) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/worktree/move", port))
+        .json(&serde_json::json!({ "path": path, "branch": branch, "name": name }))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        let body: serde_json::Value = resp.json().await?;
+        let new_path = body.get("path").and_then(|v| v.as_str()).unwrap_or("");
+        println!("Worktree moved: {}", new_path);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Open path in VS Code
+fn open_in_editor(db: &Database, path: &str) -> Result<()> {
+    std::process::Command::new("code")
+        .arg(path)
+        .spawn()
+        .context("Failed to open VS Code")?;
+    println!("Opened: {}", path);
+
+    // Counts as activity for stale detection; worktrees are stored by absolute path
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.into());
+    let _ = db.touch_worktree_opened(&path.to_string_lossy());
+    Ok(())
+}
+
+/// Show repositories in the trash from database
+fn list_trash(config: &Config, db: &Database) -> Result<()> {
+    let repos = db.list_trashed_repositories()?;
+
+    if repos.is_empty() {
+        println!("Trash is empty.");
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    for repo in repos {
+        let trashed =
+            grove_core::TrashedRepository::new(&config.trash_dir, repo, config.trash_retention);
+        let deleted = trashed
+            .repository
+            .deleted_at
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let purge = match trashed.purge_at {
+            Some(at) if at <= now => ", purged soon".to_string(),
+            Some(at) => format!(", purged in {} days", (at - now) / 86_400_000 + 1),
+            None => String::new(),
+        };
+        println!(
+            "{}/{}  {}  deleted {}{}",
+            trashed.repository.username, trashed.repository.name, trashed.repository.id, deleted, purge
+        );
+        println!("    {} -> {}", trashed.trash_path, trashed.repository.local_path);
+    }
+
+    Ok(())
+}
+
+/// Restore a repository from the trash via API
+async fn restore_repository(port: u16, repo: &str) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/trash/{}/restore", port, repo))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        let body: serde_json::Value = resp.json().await?;
+        println!("Restored to {}", body["local_path"].as_str().unwrap_or_default());
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Purge one repository (or the whole trash) via API
+async fn purge_trash(port: u16, repo: Option<&str>) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let url = match repo {
+        Some(repo) => format!("http://localhost:{}/api/trash/{}", port, repo),
+        None => format!("http://localhost:{}/api/trash", port),
+    };
+    let resp = client.delete(url).send().await?;
+
+    if resp.status().is_success() {
+        let body: serde_json::Value = resp.json().await?;
+        match repo {
+            Some(repo) => println!("Purged: {}", repo),
+            None => println!("Purged {} repositories", body["purged"].as_u64().unwrap_or(0)),
+        }
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Show operation history from database, newest first
+fn show_history(db: &Database, filter: &OperationFilter) -> Result<()> {
+    let operations = db.list_operations(filter)?;
+
+    if operations.is_empty() {
+        println!("No operations recorded.");
+        return Ok(());
+    }
+
+    for op in operations {
+        let when = chrono::DateTime::from_timestamp_millis(op.started_at)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let outcome = match op.finished_at {
+            Some(finished) => format!(
+                "{} in {:.1}s",
+                op.outcome.as_str(),
+                (finished - op.started_at) as f64 / 1000.0
+            ),
+            None => op.outcome.as_str().to_string(),
+        };
+        println!(
+            "{}  {:<18} {:<7} {:<22} {}",
+            when,
+            op.kind.as_str(),
+            op.requester.as_str(),
+            outcome,
+            op.target
+        );
+        if let Some(error) = &op.error {
+            for line in error.lines().take(5) {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the schema version of the database without migrating it
+fn show_schema(config: &Config) -> Result<()> {
+    let Some(version) = migrations::schema_version(&config.db_path)? else {
+        println!("No database at {}", config.db_path.display());
+        return Ok(());
+    };
+
+    println!("Database: {}", config.db_path.display());
+    println!(
+        "Schema version: {} (this grove: {})",
+        version,
+        migrations::LATEST_VERSION
+    );
+    for migration in migrations::MIGRATIONS {
+        let marker = if migration.version <= version { "✓" } else { " " };
+        println!("  {} {:>3}  {}", marker, migration.version, migration.description);
+    }
+
+    if version > migrations::LATEST_VERSION {
+        println!("The database was written by a newer grove; upgrade grove to open it.");
+    } else if version < migrations::LATEST_VERSION {
+        println!(
+            "Pending migrations run on next start (backup in {}).",
+            config.data_dir.join("backups").display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Compare the database with the code directory, and fix it unless `check`
+fn rebuild_database(config: &Config, port: u16, check: bool) -> Result<()> {
+    if !check && is_server_running(port) {
+        anyhow::bail!(
+            "The server is running on port {}; stop it before rebuilding (`grove rebuild --check` works while it runs)",
+            port
+        );
+    }
+
+    // A check neither migrates nor backs up the database
+    let db = match if check { Database::open_read_only(config) } else { Database::open(config) } {
+        Ok(db) => db,
+        Err(e) if check => return Err(e),
+        Err(e) => {
+            // A newer grove's database isn't damaged; leave it alone
+            if let Ok(Some(version)) = migrations::schema_version(&config.db_path)
+                && version > migrations::LATEST_VERSION
+            {
+                return Err(e);
+            }
+            let moved = move_damaged_database(config)?;
+            eprintln!("Could not open the database ({}); moved it to {}", e, moved.display());
+            Database::open(config)?
+        }
+    };
+
+    let git = GitOps::new();
+    let report = if check {
+        rebuild::check(&db, &git, &config.code_dir)?
+    } else {
+        let op = Operation::start(
+            OperationKind::Rebuild,
+            &config.code_dir.to_string_lossy(),
+            Requester::Cli,
+        );
+        db.insert_operation(&op)?;
+        let result = rebuild::rebuild(&db, &git, &config.code_dir);
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        db.finish_operation(&op.id, error.as_deref())?;
+        result?
+    };
+
+    println!(
+        "Scanned {}: {} repositories, {} worktrees",
+        config.code_dir.display(),
+        report.repositories,
+        report.worktrees
+    );
+    for difference in &report.differences {
+        println!("  {}", difference);
+    }
+
+    let fixable = report.differences.iter().filter(|d| d.is_fixable()).count();
+    if report.differences.is_empty() {
+        println!("The database matches the disk.");
+    } else if check {
+        println!(
+            "{} differences ({} fixable with `grove rebuild`)",
+            report.differences.len(),
+            fixable
+        );
+        if fixable > 0 {
+            std::process::exit(2);
+        }
+    } else {
+        println!("Fixed {} of {} differences.", fixable, report.differences.len());
+    }
+
+    Ok(())
+}
+
+/// Move an unreadable database (and its WAL files) to the backups directory
+fn move_damaged_database(config: &Config) -> Result<std::path::PathBuf> {
+    let backups = config.data_dir.join("backups");
+    std::fs::create_dir_all(&backups)?;
+    let target = backups.join(format!(
+        "repos.damaged.{}.db",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+
+    std::fs::rename(&config.db_path, &target)
+        .with_context(|| format!("Failed to move {}", config.db_path.display()))?;
+    for suffix in ["-wal", "-shm"] {
+        let from = format!("{}{}", config.db_path.display(), suffix);
+        if Path::new(&from).exists() {
+            std::fs::rename(&from, format!("{}{}", target.display(), suffix))?;
+        }
+    }
+
+    Ok(target)
+}
+
+/// List repositories from database
+fn list_repositories(db: &Database, filter: &RepositoryFilter) -> Result<()> {
+    let repos: Vec<_> = db
+        .list_repositories()?
+        .into_iter()
+        .filter(|repo| filter.matches(repo))
+        .collect();
+
+    if repos.is_empty() {
+        if filter.is_empty() {
+            println!("No repositories. Use `grove clone <url>` to add one.");
+        } else {
+            println!("No matching repositories.");
+        }
+        return Ok(());
+    }
+
+    let git = GitOps::new();
+    for repo in repos {
+        let star = if repo.favorite { "★ " } else { "" };
+        let group = repo
+            .group
+            .as_ref()
+            .map(|g| format!(" [{}]", g))
+            .unwrap_or_default();
+        let tags: String = repo.tags.iter().map(|t| format!(" #{}", t)).collect();
+        println!("{}{} - {}{}{}", star, repo.name, repo.clone_url, group, tags);
+        if let Some(error) = &repo.error {
+            print_setup_error("  ", "clone", error);
+        }
+        let worktrees = db.list_worktrees(&repo.id)?;
+        let locks = git.worktree_locks(std::path::Path::new(&repo.local_path));
+        for (i, wt) in worktrees.iter().enumerate() {
+            let marker = if i == 0 { "●" } else { "○" };
+            let lock = match locks.get(&wt.path) {
+                Some(Some(reason)) => format!(" [locked: {}]", reason),
+                Some(None) => " [locked]".to_string(),
+                None => String::new(),
+            };
+            let deps = match wt.deps_state {
+                Some(DepsState::Stale) => " [deps stale]",
+                _ => "",
+            };
+            let toolchains: String = wt
+                .toolchains
+                .iter()
+                .filter(|t| t.is_mismatch())
+                .map(|t| {
+                    format!(
+                        " [{} {} != {}]",
+                        t.tool,
+                        t.installed.as_deref().unwrap_or("missing"),
+                        t.required
+                    )
+                })
+                .collect();
+            println!(
+                "  {} {} ({}){}{}{}",
+                marker, wt.branch, wt.path, lock, deps, toolchains
+            );
+            print_worktree_notes(wt);
+            if let Some(error) = wt.error.as_ref().filter(|_| repo.error.is_none()) {
+                print_setup_error("      ", "create", error);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print why a clone or worktree creation failed, with git's stderr
+fn print_setup_error(indent: &str, what: &str, error: &grove_core::SetupError) {
+    println!(
+        "{}✗ {} failed at {} ({}): {}",
+        indent,
+        what,
+        error.step.as_str(),
+        error.category.as_str(),
+        error.message
+    );
+    for line in error.stderr.iter().flat_map(|s| s.lines()) {
+        println!("{}    {}", indent, line);
+    }
+}
+
+/// Search repositories and worktrees in the database
+fn search(db: &Database, query: &str, limit: usize) -> Result<()> {
+    let hits = db.search(query, limit)?;
+    if hits.is_empty() {
+        println!("No matches for \"{}\".", query);
+        return Ok(());
+    }
+
+    for hit in hits {
+        let matched = hit.matched.join(", ");
+        match (&hit.path, &hit.branch) {
+            (Some(path), Some(branch)) => {
+                println!("{} {} ({}) - {}", hit.name, branch, path, matched)
+            }
+            _ => println!("{} ({}) - {}", hit.name, hit.repo_id, matched),
+        }
+    }
+
+    Ok(())
+}
+
+/// Start maintenance via API
+async fn run_maintenance(port: u16, repo: &str, tasks: &[String]) -> Result<()> {
+    let tasks = tasks
+        .iter()
+        .map(|t| t.parse::<MaintenanceTask>())
+        .collect::<Result<Vec<_>>>()?;
+
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/maintenance/{}", port, repo))
+        .json(&serde_json::json!({ "tasks": tasks }))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        println!("Maintenance started: {}", repo);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Show the last maintenance run of each task from database
+fn maintenance_status(db: &Database, repo_id: &str) -> Result<()> {
+    let repo = db
+        .get_repository(repo_id)?
+        .context("Repository not found")?;
+    let runs = db.list_maintenance_runs(&repo.id)?;
+
+    if runs.is_empty() {
+        println!("No maintenance has run for {}.", repo.name);
+        return Ok(());
+    }
+
+    println!("{}", repo.name);
+    for run in runs {
+        let when = chrono::DateTime::from_timestamp_millis(run.finished_at)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let outcome = match &run.error {
+            Some(e) => format!("failed: {}", e.lines().next().unwrap_or_default()),
+            None => format!(
+                "{} -> {}",
+                format_bytes(run.size_before),
+                format_bytes(run.size_after)
+            ),
+        };
+        println!("  {:<18} {}  {}", run.task.as_str(), when, outcome);
+    }
+
+    Ok(())
+}
+
+/// Show hooks for a repository from database
+fn list_hooks(db: &Database, repo_id: &str, event: Option<HookEvent>) -> Result<()> {
+    let repo = db
+        .get_repository(repo_id)?
+        .context("Repository not found")?;
+    let hooks: Vec<_> = db
+        .list_hooks(&repo.id)?
+        .into_iter()
+        .filter(|h| event.is_none_or(|e| h.event == e))
+        .collect();
+
+    if hooks.is_empty() {
+        println!("No hooks configured for {}.", repo.name);
+        return Ok(());
+    }
+
+    println!("{}", repo.name);
+    for hook in hooks {
+        println!(
+            "  {:<12} {}  (timeout {}s)",
+            hook.event.as_str(),
+            hook.command,
+            hook.timeout_secs
+        );
+    }
+
+    Ok(())
+}
+
+/// Configure a hook via API
+async fn set_hook(
+    port: u16,
+    repo: &str,
+    event: HookEvent,
+    command: &str,
+    timeout: Option<u64>,
+) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .put(format!("http://localhost:{}/api/hooks/{}", port, repo))
+        .json(&serde_json::json!({
+            "event": event,
+            "command": command,
+            "timeout_secs": timeout,
+        }))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        println!("Hook set: {}", event.as_str());
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Fetch a repository's install settings and discovered targets via API
+async fn fetch_install_config(port: u16, repo: &str) -> Result<serde_json::Value> {
+    let resp = api_client(Requester::Cli)
+        .get(format!("http://localhost:{}/api/install-config/{}", port, repo))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        let error: serde_json::Value = resp.json().await?;
+        anyhow::bail!("{}", error);
+    }
+    Ok(resp.json().await?)
+}
+
+/// Print install settings and what would be installed in `.main`
+async fn show_install_config(port: u16, repo: &str) -> Result<()> {
+    let config = fetch_install_config(port, repo).await?;
+
+    let paths: Vec<&str> = config["install_paths"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    if paths.is_empty() {
+        println!("Projects: discovered");
+    } else {
+        println!("Projects: {}", paths.join(", "));
+    }
+    println!(
+        "Cargo:    {}",
+        config["cargo_step"].as_str().unwrap_or("default (GROVE_CARGO_STEP)")
+    );
+
+    let targets = config["targets"].as_array().cloned().unwrap_or_default();
+    if targets.is_empty() {
+        println!("Nothing to install in .main.");
+    }
+    for target in targets {
+        let dir = target["dir"].as_str().filter(|d| !d.is_empty()).unwrap_or(".");
+        let manager = &target["manager"];
+        let args: Vec<&str> = manager["args"]
+            .as_array()
+            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        println!(
+            "  {:<24} {} {}",
+            dir,
+            manager["command"].as_str().unwrap_or(""),
+            args.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Update install settings via API, keeping settings that weren't given
+async fn set_install_config(
+    port: u16,
+    repo: &str,
+    paths: Vec<String>,
+    cargo: Option<CargoStep>,
+    reset: bool,
+) -> Result<()> {
+    let current = fetch_install_config(port, repo).await?;
+    let (paths, cargo) = if reset {
+        (Vec::new(), None)
+    } else {
+        let paths = if paths.is_empty() {
+            serde_json::from_value(current["install_paths"].clone()).unwrap_or_default()
+        } else {
+            paths
+        };
+        let cargo = cargo.or_else(|| serde_json::from_value(current["cargo_step"].clone()).ok());
+        (paths, cargo)
+    };
+
+    let resp = api_client(Requester::Cli)
+        .put(format!("http://localhost:{}/api/install-config/{}", port, repo))
+        .json(&serde_json::json!({ "install_paths": paths, "cargo_step": cargo }))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        show_install_config(port, repo).await?;
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Print a repository's sharing patterns and upstream remote
+fn print_worktree_config(config: &serde_json::Value) {
+    let list = |key: &str| {
+        let patterns: Vec<&str> = config[key]
+            .as_array()
+            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        if patterns.is_empty() {
+            "(none)".to_string()
+        } else {
+            patterns.join(", ")
+        }
+    };
+    println!("Symlink: {}", list("symlink_patterns"));
+    println!("Copy:    {}", list("copy_patterns"));
+    println!("Remote:  {}", config["upstream_remote"].as_str().unwrap_or(""));
+}
+
+/// Show worktree sharing settings via API
+async fn show_worktree_config(port: u16, repo: &str) -> Result<()> {
+    let resp = api_client(Requester::Cli)
+        .get(format!("http://localhost:{}/api/worktree-config/{}", port, repo))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        print_worktree_config(&resp.json().await?);
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Update worktree sharing settings via API; false if the update was refused
+async fn set_worktree_config(port: u16, repo: &str, update: &WorktreeConfigUpdate) -> Result<bool> {
+    let resp = api_client(Requester::Cli)
+        .patch(format!("http://localhost:{}/api/worktree-config/{}", port, repo))
+        .json(update)
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        print_worktree_config(&resp.json().await?);
+        Ok(true)
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+        Ok(false)
+    }
+}
+
+/// Share files from .main into existing worktrees via API
+async fn reapply_sharing(port: u16, repo: &str) -> Result<()> {
+    let resp = api_client(Requester::Cli)
+        .post(format!("http://localhost:{}/api/worktree-config/{}/share", port, repo))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+        return Ok(());
+    }
+
+    let results: Vec<serde_json::Value> = resp.json().await?;
+    if results.is_empty() {
+        println!("No worktrees besides .main.");
+    }
+    for result in results {
+        let path = result["path"].as_str().unwrap_or("");
+        match result["error"].as_str() {
+            Some(error) => println!("  ✗ {}: {}", path, error),
+            None => println!("  {:>3} shared  {}", result["shared"].as_u64().unwrap_or(0), path),
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a hook via API
+async fn remove_hook(port: u16, repo: &str, event: HookEvent) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .delete(format!(
+            "http://localhost:{}/api/hooks/{}/{}",
+            port,
+            repo,
+            event.as_str()
+        ))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        println!("Hook removed: {}", event.as_str());
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// Fetch the tail of the latest install log for a worktree via API
+async fn fetch_latest_log(
+    client: &reqwest::Client,
+    base_url: &str,
+    path: &str,
+) -> Result<Option<String>> {
+    let logs: Vec<grove_core::InstallLog> = client
+        .get(format!("{}/api/logs", base_url))
+        .query(&[("worktree", path.trim_end_matches('/'))])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let Some(log) = logs.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let text = client
+        .get(format!("{}/api/logs/{}", base_url, log.id))
+        .query(&[("tail", 40)])
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let status = match log.success {
+        Some(true) => "succeeded",
+        Some(false) => "failed",
+        None => "running",
+    };
+
+    Ok(Some(format!("{}\n-- install {}", text.trim_end(), status)))
+}
+
+/// Print the latest install log for a worktree (reads database and log file)
+async fn show_install_log(
+    config: &Config,
+    db: &Database,
+    path: &str,
+    lines: Option<usize>,
+    follow: bool,
+) -> Result<()> {
+    // Accept relative paths, but match the stored path as given first
+    let path = path.trim_end_matches('/');
+    let mut found = db.list_install_logs(None, Some(path))?;
+    if found.is_empty()
+        && let Ok(canonical) = std::fs::canonicalize(path)
+    {
+        found = db.list_install_logs(None, Some(&canonical.to_string_lossy()))?;
+    }
+    let Some(log) = found.into_iter().next() else {
+        println!("No install logs for {}.", path);
+        return Ok(());
+    };
+
+    let logs = LogHub::new(config.logs_dir.clone());
+    let contents = logs.read(&log.id, lines)?;
+    if !contents.is_empty() {
+        println!("{}", contents.trim_end());
+    }
+
+    if follow {
+        // Lines are written through, so polling the file is enough
+        let file = logs.path(&log.id);
+        let mut offset = std::fs::metadata(&file)?.len();
+        loop {
+            let finished = db
+                .get_install_log(&log.id)?
+                .is_none_or(|l| l.finished_at.is_some());
+            let len = std::fs::metadata(&file)?.len();
+            if len > offset {
+                let bytes = std::fs::read(&file)?;
+                print!("{}", String::from_utf8_lossy(&bytes[offset as usize..]));
+                offset = len;
+            }
+            if finished {
+                break;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    if let Some(log) = db.get_install_log(&log.id)? {
+        match log.success {
+            Some(true) => eprintln!("-- install succeeded"),
+            Some(false) => eprintln!("-- install failed"),
+            None => eprintln!("-- install running (use --follow)"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Start a disk usage scan via API
+async fn refresh_disk_usage(port: u16) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/disk-usage", port))
+        .send()
+        .await?;
+
+    if resp.status().is_success() {
+        println!("Disk usage scan started. Run `grove du` to see results.");
+    } else {
+        let error: serde_json::Value = resp.json().await?;
+        eprintln!("Error: {}", error);
+    }
+
+    Ok(())
+}
+
+/// List stale worktrees, longest idle first
+fn stale_report(db: &Database, stale_after: Duration) -> Result<()> {
+    let stale = grove_core::stale::find_stale(db, &GitOps::new(), stale_after)?;
+
+    if stale.is_empty() {
+        println!("No stale worktrees.");
+        return Ok(());
+    }
+
+    print_stale(&stale);
+    let removable = stale.iter().filter(|s| s.removable).count();
+    println!();
+    if removable > 0 {
+        println!(
+            "{} of {} can be removed: `grove stale --cleanup --apply`",
+            removable,
+            stale.len()
+        );
+    } else {
+        println!("None can be removed (locked, uncommitted changes or unpushed commits).");
+    }
+
+    Ok(())
+}
+
+/// Remove removable stale worktrees through the API (dry run unless `dry_run` is false)
+async fn cleanup_stale(port: u16, days: Option<u64>, dry_run: bool) -> Result<()> {
+    let client = api_client(Requester::Cli);
+    let resp = client
+        .post(format!("http://localhost:{}/api/stale/cleanup", port))
+        .json(&serde_json::json!({ "days": days, "dry_run": dry_run }))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        let error: serde_json::Value = resp.json().await?;
+        anyhow::bail!("{}", error);
+    }
+
+    let report: CleanupReport = resp.json().await?;
+    if report.stale.is_empty() {
+        println!("No stale worktrees.");
+        return Ok(());
+    }
+
+    print_stale(&report.stale);
+    println!();
+    if report.removed.is_empty() {
+        println!("Nothing to remove.");
+    } else if report.dry_run {
+        println!("Would remove (run with --apply):");
+    } else {
+        println!("Removed:");
+    }
+    for path in &report.removed {
+        println!("  {}", path);
+    }
+    for failure in &report.failed {
+        println!("  not removed: {} ({})", failure.path, failure.error);
+    }
+
+    Ok(())
+}
+
+fn print_stale(stale: &[StaleWorktree]) {
+    for s in stale {
+        let idle = match s.idle_days {
+            Some(days) => format!("idle {}d", days),
+            None => "no activity".to_string(),
+        };
+        let reasons: Vec<&str> = s.reasons.iter().map(|r| r.as_str()).collect();
+        println!(
+            "{} {} ({}) - {}: {}",
+            s.repo_name,
+            s.branch,
+            s.path,
+            idle,
+            reasons.join(", ")
+        );
+        if let Some(kept) = &s.kept {
+            println!("      kept: {}", kept);
+        }
+    }
+}
+
+/// Print cached disk usage from database, largest repositories and worktrees first
+fn disk_usage_report(db: &Database) -> Result<()> {
+    let repos = db.list_repositories()?;
+    let mut usage = db.list_disk_usage()?;
+
+    if usage.is_empty() {
+        println!("No disk usage computed yet. Run `grove du --refresh`.");
+        return Ok(());
+    }
+
+    let mut report = Vec::new();
+    for repo in repos {
+        let bare_path = std::path::Path::new(&repo.local_path).join(".bare");
+        let bare = usage.remove(bare_path.to_string_lossy().as_ref());
+        let mut worktrees: Vec<_> = db
+            .list_worktrees(&repo.id)?
+            .into_iter()
+            .map(|wt| {
+                let u = usage.remove(&wt.path);
+                (wt, u)
+            })
+            .collect();
+        worktrees.sort_by_key(|(_, u)| std::cmp::Reverse(u.as_ref().map_or(0, |u| u.total)));
+
+        let total = bare.as_ref().map_or(0, |u| u.total)
+            + worktrees
+                .iter()
+                .filter_map(|(_, u)| u.as_ref())
+                .map(|u| u.total)
+                .sum::<u64>();
+        report.push((repo, bare, worktrees, total));
+    }
+    report.sort_by_key(|(_, _, _, total)| std::cmp::Reverse(*total));
+
+    for (repo, bare, worktrees, total) in report {
+        println!("{:>10}  {}", format_bytes(total), repo.name);
+        if let Some(bare) = bare {
+            println!("{:>10}    .bare", format_bytes(bare.total));
+        }
+        for (wt, u) in worktrees {
+            match u {
+                Some(u) => println!(
+                    "{:>10}    {}  (tracked {}, deps {}, artifacts {})",
+                    format_bytes(u.total),
+                    wt.branch,
+                    format_bytes(u.tracked),
+                    format_bytes(u.dependencies),
+                    format_bytes(u.artifacts)
+                ),
+                None => println!("{:>10}    {}", "?", wt.branch),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Human-readable byte size
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Check server status
+fn check_status(port: u16) -> Result<()> {
+    if is_server_running(port) {
+        println!("Server running on http://localhost:{}", port);
+    } else {
+        println!("Server not running");
+    }
+    Ok(())
+}
+
+/// Seed entry for harvest/grow
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SeedEntry {
+    url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    worktrees: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    favorite: bool,
+}
+
+impl SeedEntry {
+    fn new(repo: &Repository, worktrees: &[Worktree]) -> Self {
+        Self {
+            url: repo.clone_url.clone(),
+            // Exclude .main worktree, only include additional branches
+            worktrees: worktrees
+                .iter()
+                .filter(|wt| !wt.path.ends_with("/.main"))
+                .map(|wt| wt.branch.clone())
+                .collect(),
+            tags: repo.tags.clone(),
+            group: repo.group.clone(),
+            favorite: repo.favorite,
+        }
+    }
+
+    /// Body for `POST /api/clone`, carrying the entry's labels
+    fn clone_request(&self) -> serde_json::Value {
+        serde_json::json!({
+            "url": self.url,
+            "tags": self.tags,
+            "group": self.group,
+            "favorite": self.favorite,
+        })
+    }
+}
+
+/// Export repositories to seed.jsonl
+fn harvest_repositories(db: &Database, file: &str) -> Result<()> {
+    let repos = db.list_repositories()?;
+
+    if repos.is_empty() {
+        println!("No repositories to export.");
+        return Ok(());
+    }
+
+    let mut lines = Vec::new();
+    for repo in &repos {
+        let worktrees = db.list_worktrees(&repo.id)?;
+        let entry = SeedEntry::new(repo, &worktrees);
+        lines.push(serde_json::to_string(&entry)?);
+    }
+
+    std::fs::write(file, lines.join("\n") + "\n")?;
+    println!("Exported {} repositories to {}", repos.len(), file);
+
+    Ok(())
+}
+
+/// Wait for a repository's main worktree to be ready via SSE stream
+async fn wait_for_worktree_ready(
+    client: &reqwest::Client,
+    base_url: &str,
+    clone_url: &str,
+) -> Result<Option<String>> {
+    use futures_util::StreamExt;
+    use tokio::time::timeout;
+
+    let response = client
+        .get(format!("{}/api/state", base_url))
+        .header("Accept", "text/event-stream")
+        .send()
+        .await?;
+
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+
+    // 3 minute timeout for clone
+    let result = timeout(Duration::from_secs(180), async {
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            // Process complete SSE events
+            while let Some(pos) = buffer.find("\n\n") {
+                let event = buffer[..pos].to_string();
+                buffer = buffer[pos + 2..].to_string();
 
                 // Parse SSE data line
-                if let Some(data) = event.strip_prefix("data: ") {
-                    if let Ok(state) = serde_json::from_str::<serde_json::Value>(data) {
-                        if let Some(repos) = state.get("repositories").and_then(|v| v.as_array()) {
-                            if let Some(repo) = repos.iter().find(|r| {
-                                r.get("clone_url")
+                if let Some(data) = event.strip_prefix("data: ")
+                    && let Ok(state) = serde_json::from_str::<serde_json::Value>(data)
+                    && let Some(repos) = state.get("repositories").and_then(|v| v.as_array())
+                    && let Some(repo) = repos.iter().find(|r| {
+                        r.get("clone_url")
+                            .and_then(|v| v.as_str())
+                            .map(|u| u == clone_url)
+                            .unwrap_or(false)
+                    })
+                {
+                    // Check if main worktree exists and is ready
+                    let has_ready_main = repo
+                        .get("worktrees")
+                        .and_then(|v| v.as_array())
+                        .map(|wts| {
+                            wts.iter().any(|wt| {
+                                wt.get("path")
                                     .and_then(|v| v.as_str())
-                                    .map(|u| u == clone_url)
+                                    .map(|p| p.ends_with("/.main"))
                                     .unwrap_or(false)
-                            }) {
-                                // Check if main worktree exists and is ready
-                                let has_ready_main = repo
-                                    .get("worktrees")
-                                    .and_then(|v| v.as_array())
-                                    .map(|wts| {
-                                        wts.iter().any(|wt| {
-                                            wt.get("path")
-                                                .and_then(|v| v.as_str())
-                                                .map(|p| p.ends_with("/.main"))
-                                                .unwrap_or(false)
-                                                && wt
-                                                    .get("status")
-                                                    .and_then(|v| v.as_str())
-                                                    .map(|s| s == "ready")
-                                                    .unwrap_or(false)
-                                        })
-                                    })
-                                    .unwrap_or(false);
-
-                                if has_ready_main {
-                                    return Ok::<Option<String>, anyhow::Error>(
-                                        repo.get("id").and_then(|v| v.as_str()).map(String::from),
-                                    );
-                                }
-                            }
-                        }
+                                    && wt
+                                        .get("status")
+                                        .and_then(|v| v.as_str())
+                                        .map(|s| s == "ready")
+                                        .unwrap_or(false)
+                            })
+                        })
+                        .unwrap_or(false);
+
+                    if has_ready_main {
+                        return Ok::<Option<String>, anyhow::Error>(
+                            repo.get("id").and_then(|v| v.as_str()).map(String::from),
+                        );
                     }
                 }
             }
@@ -614,7 +2237,7 @@ async fn grow_repositories(port: u16, file: &str) -> Result<()> {
     let entries: Vec<SeedEntry> = content
         .lines()
         .filter(|line| !line.trim().is_empty())
-        .map(|line| serde_json::from_str(line))
+        .map(serde_json::from_str)
         .collect::<Result<Vec<_>, _>>()
         .context("Failed to parse seed file")?;
 
@@ -625,7 +2248,7 @@ async fn grow_repositories(port: u16, file: &str) -> Result<()> {
 
     println!("Importing {} repositories via server API\n", entries.len());
 
-    let client = reqwest::Client::new();
+    let client = api_client(Requester::Cli);
     let base_url = format!("http://localhost:{}", port);
 
     for (i, entry) in entries.iter().enumerate() {
@@ -634,7 +2257,7 @@ async fn grow_repositories(port: u16, file: &str) -> Result<()> {
         // Clone repository
         let resp = client
             .post(format!("{}/api/clone", base_url))
-            .json(&serde_json::json!({ "url": entry.url }))
+            .json(&entry.clone_request())
             .send()
             .await?;
 
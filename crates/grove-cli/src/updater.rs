@@ -229,12 +229,12 @@ async fn download_update(_client: &reqwest::Client, version: &str) -> Result<()>
         anyhow::bail!("Extracted binary not found");
     }
 
-    if let Some(parent) = staged.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            log(&format!("ERROR: Failed to create staging dir {:?}: {}", parent, e));
-            let _ = fs::remove_dir_all(&tmp_dir);
-            anyhow::bail!("Failed to create staging dir: {}", e);
-        }
+    if let Some(parent) = staged.parent()
+        && let Err(e) = fs::create_dir_all(parent)
+    {
+        log(&format!("ERROR: Failed to create staging dir {:?}: {}", parent, e));
+        let _ = fs::remove_dir_all(&tmp_dir);
+        anyhow::bail!("Failed to create staging dir: {}", e);
     }
 
     if let Err(e) = fs::copy(&extracted, &staged) {
@@ -292,11 +292,11 @@ pub fn apply_staged_update() -> Result<bool> {
 
     // Backup current binary (in same dir to avoid cross-device issues)
     let backup = current.with_extension("old");
-    if backup.exists() {
-        if let Err(e) = fs::remove_file(&backup) {
-            log(&format!("ERROR: Failed to remove old backup: {}", e));
-            anyhow::bail!("Failed to remove old backup: {}", e);
-        }
+    if backup.exists()
+        && let Err(e) = fs::remove_file(&backup)
+    {
+        log(&format!("ERROR: Failed to remove old backup: {}", e));
+        anyhow::bail!("Failed to remove old backup: {}", e);
     }
 
     // Move current -> backup
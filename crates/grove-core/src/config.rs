@@ -1,6 +1,9 @@
 //! Configuration for grove
 
+use crate::install::CargoStep;
+use crate::stale::CleanupMode;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Grove configuration
 #[derive(Debug, Clone)]
@@ -11,6 +14,28 @@ pub struct Config {
     pub data_dir: PathBuf,
     /// Database file path
     pub db_path: PathBuf,
+    /// Directory for persisted operation logs
+    pub logs_dir: PathBuf,
+    /// Custom package manager detectors (JSON array, optional)
+    pub package_managers_path: PathBuf,
+    /// Default timeout for a package manager install
+    pub install_timeout: Duration,
+    /// What cargo runs on install unless a repository overrides it
+    pub cargo_step: CargoStep,
+    /// Run installs through mise/asdf/rustup when a worktree pins toolchain versions
+    pub use_version_manager: bool,
+    /// How often background maintenance runs per repository (None = disabled)
+    pub maintenance_interval: Option<Duration>,
+    /// How often disk usage is recomputed in the background (None = disabled)
+    pub disk_usage_interval: Option<Duration>,
+    /// Where deleted repositories are kept until purged (same filesystem as code_dir)
+    pub trash_dir: PathBuf,
+    /// How long deleted repositories stay in the trash (None = until purged by hand)
+    pub trash_retention: Option<Duration>,
+    /// Idle time after which a worktree counts as stale
+    pub stale_after: Duration,
+    /// What the scheduled stale worktree check does
+    pub stale_cleanup: CleanupMode,
 }
 
 impl Config {
@@ -28,11 +53,80 @@ impl Config {
 
         let data_dir = grove_root.join("data");
         let db_path = data_dir.join("repos.db");
+        let logs_dir = data_dir.join("logs");
+        let package_managers_path = grove_root.join("package-managers.json");
+
+        let install_timeout = std::env::var("GROVE_INSTALL_TIMEOUT_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|m| *m > 0)
+            .map(|m| Duration::from_secs(m * 60))
+            .unwrap_or(Duration::from_secs(30 * 60));
+
+        let cargo_step = std::env::var("GROVE_CARGO_STEP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default();
+
+        let use_version_manager = std::env::var("GROVE_USE_VERSION_MANAGER")
+            .is_ok_and(|v| matches!(v.as_str(), "1" | "true"));
+
+        // GROVE_MAINTENANCE_INTERVAL_HOURS=0 disables scheduled maintenance
+        let maintenance_hours = std::env::var("GROVE_MAINTENANCE_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(24);
+        let maintenance_interval =
+            (maintenance_hours > 0).then(|| Duration::from_secs(maintenance_hours * 3600));
+
+        // GROVE_DISK_USAGE_INTERVAL_HOURS=0 disables background recomputation
+        let disk_usage_hours = std::env::var("GROVE_DISK_USAGE_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(6);
+        let disk_usage_interval =
+            (disk_usage_hours > 0).then(|| Duration::from_secs(disk_usage_hours * 3600));
+
+        // Inside code_dir by default so moving to the trash is a rename
+        let trash_dir = std::env::var("GROVE_TRASH_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| code_dir.join(".grove-trash"));
+
+        // GROVE_TRASH_RETENTION_DAYS=0 keeps trashed repositories until purged
+        let trash_days = std::env::var("GROVE_TRASH_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30);
+        let trash_retention = (trash_days > 0).then(|| Duration::from_secs(trash_days * 86400));
+
+        // GROVE_STALE_DAYS=0 is ignored: everything would be stale
+        let stale_days = std::env::var("GROVE_STALE_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|d| *d > 0)
+            .unwrap_or(30);
+        let stale_after = Duration::from_secs(stale_days * 86400);
+
+        let stale_cleanup = std::env::var("GROVE_STALE_CLEANUP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default();
 
         Self {
             code_dir,
             data_dir,
             db_path,
+            logs_dir,
+            package_managers_path,
+            install_timeout,
+            cargo_step,
+            use_version_manager,
+            maintenance_interval,
+            disk_usage_interval,
+            trash_dir,
+            trash_retention,
+            stale_after,
+            stale_cleanup,
         }
     }
 
@@ -2,15 +2,48 @@
 //!
 //! See README.md for schema and pseudocode.
 
+use crate::disk::DiskUsage;
+use crate::failure::SetupError;
+use crate::hooks::{Hook, HookEvent};
+use crate::logs::InstallLog;
+use crate::maintenance::MaintenanceRun;
+use crate::migrations;
+use crate::operations::{Operation, OperationFilter, OperationOutcome};
+use crate::search::{self, IndexedRow, SearchHit, SearchHitKind};
+use crate::toolchain::ToolchainStatus;
 use crate::types::*;
 use crate::Config;
 use anyhow::Result;
-use rusqlite::{params, Connection, OptionalExtension};
-use std::sync::Mutex;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
-/// Database wrapper with connection pooling
+/// Read-only connections kept open alongside the writer
+const READERS: usize = 4;
+
+/// Prepared statements cached per connection
+const STATEMENT_CACHE: usize = 64;
+
+/// How long a connection waits for a lock held by another process
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Columns `repository_from_row` reads, for queries over `repositories r`
+const REPOSITORY_COLUMNS: &str = "r.id, r.provider, r.username, r.name, r.clone_url, r.local_path,
+    r.type, r.default_branch, r.last_synced, r.created_at, r.deleted_at,
+    (SELECT group_concat(tag, ',') FROM repository_tags WHERE repo_id = r.id),
+    (SELECT name FROM repository_groups WHERE repo_id = r.id),
+    EXISTS(SELECT 1 FROM repository_favorites WHERE repo_id = r.id) AS favorite, r.error";
+
+/// Database with a single writer and a pool of WAL readers
+/// Readers never wait for the writer (WAL), so state pushes don't queue behind
+/// installs recording progress; writes are serialized here instead of failing
+/// with SQLITE_BUSY.
 pub struct Database {
-    conn: Mutex<Connection>,
+    writer: Mutex<Connection>,
+    readers: Vec<Mutex<Connection>>,
+    next_reader: AtomicUsize,
 }
 
 impl Database {
@@ -18,85 +51,173 @@ impl Database {
     pub fn open(config: &Config) -> Result<Self> {
         config.ensure_dirs()?;
 
-        let conn = Connection::open(&config.db_path)?;
-        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+        let mut writer = Connection::open(&config.db_path)?;
+        writer.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+        writer.busy_timeout(BUSY_TIMEOUT)?;
+        writer.set_prepared_statement_cache_capacity(STATEMENT_CACHE);
+        migrations::migrate(&mut writer, &config.data_dir.join("backups"))?;
+
+        let readers = (0..READERS)
+            .map(|_| {
+                let conn = Connection::open_with_flags(
+                    &config.db_path,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                )?;
+                conn.busy_timeout(BUSY_TIMEOUT)?;
+                conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE);
+                Ok(Mutex::new(conn))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            writer: Mutex::new(writer),
+            readers,
+            next_reader: AtomicUsize::new(0),
+        })
+    }
+
+    /// Open an existing database without migrating or writing to it
+    /// Every connection is read-only, so writes fail instead of touching the
+    /// file; the schema must already be at `LATEST_VERSION`.
+    pub fn open_read_only(config: &Config) -> Result<Self> {
+        match migrations::schema_version(&config.db_path)? {
+            None => anyhow::bail!("No database at {}", config.db_path.display()),
+            Some(version) if version != migrations::LATEST_VERSION => anyhow::bail!(
+                "The database is at schema version {} (this grove uses {}); start grove once to migrate it",
+                version,
+                migrations::LATEST_VERSION
+            ),
+            Some(_) => {}
+        }
 
-        let db = Self {
-            conn: Mutex::new(conn),
+        let open = || -> Result<Connection> {
+            let conn = Connection::open_with_flags(
+                &config.db_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            conn.busy_timeout(BUSY_TIMEOUT)?;
+            conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE);
+            Ok(conn)
         };
-        db.init_schema()?;
-        Ok(db)
+
+        Ok(Self {
+            writer: Mutex::new(open()?),
+            readers: (0..READERS).map(|_| open().map(Mutex::new)).collect::<Result<_>>()?,
+            next_reader: AtomicUsize::new(0),
+        })
     }
 
-    /// Initialize database schema
-    fn init_schema(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute_batch(include_str!("schema.sql"))?;
-        Ok(())
+    /// Run `f` in one write transaction, rolled back if it fails
+    /// Writes from other threads meanwhile join the transaction, so this is
+    /// for callers that own the database, like `grove rebuild` with the
+    /// server stopped. Readers don't see the changes until it commits.
+    pub fn transaction<T>(&self, f: impl FnOnce(&Database) -> Result<T>) -> Result<T> {
+        self.writer().execute_batch("BEGIN IMMEDIATE")?;
+        let result = f(self).and_then(|value| {
+            self.writer().execute_batch("COMMIT")?;
+            Ok(value)
+        });
+        if result.is_err()
+            && let Err(e) = self.writer().execute_batch("ROLLBACK")
+        {
+            tracing::warn!("Failed to roll back: {}", e);
+        }
+        result
+    }
+
+    /// Run database calls on the blocking pool so async tasks don't stall a
+    /// runtime worker while SQLite works
+    pub async fn call<T, F>(self: &Arc<Self>, f: F) -> Result<T>
+    where
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let db = Arc::clone(self);
+        tokio::task::spawn_blocking(move || f(&db)).await?
+    }
+
+    /// A free reader, or the next one in turn if all are busy
+    fn reader(&self) -> MutexGuard<'_, Connection> {
+        let start = self.next_reader.fetch_add(1, Ordering::Relaxed);
+        for i in 0..self.readers.len() {
+            let slot = &self.readers[(start + i) % self.readers.len()];
+            if let Ok(conn) = slot.try_lock() {
+                return conn;
+            }
+        }
+        self.readers[start % self.readers.len()].lock().unwrap()
+    }
+
+    fn writer(&self) -> MutexGuard<'_, Connection> {
+        self.writer.lock().unwrap()
     }
 
     // ─────────────────────────────────────────────────────────────
     // Repositories
     // ─────────────────────────────────────────────────────────────
 
-    /// List all non-deleted repositories
+    /// List all non-deleted repositories (favorites first, then newest)
     pub fn list_repositories(&self) -> Result<Vec<Repository>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, provider, username, name, clone_url, local_path,
-                    type, default_branch, last_synced, created_at, deleted_at
-             FROM repositories
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
              WHERE deleted_at IS NULL
-             ORDER BY created_at DESC",
-        )?;
+             ORDER BY favorite DESC, created_at DESC",
+        ))?;
 
         let repos = stmt
-            .query_map([], |row| {
-                Ok(Repository {
-                    id: row.get(0)?,
-                    provider: row.get(1)?,
-                    username: row.get(2)?,
-                    name: row.get(3)?,
-                    clone_url: row.get(4)?,
-                    local_path: row.get(5)?,
-                    repo_type: row.get(6)?,
-                    default_branch: row.get(7)?,
-                    last_synced: row.get(8)?,
-                    created_at: row.get(9)?,
-                    deleted_at: row.get(10)?,
-                })
-            })?
+            .query_map([], |row| repository_from_row(row, 0))?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(repos)
     }
 
+    /// All non-deleted repositories with their worktrees in one query
+    /// Favorites first, then newest; worktrees oldest first (`.main` leads).
+    pub fn list_repositories_with_worktrees(&self) -> Result<Vec<(Repository, Vec<Worktree>)>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS},
+                    w.path, w.repo_id, w.branch, w.head, w.status, w.commit_message,
+                    w.dirty, w.ahead, w.behind, w.last_status_check, w.created_at, w.deleted_at,
+                    w.package_managers, w.install_status, w.deps_fingerprint, w.deps_state,
+                    w.toolchains, w.note, w.issue_url, w.owner, w.last_opened_at, w.error
+             FROM repositories r
+             LEFT JOIN worktrees w ON w.repo_id = r.id AND w.deleted_at IS NULL
+             WHERE r.deleted_at IS NULL
+             ORDER BY favorite DESC, r.created_at DESC, r.id, w.created_at ASC",
+        ))?;
+
+        let mut repos: Vec<(Repository, Vec<Worktree>)> = Vec::new();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            if repos.last().is_none_or(|(repo, _)| repo.id != id) {
+                repos.push((repository_from_row(row, 0)?, Vec::new()));
+            }
+            // No worktrees: the LEFT JOIN fills the worktree columns with NULL
+            if row.get::<_, Option<String>>(15)?.is_some()
+                && let Some((_, worktrees)) = repos.last_mut()
+            {
+                worktrees.push(worktree_from_row(row, 15)?);
+            }
+        }
+
+        Ok(repos)
+    }
+
     /// Get repository by ID
     pub fn get_repository(&self, id: &str) -> Result<Option<Repository>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, provider, username, name, clone_url, local_path,
-                    type, default_branch, last_synced, created_at, deleted_at
-             FROM repositories
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
              WHERE id = ? AND deleted_at IS NULL",
-        )?;
+        ))?;
 
         let repo = stmt
-            .query_row([id], |row| {
-                Ok(Repository {
-                    id: row.get(0)?,
-                    provider: row.get(1)?,
-                    username: row.get(2)?,
-                    name: row.get(3)?,
-                    clone_url: row.get(4)?,
-                    local_path: row.get(5)?,
-                    repo_type: row.get(6)?,
-                    default_branch: row.get(7)?,
-                    last_synced: row.get(8)?,
-                    created_at: row.get(9)?,
-                    deleted_at: row.get(10)?,
-                })
-            })
+            .query_row([id], |row| repository_from_row(row, 0))
             .optional()?;
 
         Ok(repo)
@@ -109,30 +230,15 @@ impl Database {
         username: &str,
         name: &str,
     ) -> Result<Option<Repository>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, provider, username, name, clone_url, local_path,
-                    type, default_branch, last_synced, created_at, deleted_at
-             FROM repositories
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
              WHERE provider = ? AND username = ? AND name = ? AND deleted_at IS NULL",
-        )?;
+        ))?;
 
         let repo = stmt
-            .query_row([provider, username, name], |row| {
-                Ok(Repository {
-                    id: row.get(0)?,
-                    provider: row.get(1)?,
-                    username: row.get(2)?,
-                    name: row.get(3)?,
-                    clone_url: row.get(4)?,
-                    local_path: row.get(5)?,
-                    repo_type: row.get(6)?,
-                    default_branch: row.get(7)?,
-                    last_synced: row.get(8)?,
-                    created_at: row.get(9)?,
-                    deleted_at: row.get(10)?,
-                })
-            })
+            .query_row([provider, username, name], |row| repository_from_row(row, 0))
             .optional()?;
 
         Ok(repo)
@@ -140,30 +246,15 @@ impl Database {
 
     /// Get repository by local path
     pub fn get_repository_by_path(&self, path: &str) -> Result<Option<Repository>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, provider, username, name, clone_url, local_path,
-                    type, default_branch, last_synced, created_at, deleted_at
-             FROM repositories
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
              WHERE local_path = ? AND deleted_at IS NULL",
-        )?;
+        ))?;
 
         let repo = stmt
-            .query_row([path], |row| {
-                Ok(Repository {
-                    id: row.get(0)?,
-                    provider: row.get(1)?,
-                    username: row.get(2)?,
-                    name: row.get(3)?,
-                    clone_url: row.get(4)?,
-                    local_path: row.get(5)?,
-                    repo_type: row.get(6)?,
-                    default_branch: row.get(7)?,
-                    last_synced: row.get(8)?,
-                    created_at: row.get(9)?,
-                    deleted_at: row.get(10)?,
-                })
-            })
+            .query_row([path], |row| repository_from_row(row, 0))
             .optional()?;
 
         Ok(repo)
@@ -171,7 +262,7 @@ impl Database {
 
     /// Insert new repository, returns ID
     pub fn insert_repository(&self, repo: &NewRepository) -> Result<String> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().timestamp_millis();
 
@@ -196,23 +287,133 @@ impl Database {
         Ok(id)
     }
 
+    /// Soft-delete a repository and its worktrees (moved to the trash)
+    pub fn trash_repository(&self, id: &str) -> Result<()> {
+        let mut conn = self.writer();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE worktrees SET deleted_at = ? WHERE repo_id = ? AND deleted_at IS NULL",
+            params![now, id],
+        )?;
+        tx.execute(
+            "UPDATE repositories SET deleted_at = ? WHERE id = ?",
+            params![now, id],
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Undo `trash_repository`
+    pub fn restore_repository(&self, id: &str) -> Result<()> {
+        let mut conn = self.writer();
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE worktrees SET deleted_at = NULL WHERE repo_id = ?",
+            params![id],
+        )?;
+        tx.execute(
+            "UPDATE repositories SET deleted_at = NULL WHERE id = ?",
+            params![id],
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Repositories in the trash, most recently deleted first
+    pub fn list_trashed_repositories(&self) -> Result<Vec<Repository>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
+             WHERE deleted_at IS NOT NULL
+             ORDER BY deleted_at DESC",
+        ))?;
+
+        let repos = stmt
+            .query_map([], |row| repository_from_row(row, 0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(repos)
+    }
+
+    /// Get a repository in the trash by ID
+    pub fn get_trashed_repository(&self, id: &str) -> Result<Option<Repository>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
+             WHERE id = ? AND deleted_at IS NOT NULL",
+        ))?;
+
+        let repo = stmt
+            .query_row([id], |row| repository_from_row(row, 0))
+            .optional()?;
+
+        Ok(repo)
+    }
+
+    /// Get a repository in the trash by provider/username/name or local path
+    /// (either would collide with a new clone)
+    pub fn find_trashed_repository(
+        &self,
+        provider: &str,
+        username: &str,
+        name: &str,
+        local_path: &str,
+    ) -> Result<Option<Repository>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {REPOSITORY_COLUMNS}
+             FROM repositories r
+             WHERE ((provider = ? AND username = ? AND name = ?) OR local_path = ?)
+               AND deleted_at IS NOT NULL",
+        ))?;
+
+        let repo = stmt
+            .query_row([provider, username, name, local_path], |row| {
+                repository_from_row(row, 0)
+            })
+            .optional()?;
+
+        Ok(repo)
+    }
+
     /// Hard delete repository and its worktrees
     pub fn delete_repository(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.writer();
 
+        let tx = conn.transaction()?;
         // Delete worktrees first (foreign key)
-        conn.execute("DELETE FROM worktrees WHERE repo_id = ?", params![id])?;
+        tx.execute("DELETE FROM worktrees WHERE repo_id = ?", params![id])?;
         // Delete worktree config
-        conn.execute("DELETE FROM worktree_config WHERE repo_id = ?", params![id])?;
+        tx.execute("DELETE FROM worktree_config WHERE repo_id = ?", params![id])?;
+        // Delete maintenance history
+        tx.execute("DELETE FROM maintenance_runs WHERE repo_id = ?", params![id])?;
+        // Delete cached disk usage of .bare and the worktrees
+        tx.execute("DELETE FROM disk_usage WHERE repo_id = ?", params![id])?;
+        // Delete hooks
+        tx.execute("DELETE FROM hooks WHERE repo_id = ?", params![id])?;
+        // Delete install log records (the files are removed by the caller)
+        tx.execute("DELETE FROM install_logs WHERE repo_id = ?", params![id])?;
+        // Delete labels
+        tx.execute("DELETE FROM repository_tags WHERE repo_id = ?", params![id])?;
+        tx.execute("DELETE FROM repository_groups WHERE repo_id = ?", params![id])?;
+        tx.execute("DELETE FROM repository_favorites WHERE repo_id = ?", params![id])?;
         // Delete repository
-        conn.execute("DELETE FROM repositories WHERE id = ?", params![id])?;
+        tx.execute("DELETE FROM repositories WHERE id = ?", params![id])?;
+        tx.commit()?;
 
         Ok(())
     }
 
     /// Update last_synced timestamp
     pub fn update_repository_synced(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
         let now = chrono::Utc::now().timestamp_millis();
 
         conn.execute(
@@ -223,9 +424,22 @@ impl Database {
         Ok(())
     }
 
+    /// Record why the clone failed, or clear it (None)
+    pub fn update_repository_error(&self, id: &str, error: Option<&SetupError>) -> Result<()> {
+        let conn = self.writer();
+        let error = error.map(serde_json::to_string).transpose()?;
+
+        conn.execute(
+            "UPDATE repositories SET error = ? WHERE id = ?",
+            params![error, id],
+        )?;
+
+        Ok(())
+    }
+
     /// Update default branch
     pub fn update_repository_default_branch(&self, id: &str, default_branch: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
 
         conn.execute(
             "UPDATE repositories SET default_branch = ? WHERE id = ?",
@@ -235,39 +449,187 @@ impl Database {
         Ok(())
     }
 
+    /// Update clone URL (origin changed on disk)
+    pub fn update_repository_clone_url(&self, id: &str, clone_url: &str) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "UPDATE repositories SET clone_url = ? WHERE id = ?",
+            params![clone_url, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Change a repository's tags, group and favorite flag (unset fields are kept)
+    pub fn update_repository_labels(&self, id: &str, update: &LabelUpdate) -> Result<()> {
+        let mut conn = self.writer();
+        let tx = conn.transaction()?;
+
+        if let Some(tags) = &update.tags {
+            tx.execute("DELETE FROM repository_tags WHERE repo_id = ?", params![id])?;
+            for tag in tags {
+                tx.execute(
+                    "INSERT OR IGNORE INTO repository_tags (repo_id, tag) VALUES (?, ?)",
+                    params![id, tag],
+                )?;
+            }
+        }
+        for tag in &update.add_tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO repository_tags (repo_id, tag) VALUES (?, ?)",
+                params![id, tag],
+            )?;
+        }
+        for tag in &update.remove_tags {
+            tx.execute(
+                "DELETE FROM repository_tags WHERE repo_id = ? AND tag = ?",
+                params![id, tag],
+            )?;
+        }
+
+        match update.group.as_deref() {
+            Some("") => {
+                tx.execute("DELETE FROM repository_groups WHERE repo_id = ?", params![id])?;
+            }
+            Some(group) => {
+                tx.execute(
+                    "INSERT OR REPLACE INTO repository_groups (repo_id, name) VALUES (?, ?)",
+                    params![id, group],
+                )?;
+            }
+            None => {}
+        }
+
+        match update.favorite {
+            Some(true) => {
+                tx.execute(
+                    "INSERT OR IGNORE INTO repository_favorites (repo_id, pinned_at) VALUES (?, ?)",
+                    params![id, chrono::Utc::now().timestamp_millis()],
+                )?;
+            }
+            Some(false) => {
+                tx.execute("DELETE FROM repository_favorites WHERE repo_id = ?", params![id])?;
+            }
+            None => {}
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Tag and group names with how many repositories use them
+    pub fn list_labels(&self) -> Result<LabelCounts> {
+        let conn = self.reader();
+        let mut labels = LabelCounts::default();
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT t.tag, COUNT(*) FROM repository_tags t
+             JOIN repositories r ON r.id = t.repo_id AND r.deleted_at IS NULL
+             GROUP BY t.tag ORDER BY t.tag",
+        )?;
+        labels.tags = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT g.name, COUNT(*) FROM repository_groups g
+             JOIN repositories r ON r.id = g.repo_id AND r.deleted_at IS NULL
+             GROUP BY g.name ORDER BY g.name",
+        )?;
+        labels.groups = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(labels)
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Search
+    // ─────────────────────────────────────────────────────────────
+
+    /// Repositories and worktrees matching `query`, best first (see `search.rs`)
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let terms = search::terms(query);
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        if let Some(expression) = search::match_expression(&terms) {
+            let rows = self.search_rows(Some(&expression))?;
+            let hits = search::rank(rows, &terms, limit);
+            if !hits.is_empty() {
+                return Ok(hits);
+            }
+        }
+        // Nothing shares a trigram (typos) or the terms are too short: rank every row
+        let rows = self.search_rows(None)?;
+        Ok(search::rank(rows, &terms, limit))
+    }
+
+    /// Rows of the search index, optionally narrowed by an FTS5 MATCH expression
+    fn search_rows(&self, expression: Option<&str>) -> Result<Vec<IndexedRow>> {
+        let conn = self.reader();
+        let map = |row: &rusqlite::Row| -> rusqlite::Result<IndexedRow> {
+            let kind: String = row.get(0)?;
+            let mut fields = Vec::with_capacity(search::FIELDS.len());
+            for (i, field) in search::FIELDS.iter().enumerate() {
+                let text: Option<String> = row.get(3 + i)?;
+                fields.push((*field, text.unwrap_or_default()));
+            }
+            Ok(IndexedRow {
+                kind: if kind == "worktree" {
+                    SearchHitKind::Worktree
+                } else {
+                    SearchHitKind::Repository
+                },
+                repo_id: row.get(1)?,
+                path: row.get(2)?,
+                fields,
+            })
+        };
+
+        let rows = match expression {
+            Some(expression) => {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT kind, repo_id, path, name, url, branch, subject, labels, note, issue, owner
+                     FROM search_index WHERE search_index MATCH ?
+                     ORDER BY rank LIMIT ?",
+                )?;
+                stmt.query_map(params![expression, search::CANDIDATE_LIMIT], map)?
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            None => {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT kind, repo_id, path, name, url, branch, subject, labels, note, issue, owner
+                     FROM search_index",
+                )?;
+                stmt.query_map([], map)?.collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(rows)
+    }
+
     // ─────────────────────────────────────────────────────────────
     // Worktrees
     // ─────────────────────────────────────────────────────────────
 
     /// List worktrees for a repository
     pub fn list_worktrees(&self, repo_id: &str) -> Result<Vec<Worktree>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
             "SELECT path, repo_id, branch, head, status, commit_message,
-                    dirty, ahead, behind, last_status_check, created_at, deleted_at
+                    dirty, ahead, behind, last_status_check, created_at, deleted_at,
+                    package_managers, install_status, deps_fingerprint, deps_state,
+                    toolchains, note, issue_url, owner, last_opened_at, error
              FROM worktrees
              WHERE repo_id = ? AND deleted_at IS NULL
              ORDER BY created_at ASC",
         )?;
 
         let worktrees = stmt
-            .query_map([repo_id], |row| {
-                let status_str: String = row.get(4)?;
-                Ok(Worktree {
-                    path: row.get(0)?,
-                    repo_id: row.get(1)?,
-                    branch: row.get(2)?,
-                    head: row.get(3)?,
-                    status: status_str.parse().unwrap_or(WorktreeStatus::Error),
-                    commit_message: row.get(5)?,
-                    dirty: row.get(6)?,
-                    ahead: row.get(7)?,
-                    behind: row.get(8)?,
-                    last_status_check: row.get(9)?,
-                    created_at: row.get(10)?,
-                    deleted_at: row.get(11)?,
-                })
-            })?
+            .query_map([repo_id], |row| worktree_from_row(row, 0))?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(worktrees)
@@ -275,32 +637,18 @@ impl Database {
 
     /// Get worktree by path
     pub fn get_worktree(&self, path: &str) -> Result<Option<Worktree>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
             "SELECT path, repo_id, branch, head, status, commit_message,
-                    dirty, ahead, behind, last_status_check, created_at, deleted_at
+                    dirty, ahead, behind, last_status_check, created_at, deleted_at,
+                    package_managers, install_status, deps_fingerprint, deps_state,
+                    toolchains, note, issue_url, owner, last_opened_at, error
              FROM worktrees
              WHERE path = ? AND deleted_at IS NULL",
         )?;
 
         let worktree = stmt
-            .query_row([path], |row| {
-                let status_str: String = row.get(4)?;
-                Ok(Worktree {
-                    path: row.get(0)?,
-                    repo_id: row.get(1)?,
-                    branch: row.get(2)?,
-                    head: row.get(3)?,
-                    status: status_str.parse().unwrap_or(WorktreeStatus::Error),
-                    commit_message: row.get(5)?,
-                    dirty: row.get(6)?,
-                    ahead: row.get(7)?,
-                    behind: row.get(8)?,
-                    last_status_check: row.get(9)?,
-                    created_at: row.get(10)?,
-                    deleted_at: row.get(11)?,
-                })
-            })
+            .query_row([path], |row| worktree_from_row(row, 0))
             .optional()?;
 
         Ok(worktree)
@@ -308,7 +656,7 @@ impl Database {
 
     /// Insert new worktree
     pub fn insert_worktree(&self, worktree: &NewWorktree) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
         let now = chrono::Utc::now().timestamp_millis();
 
         conn.execute(
@@ -334,7 +682,7 @@ impl Database {
         head: Option<&str>,
         commit_message: Option<&str>,
     ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
 
         conn.execute(
             "UPDATE worktrees SET status = ?, head = ?, commit_message = ? WHERE path = ?",
@@ -344,6 +692,26 @@ impl Database {
         Ok(())
     }
 
+    /// Update head and commit message of a worktree that is still ready
+    ///
+    /// Leaves every other status (and its error) alone, so a refresh racing
+    /// a create, delete or failure can't mark the worktree ready.
+    pub fn refresh_worktree_head(
+        &self,
+        path: &str,
+        head: Option<&str>,
+        commit_message: Option<&str>,
+    ) -> Result<bool> {
+        let conn = self.writer();
+
+        let updated = conn.execute(
+            "UPDATE worktrees SET head = ?, commit_message = ? WHERE path = ? AND status = ?",
+            params![head, commit_message, path, WorktreeStatus::Ready.as_str()],
+        )?;
+
+        Ok(updated > 0)
+    }
+
     /// Update worktree git status (dirty, ahead, behind)
     pub fn update_worktree_git_status(
         &self,
@@ -352,7 +720,7 @@ impl Database {
         ahead: i32,
         behind: i32,
     ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
         let now = chrono::Utc::now().timestamp_millis();
 
         conn.execute(
@@ -363,11 +731,141 @@ impl Database {
         Ok(())
     }
 
+    /// Record the package managers detected for a worktree
+    pub fn update_worktree_package_managers(&self, path: &str, managers: &[String]) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "UPDATE worktrees SET package_managers = ? WHERE path = ?",
+            params![managers.join(","), path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record the outcome of a worktree's dependency install
+    pub fn update_worktree_install_status(&self, path: &str, status: InstallStatus) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "UPDATE worktrees SET install_status = ? WHERE path = ?",
+            params![status.as_str(), path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record the dependency fingerprint of a successful install
+    pub fn update_worktree_deps_fingerprint(&self, path: &str, fingerprint: &str) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "UPDATE worktrees SET deps_fingerprint = ?, deps_state = ? WHERE path = ?",
+            params![fingerprint, DepsState::UpToDate.as_str(), path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record whether a worktree's dependencies are up to date
+    pub fn update_worktree_deps_state(&self, path: &str, deps_state: DepsState) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "UPDATE worktrees SET deps_state = ? WHERE path = ?",
+            params![deps_state.as_str(), path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record the toolchain requirements found in a worktree and how they compare
+    pub fn update_worktree_toolchains(&self, path: &str, toolchains: &[ToolchainStatus]) -> Result<()> {
+        let conn = self.writer();
+        let json = serde_json::to_string(toolchains).unwrap_or_else(|_| "[]".to_string());
+
+        conn.execute(
+            "UPDATE worktrees SET toolchains = ? WHERE path = ?",
+            params![json, path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Change a worktree's note, issue link and owner ("" clears, None keeps)
+    pub fn update_worktree_notes(&self, path: &str, notes: &WorktreeNotes) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "UPDATE worktrees SET
+                note = CASE WHEN ?1 IS NULL THEN note ELSE NULLIF(?1, '') END,
+                issue_url = CASE WHEN ?2 IS NULL THEN issue_url ELSE NULLIF(?2, '') END,
+                owner = CASE WHEN ?3 IS NULL THEN owner ELSE NULLIF(?3, '') END
+             WHERE path = ?4",
+            params![notes.note, notes.issue_url, notes.owner, path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record why creating a worktree failed, or clear it (None)
+    pub fn update_worktree_error(&self, path: &str, error: Option<&SetupError>) -> Result<()> {
+        let conn = self.writer();
+        let error = error.map(serde_json::to_string).transpose()?;
+
+        conn.execute(
+            "UPDATE worktrees SET error = ? WHERE path = ?",
+            params![error, path],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record that a worktree was opened in an editor; false if it isn't tracked
+    pub fn touch_worktree_opened(&self, path: &str) -> Result<bool> {
+        let conn = self.writer();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let updated = conn.execute(
+            "UPDATE worktrees SET last_opened_at = ? WHERE path = ? AND deleted_at IS NULL",
+            params![now, path],
+        )?;
+
+        Ok(updated > 0)
+    }
+
+    /// Move worktree to a new path and branch (path is the primary key)
+    pub fn rename_worktree(&self, old_path: &str, new_path: &str, branch: &str) -> Result<()> {
+        let mut conn = self.writer();
+        // A savepoint, so a rebuild can call it inside `transaction`
+        let tx = conn.savepoint()?;
+
+        let updated = tx.execute(
+            "UPDATE worktrees SET path = ?, branch = ? WHERE path = ?",
+            params![new_path, branch, old_path],
+        )?;
+        if updated == 0 {
+            anyhow::bail!("worktree not found: {}", old_path);
+        }
+        tx.execute(
+            "UPDATE disk_usage SET path = ? WHERE path = ?",
+            params![new_path, old_path],
+        )?;
+        tx.execute(
+            "UPDATE install_logs SET worktree_path = ? WHERE worktree_path = ?",
+            params![new_path, old_path],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Hard delete worktree
     pub fn delete_worktree(&self, path: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
 
         conn.execute("DELETE FROM worktrees WHERE path = ?", params![path])?;
+        conn.execute("DELETE FROM disk_usage WHERE path = ?", params![path])?;
 
         Ok(())
     }
@@ -378,9 +876,10 @@ impl Database {
 
     /// Get worktree config for a repository
     pub fn get_worktree_config(&self, repo_id: &str) -> Result<Option<WorktreeConfig>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT repo_id, symlink_patterns, copy_patterns, upstream_remote
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT repo_id, symlink_patterns, copy_patterns, upstream_remote,
+                    install_paths, cargo_step
              FROM worktree_config WHERE repo_id = ?",
         )?;
 
@@ -388,9 +887,13 @@ impl Database {
             .query_row([repo_id], |row| {
                 Ok(WorktreeConfig {
                     repo_id: row.get(0)?,
-                    symlink_patterns: row.get(1)?,
-                    copy_patterns: row.get(2)?,
+                    symlink_patterns: json_list(row.get(1)?),
+                    copy_patterns: json_list(row.get(2)?),
                     upstream_remote: row.get(3)?,
+                    install_paths: json_list(row.get(4)?),
+                    cargo_step: row
+                        .get::<_, Option<String>>(5)?
+                        .and_then(|s| s.parse().ok()),
                 })
             })
             .optional()?;
@@ -400,23 +903,530 @@ impl Database {
 
     /// Upsert worktree config
     pub fn upsert_worktree_config(&self, config: &WorktreeConfig) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.writer();
 
         conn.execute(
-            "INSERT INTO worktree_config (repo_id, symlink_patterns, copy_patterns, upstream_remote)
-             VALUES (?1, ?2, ?3, ?4)
+            "INSERT INTO worktree_config (repo_id, symlink_patterns, copy_patterns, upstream_remote,
+                                          install_paths, cargo_step)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
              ON CONFLICT(repo_id) DO UPDATE SET
                 symlink_patterns = excluded.symlink_patterns,
                 copy_patterns = excluded.copy_patterns,
-                upstream_remote = excluded.upstream_remote",
+                upstream_remote = excluded.upstream_remote,
+                install_paths = excluded.install_paths,
+                cargo_step = excluded.cargo_step",
             params![
                 config.repo_id,
-                config.symlink_patterns,
-                config.copy_patterns,
+                serde_json::to_string(&config.symlink_patterns)?,
+                serde_json::to_string(&config.copy_patterns)?,
                 config.upstream_remote,
+                serde_json::to_string(&config.install_paths)?,
+                config.cargo_step.map(|s| s.as_str()),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Maintenance
+    // ─────────────────────────────────────────────────────────────
+
+    /// List last maintenance run per task for a repository
+    pub fn list_maintenance_runs(&self, repo_id: &str) -> Result<Vec<MaintenanceRun>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT repo_id, task, started_at, finished_at, size_before, size_after, error
+             FROM maintenance_runs
+             WHERE repo_id = ?
+             ORDER BY started_at ASC",
+        )?;
+
+        let runs = stmt
+            .query_map([repo_id], |row| {
+                let task: String = row.get(1)?;
+                let Ok(task) = task.parse() else {
+                    return Ok(None);
+                };
+                Ok(Some(MaintenanceRun {
+                    repo_id: row.get(0)?,
+                    task,
+                    started_at: row.get(2)?,
+                    finished_at: row.get(3)?,
+                    size_before: row.get(4)?,
+                    size_after: row.get(5)?,
+                    error: row.get(6)?,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Skip tasks this binary doesn't know about
+        Ok(runs.into_iter().flatten().collect())
+    }
+
+    /// Record the latest run of a maintenance task (replaces the previous one)
+    pub fn record_maintenance_run(&self, run: &MaintenanceRun) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "INSERT INTO maintenance_runs
+             (repo_id, task, started_at, finished_at, size_before, size_after, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(repo_id, task) DO UPDATE SET
+                started_at = excluded.started_at,
+                finished_at = excluded.finished_at,
+                size_before = excluded.size_before,
+                size_after = excluded.size_after,
+                error = excluded.error",
+            params![
+                run.repo_id,
+                run.task.as_str(),
+                run.started_at,
+                run.finished_at,
+                run.size_before,
+                run.size_after,
+                run.error,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Hooks
+    // ─────────────────────────────────────────────────────────────
+
+    /// List hooks configured for a repository
+    pub fn list_hooks(&self, repo_id: &str) -> Result<Vec<Hook>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT repo_id, event, command, timeout_secs FROM hooks WHERE repo_id = ?",
+        )?;
+
+        let hooks = stmt
+            .query_map([repo_id], |row| {
+                let event: String = row.get(1)?;
+                let Ok(event) = event.parse() else {
+                    return Ok(None);
+                };
+                Ok(Some(Hook {
+                    repo_id: row.get(0)?,
+                    event,
+                    command: row.get(2)?,
+                    timeout_secs: row.get(3)?,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(hooks.into_iter().flatten().collect())
+    }
+
+    /// Get the hook for a repository and event
+    pub fn get_hook(&self, repo_id: &str, event: HookEvent) -> Result<Option<Hook>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, timeout_secs FROM hooks WHERE repo_id = ? AND event = ?",
+        )?;
+
+        let hook = stmt
+            .query_row(params![repo_id, event.as_str()], |row| {
+                Ok(Hook {
+                    repo_id: repo_id.to_string(),
+                    event,
+                    command: row.get(0)?,
+                    timeout_secs: row.get(1)?,
+                })
+            })
+            .optional()?;
+
+        Ok(hook)
+    }
+
+    /// Insert or replace a hook
+    pub fn upsert_hook(&self, hook: &Hook) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "INSERT INTO hooks (repo_id, event, command, timeout_secs)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(repo_id, event) DO UPDATE SET
+                command = excluded.command,
+                timeout_secs = excluded.timeout_secs",
+            params![
+                hook.repo_id,
+                hook.event.as_str(),
+                hook.command,
+                hook.timeout_secs,
             ],
         )?;
 
         Ok(())
     }
+
+    /// Remove a hook
+    pub fn delete_hook(&self, repo_id: &str, event: HookEvent) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "DELETE FROM hooks WHERE repo_id = ? AND event = ?",
+            params![repo_id, event.as_str()],
+        )?;
+
+        Ok(())
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Install Logs
+    // ─────────────────────────────────────────────────────────────
+
+    /// List install logs, newest first, for a worktree or a whole repository
+    pub fn list_install_logs(
+        &self,
+        repo_id: Option<&str>,
+        worktree_path: Option<&str>,
+    ) -> Result<Vec<InstallLog>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, repo_id, worktree_path, started_at, finished_at, success
+             FROM install_logs
+             WHERE (?1 IS NULL OR repo_id = ?1) AND (?2 IS NULL OR worktree_path = ?2)
+             ORDER BY started_at DESC",
+        )?;
+
+        let logs = stmt
+            .query_map(params![repo_id, worktree_path], map_install_log)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(logs)
+    }
+
+    /// Get install log by ID
+    pub fn get_install_log(&self, id: &str) -> Result<Option<InstallLog>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, repo_id, worktree_path, started_at, finished_at, success
+             FROM install_logs WHERE id = ?",
+        )?;
+
+        Ok(stmt.query_row([id], map_install_log).optional()?)
+    }
+
+    /// Record the start of an install run
+    pub fn insert_install_log(&self, log: &InstallLog) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "INSERT INTO install_logs (id, repo_id, worktree_path, started_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![log.id, log.repo_id, log.worktree_path, log.started_at],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record the outcome of an install run
+    pub fn finish_install_log(&self, id: &str, success: bool) -> Result<()> {
+        let conn = self.writer();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        conn.execute(
+            "UPDATE install_logs SET finished_at = ?, success = ? WHERE id = ?",
+            params![now, success, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Delete install log records for a worktree, returning their IDs
+    pub fn delete_install_logs(&self, worktree_path: &str) -> Result<Vec<String>> {
+        let conn = self.writer();
+
+        let ids = conn
+            .prepare("SELECT id FROM install_logs WHERE worktree_path = ?")?
+            .query_map([worktree_path], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        conn.execute(
+            "DELETE FROM install_logs WHERE worktree_path = ?",
+            params![worktree_path],
+        )?;
+
+        Ok(ids)
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Operations
+    // ─────────────────────────────────────────────────────────────
+
+    /// List operations, newest first
+    pub fn list_operations(&self, filter: &OperationFilter) -> Result<Vec<Operation>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, kind, target, requester, started_at, finished_at, outcome, error
+             FROM operations
+             WHERE (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR target = ?2)
+               AND (?3 IS NULL OR outcome = ?3)
+             ORDER BY started_at DESC
+             LIMIT ?4",
+        )?;
+
+        let operations = stmt
+            .query_map(
+                params![
+                    filter.kind.map(|k| k.as_str()),
+                    filter.target,
+                    filter.outcome.map(|o| o.as_str()),
+                    filter.limit.unwrap_or(50) as i64,
+                ],
+                map_operation,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Skip kinds this binary doesn't know about
+        Ok(operations.into_iter().flatten().collect())
+    }
+
+    /// Get operation by ID
+    pub fn get_operation(&self, id: &str) -> Result<Option<Operation>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, kind, target, requester, started_at, finished_at, outcome, error
+             FROM operations WHERE id = ?",
+        )?;
+
+        Ok(stmt.query_row([id], map_operation).optional()?.flatten())
+    }
+
+    /// Record the start of an operation
+    pub fn insert_operation(&self, op: &Operation) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "INSERT INTO operations (id, kind, target, requester, started_at, outcome)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                op.id,
+                op.kind.as_str(),
+                op.target,
+                op.requester.as_str(),
+                op.started_at,
+                op.outcome.as_str(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record how an operation ended (failed when `error` is set)
+    pub fn finish_operation(&self, id: &str, error: Option<&str>) -> Result<()> {
+        let conn = self.writer();
+        let now = chrono::Utc::now().timestamp_millis();
+        let outcome = match error {
+            Some(_) => OperationOutcome::Failed,
+            None => OperationOutcome::Succeeded,
+        };
+
+        conn.execute(
+            "UPDATE operations SET finished_at = ?, outcome = ?, error = ? WHERE id = ?",
+            params![now, outcome.as_str(), error, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Mark operations left running by a previous server as interrupted and
+    /// drop history older than `keep_ms`
+    pub fn close_stale_operations(&self, keep_ms: i64) -> Result<()> {
+        let conn = self.writer();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        conn.execute(
+            "UPDATE operations SET outcome = ?, finished_at = ? WHERE outcome = ?",
+            params![
+                OperationOutcome::Interrupted.as_str(),
+                now,
+                OperationOutcome::Running.as_str()
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM operations WHERE started_at < ?",
+            params![now - keep_ms],
+        )?;
+
+        Ok(())
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Disk Usage
+    // ─────────────────────────────────────────────────────────────
+
+    /// All cached disk usage, keyed by path
+    pub fn list_disk_usage(&self) -> Result<HashMap<String, DiskUsage>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT path, total, tracked, dependencies, artifacts, computed_at FROM disk_usage",
+        )?;
+
+        let usage = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    DiskUsage {
+                        total: row.get(1)?,
+                        tracked: row.get(2)?,
+                        dependencies: row.get(3)?,
+                        artifacts: row.get(4)?,
+                        computed_at: row.get(5)?,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(usage)
+    }
+
+    /// Insert or replace cached disk usage for a path
+    pub fn record_disk_usage(&self, repo_id: &str, path: &str, usage: &DiskUsage) -> Result<()> {
+        let conn = self.writer();
+
+        conn.execute(
+            "INSERT INTO disk_usage (path, repo_id, total, tracked, dependencies, artifacts, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(path) DO UPDATE SET
+                repo_id = excluded.repo_id,
+                total = excluded.total,
+                tracked = excluded.tracked,
+                dependencies = excluded.dependencies,
+                artifacts = excluded.artifacts,
+                computed_at = excluded.computed_at",
+            params![
+                path,
+                repo_id,
+                usage.total,
+                usage.tracked,
+                usage.dependencies,
+                usage.artifacts,
+                usage.computed_at,
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Repository from its 15 columns (id ... deleted_at, tags, group, favorite, error) starting at `at`
+fn repository_from_row(row: &rusqlite::Row, at: usize) -> rusqlite::Result<Repository> {
+    let mut tags: Vec<String> = row
+        .get::<_, Option<String>>(at + 11)?
+        .map(|t| t.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    tags.sort();
+
+    Ok(Repository {
+        id: row.get(at)?,
+        provider: row.get(at + 1)?,
+        username: row.get(at + 2)?,
+        name: row.get(at + 3)?,
+        clone_url: row.get(at + 4)?,
+        local_path: row.get(at + 5)?,
+        repo_type: row.get(at + 6)?,
+        default_branch: row.get(at + 7)?,
+        last_synced: row.get(at + 8)?,
+        created_at: row.get(at + 9)?,
+        deleted_at: row.get(at + 10)?,
+        tags,
+        group: row.get(at + 12)?,
+        favorite: row.get(at + 13)?,
+        error: row
+            .get::<_, Option<String>>(at + 14)?
+            .and_then(|s| serde_json::from_str(&s).ok()),
+    })
+}
+
+/// Worktree from its 22 columns (path ... note, issue_url, owner, last_opened_at, error) starting at `at`
+fn worktree_from_row(row: &rusqlite::Row, at: usize) -> rusqlite::Result<Worktree> {
+    let status: String = row.get(at + 4)?;
+    Ok(Worktree {
+        path: row.get(at)?,
+        repo_id: row.get(at + 1)?,
+        branch: row.get(at + 2)?,
+        head: row.get(at + 3)?,
+        status: status.parse().unwrap_or(WorktreeStatus::Error),
+        commit_message: row.get(at + 5)?,
+        dirty: row.get(at + 6)?,
+        ahead: row.get(at + 7)?,
+        behind: row.get(at + 8)?,
+        last_status_check: row.get(at + 9)?,
+        created_at: row.get(at + 10)?,
+        deleted_at: row.get(at + 11)?,
+        package_managers: split_list(row.get(at + 12)?),
+        install_status: row
+            .get::<_, Option<String>>(at + 13)?
+            .and_then(|s| s.parse().ok()),
+        deps_fingerprint: row.get(at + 14)?,
+        deps_state: row
+            .get::<_, Option<String>>(at + 15)?
+            .and_then(|s| s.parse().ok()),
+        toolchains: row
+            .get::<_, Option<String>>(at + 16)?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        locked: false,
+        lock_reason: None,
+        disk_usage: None,
+        note: row.get(at + 17)?,
+        issue_url: row.get(at + 18)?,
+        owner: row.get(at + 19)?,
+        last_opened_at: row.get(at + 20)?,
+        error: row
+            .get::<_, Option<String>>(at + 21)?
+            .and_then(|s| serde_json::from_str(&s).ok()),
+    })
+}
+
+fn map_operation(row: &rusqlite::Row) -> rusqlite::Result<Option<Operation>> {
+    let kind: String = row.get(1)?;
+    let Ok(kind) = kind.parse() else {
+        return Ok(None);
+    };
+    let requester: String = row.get(3)?;
+    let outcome: String = row.get(6)?;
+    Ok(Some(Operation {
+        id: row.get(0)?,
+        kind,
+        target: row.get(2)?,
+        requester: requester.parse().unwrap_or_default(),
+        started_at: row.get(4)?,
+        finished_at: row.get(5)?,
+        outcome: outcome.parse().unwrap_or(OperationOutcome::Interrupted),
+        error: row.get(7)?,
+    }))
+}
+
+fn map_install_log(row: &rusqlite::Row) -> rusqlite::Result<InstallLog> {
+    Ok(InstallLog {
+        id: row.get(0)?,
+        repo_id: row.get(1)?,
+        worktree_path: row.get(2)?,
+        started_at: row.get(3)?,
+        finished_at: row.get(4)?,
+        success: row.get(5)?,
+    })
+}
+
+/// A JSON array column (sharing patterns)
+fn json_list(value: Option<String>) -> Vec<String> {
+    value
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Split a comma-joined column into its items
+fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
 }
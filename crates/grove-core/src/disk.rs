@@ -0,0 +1,172 @@
+//! Disk usage accounting for repositories and worktrees
+//!
+//! Worktree usage is split into tracked files, dependency directories and
+//! everything else (ignored build output, untracked files).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory names counted as installed dependencies (at any depth)
+pub const DEPENDENCY_DIRS: &[&str] = &[
+    "node_modules",
+    "bower_components",
+    ".pnpm-store",
+    ".yarn",
+    ".venv",
+    "venv",
+    ".bundle",
+];
+
+/// Directory names counted as dependencies only next to a `mix.exs`
+/// Elsewhere they are build output, e.g. cargo's `target/debug/deps`.
+pub const MIX_DEPENDENCY_DIRS: &[&str] = &["deps", "_build"];
+
+/// Whether `dir` is a dependency directory (see [`DEPENDENCY_DIRS`], [`MIX_DEPENDENCY_DIRS`])
+pub fn is_dependency_dir(dir: &Path) -> bool {
+    let Some(name) = dir.file_name() else {
+        return false;
+    };
+    DEPENDENCY_DIRS.iter().any(|d| name == OsStr::new(d))
+        || (MIX_DEPENDENCY_DIRS.iter().any(|d| name == OsStr::new(d))
+            && dir.parent().is_some_and(|p| p.join("mix.exs").is_file()))
+}
+
+/// Disk usage breakdown in bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskUsage {
+    /// Total bytes on disk
+    pub total: u64,
+    /// Files tracked by git (for a repository: the object database in `.bare`)
+    pub tracked: u64,
+    /// Dependency directories (see [`is_dependency_dir`])
+    pub dependencies: u64,
+    /// Ignored build output (target, dist, .next, ...) and untracked files
+    pub artifacts: u64,
+    /// When this was computed (ms since epoch)
+    pub computed_at: i64,
+}
+
+/// Compute disk usage of a bare repository (blocking)
+pub fn repository_usage(bare_path: &Path) -> DiskUsage {
+    let size = dir_size(bare_path);
+    DiskUsage {
+        total: size,
+        tracked: size,
+        dependencies: 0,
+        artifacts: 0,
+        computed_at: chrono::Utc::now().timestamp_millis(),
+    }
+}
+
+/// Compute disk usage of a worktree (blocking)
+///
+/// Symlinks are not followed, so files shared from `.main` are not double counted.
+pub fn worktree_usage(worktree_path: &Path) -> DiskUsage {
+    let mut dependencies = 0;
+    let mut other = 0;
+    walk(worktree_path, &mut dependencies, &mut other);
+
+    // Vendored dependencies are both tracked and in a dependency dir; clamp so
+    // the buckets still add up to the total
+    let tracked = tracked_size(worktree_path).min(other);
+    DiskUsage {
+        total: other + dependencies,
+        tracked,
+        dependencies,
+        artifacts: other - tracked,
+        computed_at: chrono::Utc::now().timestamp_millis(),
+    }
+}
+
+/// Total size of all files under a directory (symlinks are not followed)
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(ft) if ft.is_dir() => dir_size(&entry.path()),
+            Ok(ft) if ft.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Walk a worktree, splitting dependency directories from everything else
+fn walk(dir: &Path, dependencies: &mut u64, other: &mut u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(ft) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+
+        if ft.is_dir() {
+            if name == OsStr::new(".git") {
+                continue;
+            }
+            if is_dependency_dir(&entry.path()) {
+                *dependencies += dir_size(&entry.path());
+            } else {
+                walk(&entry.path(), dependencies, other);
+            }
+        } else if ft.is_file() && name != OsStr::new(".git") {
+            *other += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+}
+
+/// Size of files git tracks in a worktree (as they exist on disk)
+fn tracked_size(worktree_path: &Path) -> u64 {
+    let Ok(output) = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(worktree_path)
+        .output()
+    else {
+        return 0;
+    };
+    if !output.status.success() {
+        return 0;
+    }
+
+    output
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| {
+            let path = worktree_path.join(String::from_utf8_lossy(p).as_ref());
+            std::fs::symlink_metadata(path).ok()
+        })
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Directories git ignores in a worktree (build output such as target, dist, .next)
+/// Only the outermost ignored directory is listed; empty if git can't list them.
+pub fn ignored_dirs(worktree_path: &Path) -> HashSet<PathBuf> {
+    let Ok(output) = Command::new("git")
+        .args(["ls-files", "-z", "--others", "--ignored", "--exclude-standard", "--directory"])
+        .current_dir(worktree_path)
+        .output()
+    else {
+        return HashSet::new();
+    };
+    if !output.status.success() {
+        return HashSet::new();
+    }
+
+    output
+        .stdout
+        .split(|b| *b == 0)
+        .filter_map(|p| String::from_utf8_lossy(p).strip_suffix('/').map(|d| worktree_path.join(d)))
+        .collect()
+}
@@ -0,0 +1,282 @@
+//! Structured errors of failed clones and worktree creation
+//!
+//! A clone or worktree creation that fails keeps its record with a
+//! [`SetupError`]: what kind of problem it was, the step that failed and the
+//! tail of git's stderr. Retrying resumes at that step, so a clone whose fetch
+//! failed doesn't download the repository again.
+
+use crate::git::GitCommandError;
+use serde::{Deserialize, Serialize};
+
+/// Most stderr lines kept
+const STDERR_LINES: usize = 20;
+
+/// Most characters kept of the message and of the stderr excerpt
+const MAX_CHARS: usize = 2000;
+
+/// Steps of setting up a repository or worktree, in the order they run
+/// Clones run all but `Share`; worktrees start at `CreateWorktree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupStep {
+    /// Clear a leftover directory and create the repository directory
+    Prepare,
+    /// Bare clone
+    Clone,
+    /// `.git` pointer and fetch refspec
+    Configure,
+    Fetch,
+    /// `git worktree add` (`.main` for clones)
+    CreateWorktree,
+    /// Symlink/copy files from `.main`
+    Share,
+    Install,
+    /// post-clone / post-create hook
+    Hook,
+    /// Read the git status and mark the worktree ready
+    Status,
+}
+
+impl SetupStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prepare => "prepare",
+            Self::Clone => "clone",
+            Self::Configure => "configure",
+            Self::Fetch => "fetch",
+            Self::CreateWorktree => "create_worktree",
+            Self::Share => "share",
+            Self::Install => "install",
+            Self::Hook => "hook",
+            Self::Status => "status",
+        }
+    }
+}
+
+/// What kind of problem made a step fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Host unreachable, connection refused or timed out
+    Network,
+    /// Credentials missing or rejected
+    Auth,
+    /// Repository, branch or remote doesn't exist
+    NotFound,
+    /// Path or branch already in use, or locked
+    Conflict,
+    /// Reading or writing files failed (permissions, disk full)
+    Filesystem,
+    /// Any other git failure
+    Git,
+    Internal,
+}
+
+impl ErrorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Network => "network",
+            Self::Auth => "auth",
+            Self::NotFound => "not_found",
+            Self::Conflict => "conflict",
+            Self::Filesystem => "filesystem",
+            Self::Git => "git",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// Guess the category from an error's messages (git's wording)
+    pub fn classify(error: &anyhow::Error) -> Self {
+        let text = format!("{:#}", error).to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| text.contains(n));
+
+        // Before filesystem: ssh also says "permission denied"
+        if has(&[
+            "authentication failed",
+            "could not read username",
+            "permission denied (publickey",
+            "invalid credentials",
+            "403",
+        ]) {
+            Self::Auth
+        } else if has(&[
+            "could not resolve host",
+            "failed to connect",
+            "connection refused",
+            "connection reset",
+            "timed out",
+            "network is unreachable",
+            "early eof",
+            "talking to the server",
+            "error sending request",
+        ]) {
+            Self::Network
+        } else if has(&[
+            "not found",
+            "does not exist",
+            "does not appear to be a git repository",
+            "invalid reference",
+            "couldn't find remote ref",
+        ]) {
+            Self::NotFound
+        } else if has(&["already exists", "already checked out", "already used by worktree", "locked"]) {
+            Self::Conflict
+        } else if error.chain().any(|e| e.is::<GitCommandError>()) || text.starts_with("git ") {
+            Self::Git
+        } else if error.chain().any(|e| e.is::<std::io::Error>()) {
+            Self::Filesystem
+        } else {
+            Self::Internal
+        }
+    }
+}
+
+/// Why a clone or worktree creation failed, kept until it is retried
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetupError {
+    pub category: ErrorCategory,
+    /// First line of the error
+    pub message: String,
+    /// The step that failed; a retry starts here
+    pub step: SetupStep,
+    /// Last lines git wrote to stderr, if a git command failed
+    pub stderr: Option<String>,
+    /// ms since epoch
+    pub failed_at: i64,
+    /// The failed setup skipped installing; a retry does too
+    #[serde(default)]
+    pub skip_install: bool,
+}
+
+impl SetupError {
+    pub fn new(step: SetupStep, error: &anyhow::Error, skip_install: bool) -> Self {
+        let git = error.chain().find_map(|e| e.downcast_ref::<GitCommandError>());
+        let message = match git {
+            // The stderr is in its own field
+            Some(git) if git.stderr.trim().lines().count() > 1 => {
+                format!("git {} failed", git.args)
+            }
+            _ => format!("{:#}", error).lines().next().unwrap_or("").trim().to_string(),
+        };
+        let stderr = git.map(|g| excerpt(&g.stderr)).filter(|s| !s.is_empty());
+
+        Self {
+            category: ErrorCategory::classify(error),
+            message: truncate(&message),
+            step,
+            stderr,
+            failed_at: chrono::Utc::now().timestamp_millis(),
+            skip_install,
+        }
+    }
+}
+
+/// Last lines of stderr, without git's progress noise
+fn excerpt(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(|l| l.rsplit('\r').next().unwrap_or(l).trim_end())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let tail = lines[lines.len().saturating_sub(STDERR_LINES)..].join("\n");
+    truncate(&tail)
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn git_error(args: &str, stderr: &str) -> anyhow::Error {
+        anyhow::Error::new(GitCommandError {
+            args: args.to_string(),
+            stderr: stderr.to_string(),
+        })
+    }
+
+    #[test]
+    fn classifies_git_stderr() {
+        let cases = [
+            (
+                "fatal: Authentication failed for 'https://github.com/user/repo.git/'",
+                ErrorCategory::Auth,
+            ),
+            (
+                "git@github.com: Permission denied (publickey).\nfatal: Could not read from remote repository.",
+                ErrorCategory::Auth,
+            ),
+            (
+                "fatal: unable to access 'https://github.com/user/repo.git/': Could not resolve host: github.com",
+                ErrorCategory::Network,
+            ),
+            ("fatal: early EOF", ErrorCategory::Network),
+            (
+                "remote: Repository not found.\nfatal: repository 'https://github.com/user/gone.git/' not found",
+                ErrorCategory::NotFound,
+            ),
+            ("fatal: invalid reference: feature/x", ErrorCategory::NotFound),
+            (
+                "fatal: 'feature/x' is already checked out at '/code/user/repo/feature-x'",
+                ErrorCategory::Conflict,
+            ),
+            ("fatal: '/code/user/repo/x' already exists", ErrorCategory::Conflict),
+            ("fatal: bad object HEAD", ErrorCategory::Git),
+        ];
+
+        for (stderr, expected) in cases {
+            assert_eq!(
+                ErrorCategory::classify(&git_error("fetch origin", stderr)),
+                expected,
+                "{}",
+                stderr
+            );
+        }
+    }
+
+    #[test]
+    fn auth_wins_over_filesystem_permission_errors() {
+        let ssh = git_error("clone", "Permission denied (publickey).");
+        assert_eq!(ErrorCategory::classify(&ssh), ErrorCategory::Auth);
+
+        let io = anyhow::Error::new(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "Permission denied (os error 13)",
+        ))
+        .context("Failed to create /code/user/repo");
+        assert_eq!(ErrorCategory::classify(&io), ErrorCategory::Filesystem);
+    }
+
+    #[test]
+    fn context_is_searched_too() {
+        let error = anyhow!("exit status 128").context("Fetching failed: could not resolve host");
+        assert_eq!(ErrorCategory::classify(&error), ErrorCategory::Network);
+        assert_eq!(
+            ErrorCategory::classify(&anyhow!("task panicked")),
+            ErrorCategory::Internal
+        );
+    }
+
+    #[test]
+    fn setup_error_moves_long_stderr_out_of_the_message() {
+        let error = git_error(
+            "worktree add",
+            "Preparing worktree\r\nfatal: 'x' already exists\n\n",
+        );
+
+        let setup = SetupError::new(SetupStep::CreateWorktree, &error, false);
+
+        assert_eq!(setup.category, ErrorCategory::Conflict);
+        assert_eq!(setup.message, "git worktree add failed");
+        assert_eq!(
+            setup.stderr.as_deref(),
+            Some("Preparing worktree\nfatal: 'x' already exists")
+        );
+    }
+}
@@ -4,9 +4,10 @@
 //! See README.md for pseudocode and diagrams.
 
 use crate::types::{GitStatus, ParsedGitUrl};
-use anyhow::{bail, Context, Result};
-use std::path::Path;
-use std::process::Command;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 /// Git operations handler
 pub struct GitOps;
@@ -50,7 +51,7 @@ impl GitOps {
             // Fetch - returns (Repository, Outcome)
             let (_repo, _outcome) = prepare
                 .fetch_only(Discard, &gix::interrupt::IS_INTERRUPTED)
-                .map_err(|e| anyhow::anyhow!("fetch failed: {:?}", e))?;
+                .context("fetch failed")?;
 
             // Repository is already persisted by fetch_only
 
@@ -75,10 +76,7 @@ impl GitOps {
             .context("failed to execute git config")?;
 
         if !output.status.success() {
-            bail!(
-                "git config failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
+            return Err(GitCommandError::new(&["config", key, value], &output.stderr).into());
         }
 
         Ok(())
@@ -121,6 +119,19 @@ impl GitOps {
         Ok(())
     }
 
+    /// Drop remote-tracking branches deleted on the remote (`git remote prune`)
+    /// gix fetches don't prune; without this a deleted upstream never shows as gone.
+    pub async fn prune_remote(&self, repo_path: &Path, remote: &str) -> Result<()> {
+        let repo_path = repo_path.to_path_buf();
+        let remote = remote.to_string();
+
+        tokio::task::spawn_blocking(move || git_cmd(&repo_path, &["remote", "prune", &remote]))
+            .await
+            .context("prune task panicked")??;
+
+        Ok(())
+    }
+
     // ─────────────────────────────────────────────────────────────
     // Worktree Operations (git CLI - gix doesn't support mutations)
     // ─────────────────────────────────────────────────────────────
@@ -171,7 +182,7 @@ impl GitOps {
                         "--track",
                         "-b",
                         &branch,
-                        &worktree_path.to_string_lossy().as_ref(),
+                        worktree_path.to_string_lossy().as_ref(),
                         &remote_ref,
                     ],
                 )?;
@@ -184,7 +195,7 @@ impl GitOps {
                         "add",
                         "-b",
                         &branch,
-                        &worktree_path.to_string_lossy().as_ref(),
+                        worktree_path.to_string_lossy().as_ref(),
                     ],
                 )?;
             }
@@ -198,23 +209,208 @@ impl GitOps {
     }
 
     /// Remove worktree
-    pub async fn remove_worktree(&self, repo_path: &Path, worktree_path: &Path) -> Result<()> {
+    /// Locked worktrees are only removed when `include_locked` is set
+    pub async fn remove_worktree(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        include_locked: bool,
+    ) -> Result<()> {
         let repo_path = repo_path.to_path_buf();
         let worktree_path = worktree_path.to_path_buf();
 
+        tokio::task::spawn_blocking(move || {
+            let worktree_path = worktree_path.to_string_lossy();
+            let mut args = vec!["worktree", "remove", worktree_path.as_ref(), "--force"];
+            // git needs --force twice to remove a locked worktree
+            if include_locked {
+                args.push("--force");
+            }
+            git_cmd(&repo_path, &args)
+        })
+        .await
+        .context("remove worktree task panicked")??;
+
+        Ok(())
+    }
+
+    /// Lock worktree (`git worktree lock`) so git and grove refuse to remove it
+    pub async fn lock_worktree(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let repo_path = repo_path.to_path_buf();
+        let worktree_path = worktree_path.to_path_buf();
+        let reason = reason.map(str::to_string);
+
+        tokio::task::spawn_blocking(move || {
+            let worktree_path = worktree_path.to_string_lossy();
+            let mut args = vec!["worktree", "lock"];
+            if let Some(reason) = reason.as_deref() {
+                args.extend(["--reason", reason]);
+            }
+            args.push(worktree_path.as_ref());
+            git_cmd(&repo_path, &args)
+        })
+        .await
+        .context("lock worktree task panicked")??;
+
+        Ok(())
+    }
+
+    /// Unlock worktree (`git worktree unlock`)
+    pub async fn unlock_worktree(&self, repo_path: &Path, worktree_path: &Path) -> Result<()> {
+        let repo_path = repo_path.to_path_buf();
+        let worktree_path = worktree_path.to_path_buf();
+
+        tokio::task::spawn_blocking(move || {
+            git_cmd(
+                &repo_path,
+                &["worktree", "unlock", &worktree_path.to_string_lossy()],
+            )
+        })
+        .await
+        .context("unlock worktree task panicked")??;
+
+        Ok(())
+    }
+
+    /// Locked worktrees of a repository: worktree path -> lock reason
+    ///
+    /// Reads `$GIT_DIR/worktrees/*/locked` directly so it stays cheap enough to
+    /// call on every state push and works while the worktree itself is missing
+    /// (e.g. on an unmounted external disk).
+    pub fn worktree_locks(&self, repo_path: &Path) -> HashMap<String, Option<String>> {
+        let mut locks = HashMap::new();
+        let Some(git_dir) = resolve_git_dir(repo_path) else {
+            return locks;
+        };
+        let Ok(entries) = std::fs::read_dir(git_dir.join("worktrees")) else {
+            return locks;
+        };
+
+        for entry in entries.flatten() {
+            let admin = entry.path();
+            let Ok(reason) = std::fs::read_to_string(admin.join("locked")) else {
+                continue;
+            };
+            // gitdir holds "<worktree>/.git"
+            let Ok(gitdir) = std::fs::read_to_string(admin.join("gitdir")) else {
+                continue;
+            };
+            let gitdir = gitdir.trim();
+            let worktree = gitdir.strip_suffix("/.git").unwrap_or(gitdir);
+            let reason = reason.trim();
+            locks.insert(
+                worktree.to_string(),
+                (!reason.is_empty()).then(|| reason.to_string()),
+            );
+        }
+
+        locks
+    }
+
+    /// Worktrees registered with a repository (`git worktree list`), bare entry excluded
+    pub fn list_worktrees(&self, repo_path: &Path) -> Result<Vec<WorktreeEntry>> {
+        let output = self.git_output(repo_path, &["worktree", "list", "--porcelain"])?;
+        let mut entries = Vec::new();
+
+        // Blank-line separated records: "worktree <path>", "HEAD <sha>",
+        // "branch refs/heads/<name>" or "detached", "bare", "locked", "prunable"
+        for record in output.split("\n\n") {
+            let mut entry = WorktreeEntry::default();
+            let mut bare = false;
+            for line in record.lines() {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                match key {
+                    "worktree" => entry.path = value.to_string(),
+                    "HEAD" => entry.head = Some(value.to_string()),
+                    "branch" => {
+                        entry.branch =
+                            Some(value.strip_prefix("refs/heads/").unwrap_or(value).to_string())
+                    }
+                    "bare" => bare = true,
+                    "prunable" => entry.prunable = true,
+                    _ => {}
+                }
+            }
+            if !entry.path.is_empty() && !bare {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// URL of a remote, None if it isn't configured
+    pub fn remote_url(&self, repo_path: &Path, remote: &str) -> Option<String> {
+        git_config_get(repo_path, &format!("remote.{}.url", remote)).filter(|url| !url.is_empty())
+    }
+
+    /// Move worktree to a new path
+    pub async fn move_worktree(&self, repo_path: &Path, from: &Path, to: &Path) -> Result<()> {
+        let repo_path = repo_path.to_path_buf();
+        let from = from.to_path_buf();
+        let to = to.to_path_buf();
+
         tokio::task::spawn_blocking(move || {
             git_cmd(
                 &repo_path,
                 &[
                     "worktree",
-                    "remove",
-                    &worktree_path.to_string_lossy(),
-                    "--force",
+                    "move",
+                    &from.to_string_lossy(),
+                    &to.to_string_lossy(),
                 ],
             )
         })
         .await
-        .context("remove worktree task panicked")??;
+        .context("move worktree task panicked")??;
+
+        Ok(())
+    }
+
+    /// Rename a local branch and point its upstream at the same name on the remote
+    ///
+    /// Only local tracking config is touched; nothing is pushed. If `remote/new`
+    /// already exists it becomes the upstream, otherwise the branch is configured
+    /// so the next `git push` creates it. If retargeting the upstream fails, the
+    /// rename is undone so callers never see a half-renamed branch.
+    pub async fn rename_branch(
+        &self,
+        repo_path: &Path,
+        old: &str,
+        new: &str,
+        remote: &str,
+    ) -> Result<()> {
+        let repo_path = repo_path.to_path_buf();
+        let old = old.to_string();
+        let new = new.to_string();
+        let remote = remote.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            rename_branch_or_undo(&repo_path, &old, &new, || {
+                let remote_ref = format!("{}/{}", remote, new);
+                if git_rev_parse(&repo_path, &format!("refs/remotes/{}", remote_ref))? {
+                    git_cmd(&repo_path, &["branch", "--set-upstream-to", &remote_ref, &new])?;
+                } else if git_config_get(&repo_path, &format!("branch.{}.remote", new)).is_some() {
+                    // Had an upstream under the old name - retarget it to the new name
+                    git_cmd(
+                        &repo_path,
+                        &[
+                            "config",
+                            &format!("branch.{}.merge", new),
+                            &format!("refs/heads/{}", new),
+                        ],
+                    )?;
+                }
+                Ok(())
+            })
+        })
+        .await
+        .context("rename branch task panicked")??;
 
         Ok(())
     }
@@ -267,7 +463,7 @@ impl GitOps {
             )
             .ok()
             .and_then(|output| {
-                let parts: Vec<&str> = output.trim().split_whitespace().collect();
+                let parts: Vec<&str> = output.split_whitespace().collect();
                 if parts.len() == 2 {
                     Some((
                         parts[1].parse().unwrap_or(0),
@@ -289,17 +485,146 @@ impl GitOps {
         })
     }
 
+    // ─────────────────────────────────────────────────────────────
+    // Activity (stale worktree detection)
+    // ─────────────────────────────────────────────────────────────
+
+    /// Commit time of HEAD in ms since epoch
+    pub fn last_commit_time(&self, worktree_path: &Path) -> Option<i64> {
+        let output = self
+            .git_output(worktree_path, &["log", "-1", "--format=%ct", "HEAD"])
+            .ok()?;
+        output.trim().parse::<i64>().ok().map(|secs| secs * 1000)
+    }
+
+    /// Whether the work of `branch` is in the default branch, merged or squash-merged
+    /// Compares with `{remote}/{default_branch}` when it exists, else the local branch.
+    /// A branch with no upstream that never moved since it was created has no work
+    /// of its own, so a fresh branch sitting on an old commit doesn't count.
+    pub fn is_merged(&self, repo_path: &Path, branch: &str, default_branch: &str, remote: &str) -> bool {
+        let remote_ref = format!("refs/remotes/{}/{}", remote, default_branch);
+        let into = if git_rev_parse(repo_path, &remote_ref).unwrap_or(false) {
+            remote_ref
+        } else {
+            format!("refs/heads/{}", default_branch)
+        };
+        let branch_ref = format!("refs/heads/{}", branch);
+        if !self.has_own_history(repo_path, &branch_ref) {
+            return false;
+        }
+
+        self.git(repo_path, &["merge-base", "--is-ancestor", &branch_ref, &into])
+            .is_ok()
+            || self.is_squash_merged(repo_path, &branch_ref, &into)
+    }
+
+    /// Whether a branch tracks an upstream or has moved since it was created
+    /// Commits made in a worktree land in the branch reflog; its creation doesn't
+    /// (bare repositories don't log) or shows up as "branch: Created from".
+    fn has_own_history(&self, repo_path: &Path, branch_ref: &str) -> bool {
+        let has_upstream = self
+            .git_output(repo_path, &["for-each-ref", "--format=%(upstream)", branch_ref])
+            .is_ok_and(|upstream| !upstream.trim().is_empty());
+        has_upstream
+            || self
+                .git_output(repo_path, &["reflog", "show", "--format=%gs", branch_ref])
+                .is_ok_and(|log| log.lines().any(|entry| !entry.starts_with("branch: Created from")))
+    }
+
+    /// Whether the combined changes of a branch were applied to `into` as one commit
+    /// Compares the patch id of the branch's whole diff since the merge base with
+    /// those of the commits `into` gained since; writes nothing to the repository.
+    fn is_squash_merged(&self, repo_path: &Path, branch_ref: &str, into: &str) -> bool {
+        let Ok(base) = self.git_output(repo_path, &["merge-base", into, branch_ref]) else {
+            return false;
+        };
+        let base = base.trim();
+        let Ok(squashed) = self.patch_ids(
+            repo_path,
+            &["diff", "--no-color", "--no-ext-diff", base, branch_ref],
+        ) else {
+            return false;
+        };
+        let Some(squashed) = squashed.first() else {
+            return false;
+        };
+        self.patch_ids(
+            repo_path,
+            &["log", "-p", "--no-merges", "--no-color", "--no-ext-diff", &format!("{}..{}", base, into)],
+        )
+        .is_ok_and(|merged| merged.contains(squashed))
+    }
+
+    /// Stable patch ids of the patches a git command prints (`diff`, `log -p`)
+    fn patch_ids(&self, cwd: &Path, args: &[&str]) -> Result<Vec<String>> {
+        let mut source = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to execute git {}", args.join(" ")))?;
+        let patches = source.stdout.take().context("git produced no output")?;
+        let output = Command::new("git")
+            .args(["patch-id", "--stable"])
+            .current_dir(cwd)
+            .stdin(patches)
+            .output()
+            .context("failed to execute git patch-id")?;
+
+        if !source.wait()?.success() {
+            anyhow::bail!("git {} failed", args.join(" "));
+        }
+        if !output.status.success() {
+            return Err(GitCommandError::new(&["patch-id"], &output.stderr).into());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Whether `branch` tracks an upstream that no longer exists on the remote
+    pub fn upstream_gone(&self, repo_path: &Path, branch: &str) -> bool {
+        let branch_ref = format!("refs/heads/{}", branch);
+        self.git_output(
+            repo_path,
+            &["for-each-ref", "--format=%(upstream:track)", &branch_ref],
+        )
+        .is_ok_and(|track| track.trim() == "[gone]")
+    }
+
+    /// Whether a worktree has uncommitted changes, untracked files included
+    pub fn uncommitted_changes(&self, worktree_path: &Path) -> Result<bool> {
+        let output = self.git_output(worktree_path, &["status", "--porcelain"])?;
+        Ok(!output.trim().is_empty())
+    }
+
+    /// Commits reachable from HEAD that are on no remote-tracking branch
+    pub fn unpushed_commits(&self, worktree_path: &Path) -> Result<u32> {
+        let output = self.git_output(
+            worktree_path,
+            &["rev-list", "--count", "HEAD", "--not", "--remotes"],
+        )?;
+        output
+            .trim()
+            .parse()
+            .context("unexpected git rev-list output")
+    }
+
     /// Detect default branch from remote HEAD
     pub fn detect_default_branch(&self, repo_path: &Path) -> Result<String> {
         let repo = gix::open(repo_path).context("failed to open repository")?;
 
         // Try to get origin/HEAD symbolic ref
-        if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
-            if let Some(target) = reference.target().try_name() {
-                let name = target.to_string();
-                if let Some(branch) = name.strip_prefix("refs/remotes/origin/") {
-                    return Ok(branch.to_string());
-                }
+        if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD")
+            && let Some(target) = reference.target().try_name()
+        {
+            let name = target.to_string();
+            if let Some(branch) = name.strip_prefix("refs/remotes/origin/") {
+                return Ok(branch.to_string());
             }
         }
 
@@ -340,11 +665,7 @@ impl GitOps {
             .with_context(|| format!("failed to execute git {}", args.join(" ")))?;
 
         if !output.status.success() {
-            bail!(
-                "git {} failed: {}",
-                args.join(" "),
-                String::from_utf8_lossy(&output.stderr)
-            );
+            return Err(GitCommandError::new(args, &output.stderr).into());
         }
 
         Ok(())
@@ -359,17 +680,49 @@ impl GitOps {
             .with_context(|| format!("failed to execute git {}", args.join(" ")))?;
 
         if !output.status.success() {
-            bail!(
-                "git {} failed: {}",
-                args.join(" "),
-                String::from_utf8_lossy(&output.stderr)
-            );
+            return Err(GitCommandError::new(args, &output.stderr).into());
         }
 
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 }
 
+/// A git command that exited with an error, keeping what it wrote to stderr
+#[derive(Debug, Clone)]
+pub struct GitCommandError {
+    /// Arguments after `git`, space-separated
+    pub args: String,
+    pub stderr: String,
+}
+
+impl GitCommandError {
+    fn new(args: &[&str], stderr: &[u8]) -> Self {
+        Self {
+            args: args.join(" "),
+            stderr: String::from_utf8_lossy(stderr).to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for GitCommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "git {} failed: {}", self.args, self.stderr)
+    }
+}
+
+impl std::error::Error for GitCommandError {}
+
+/// A worktree as listed by `git worktree list --porcelain`
+#[derive(Debug, Clone, Default)]
+pub struct WorktreeEntry {
+    pub path: String,
+    /// None when HEAD is detached
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    /// Its directory is gone (`git worktree prune` would drop it)
+    pub prunable: bool,
+}
+
 impl Default for GitOps {
     fn default() -> Self {
         Self::new()
@@ -389,11 +742,7 @@ fn git_cmd(cwd: &Path, args: &[&str]) -> Result<()> {
         .with_context(|| format!("failed to execute git {}", args.join(" ")))?;
 
     if !output.status.success() {
-        bail!(
-            "git {} failed: {}",
-            args.join(" "),
-            String::from_utf8_lossy(&output.stderr)
-        );
+        return Err(GitCommandError::new(args, &output.stderr).into());
     }
 
     Ok(())
@@ -410,18 +759,68 @@ fn git_rev_parse(repo_path: &Path, refspec: &str) -> Result<bool> {
     Ok(output.status.success())
 }
 
+/// Resolve the git directory of a repo root (follows the `.git` pointer file to `.bare`)
+fn resolve_git_dir(repo_path: &Path) -> Option<PathBuf> {
+    let dot_git = repo_path.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    if let Ok(content) = std::fs::read_to_string(&dot_git) {
+        let target = content.trim().strip_prefix("gitdir:")?.trim();
+        return Some(repo_path.join(target));
+    }
+    // Already a git directory (e.g. `.bare`)
+    Some(repo_path.to_path_buf())
+}
+
+/// `git branch -m old new`, then `retarget`; renames back if `retarget` fails
+/// `branch -m` moves the tracking config along, so the undo restores it too.
+fn rename_branch_or_undo(
+    repo_path: &Path,
+    old: &str,
+    new: &str,
+    retarget: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    git_cmd(repo_path, &["branch", "-m", old, new])?;
+
+    if let Err(e) = retarget() {
+        if let Err(undo) = git_cmd(repo_path, &["branch", "-m", new, old]) {
+            tracing::error!("Failed to restore branch {}: {}", old, undo);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Read a git config value, None if unset (standalone version for spawn_blocking)
+fn git_config_get(repo_path: &Path, key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .current_dir(repo_path)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 // ─────────────────────────────────────────────────────────────
 // File Sharing
 // ─────────────────────────────────────────────────────────────
 
 /// Share files between worktrees (symlinks and copies)
-/// Patterns use glob syntax (e.g., ".env", ".env.*", ".claude/**")
+/// Patterns use glob syntax (e.g., ".env", ".env.*", ".claude/**"). Files that
+/// already exist in the target are left alone, so sharing again only fills in
+/// what is missing. Returns how many files were linked or copied.
 pub fn share_files(
     source: &Path,
     target: &Path,
     symlink_patterns: &[&str],
     copy_patterns: &[&str],
-) -> Result<()> {
+) -> Result<usize> {
     use std::fs;
     use std::os::unix::fs::symlink;
 
@@ -453,6 +852,7 @@ pub fn share_files(
     let mut files = Vec::new();
     collect_files(source, source, &mut files)?;
 
+    let mut shared = 0;
     for file in files {
         let source_full = source.join(&file);
         let target_full = target.join(&file);
@@ -469,8 +869,8 @@ pub fn share_files(
             continue;
         }
 
-        // Skip if target already exists
-        if target_full.exists() {
+        // Skip if target already exists (a dangling symlink included)
+        if target_full.symlink_metadata().is_ok() {
             continue;
         }
 
@@ -484,9 +884,10 @@ pub fn share_files(
         } else if should_copy {
             fs::copy(&source_full, &target_full)?;
         }
+        shared += 1;
     }
 
-    Ok(())
+    Ok(shared)
 }
 
 /// Simple glob matching (supports * and **)
@@ -545,19 +946,19 @@ fn parse_git_url(url: &str) -> Option<ParsedGitUrl> {
     }
 
     // HTTPS format: https://github.com/user/repo.git
-    if url.starts_with("https://") || url.starts_with("http://") {
-        if let Ok(parsed) = url::Url::parse(url) {
-            let provider = extract_provider(parsed.host_str().unwrap_or(""));
-            let path = parsed.path().trim_start_matches('/').trim_end_matches(".git");
-            let path_parts: Vec<&str> = path.splitn(2, '/').collect();
-            if path_parts.len() == 2 {
-                return Some(ParsedGitUrl {
-                    provider,
-                    username: path_parts[0].to_string(),
-                    name: path_parts[1].to_string(),
-                    url: url.to_string(),
-                });
-            }
+    if (url.starts_with("https://") || url.starts_with("http://"))
+        && let Ok(parsed) = url::Url::parse(url)
+    {
+        let provider = extract_provider(parsed.host_str().unwrap_or(""));
+        let path = parsed.path().trim_start_matches('/').trim_end_matches(".git");
+        let path_parts: Vec<&str> = path.splitn(2, '/').collect();
+        if path_parts.len() == 2 {
+            return Some(ParsedGitUrl {
+                provider,
+                username: path_parts[0].to_string(),
+                name: path_parts[1].to_string(),
+                url: url.to_string(),
+            });
         }
     }
 
@@ -575,3 +976,104 @@ fn extract_provider(host: &str) -> String {
         host.split('.').next().unwrap_or("unknown").to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repository with one commit on `main` and a `feature` branch tracking origin
+    fn repository() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        git_cmd(path, &["init", "-q", "-b", "main"]).unwrap();
+        git_cmd(
+            path,
+            &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-q", "--allow-empty", "-m", "init"],
+        )
+        .unwrap();
+        git_cmd(path, &["branch", "feature"]).unwrap();
+        git_cmd(path, &["config", "branch.feature.remote", "origin"]).unwrap();
+        git_cmd(path, &["config", "branch.feature.merge", "refs/heads/feature"]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rename_keeps_the_new_name_when_retargeting_works() {
+        let repo = repository();
+        let path = repo.path();
+
+        rename_branch_or_undo(path, "feature", "renamed", || Ok(())).unwrap();
+
+        assert!(git_rev_parse(path, "refs/heads/renamed").unwrap());
+        assert!(!git_rev_parse(path, "refs/heads/feature").unwrap());
+        assert_eq!(git_config_get(path, "branch.renamed.remote").as_deref(), Some("origin"));
+    }
+
+    #[test]
+    fn rename_is_undone_when_retargeting_fails() {
+        let repo = repository();
+        let path = repo.path();
+
+        let result = rename_branch_or_undo(path, "feature", "renamed", || {
+            anyhow::bail!("set-upstream failed")
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "set-upstream failed");
+        assert!(git_rev_parse(path, "refs/heads/feature").unwrap());
+        assert!(!git_rev_parse(path, "refs/heads/renamed").unwrap());
+        // The tracking config moved back with the branch
+        assert_eq!(git_config_get(path, "branch.feature.remote").as_deref(), Some("origin"));
+        assert_eq!(
+            git_config_get(path, "branch.feature.merge").as_deref(),
+            Some("refs/heads/feature")
+        );
+        assert_eq!(git_config_get(path, "branch.renamed.remote"), None);
+    }
+
+    #[tokio::test]
+    async fn rename_branch_retargets_a_tracked_upstream() {
+        let repo = repository();
+        let path = repo.path();
+
+        GitOps::new()
+            .rename_branch(path, "feature", "renamed", "origin")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            git_config_get(path, "branch.renamed.merge").as_deref(),
+            Some("refs/heads/renamed")
+        );
+    }
+
+    #[test]
+    fn squash_merges_are_found_without_writing_objects() {
+        let repo = repository();
+        let path = repo.path();
+        let commit = |args: &[&str]| {
+            let mut full = vec!["-c", "user.name=t", "-c", "user.email=t@t"];
+            full.extend_from_slice(args);
+            git_cmd(path, &full).unwrap();
+        };
+        git_cmd(path, &["checkout", "-q", "feature"]).unwrap();
+        std::fs::write(path.join("a.txt"), "a\n").unwrap();
+        git_cmd(path, &["add", "a.txt"]).unwrap();
+        commit(&["commit", "-q", "-m", "a"]);
+        std::fs::write(path.join("b.txt"), "b\n").unwrap();
+        git_cmd(path, &["add", "b.txt"]).unwrap();
+        commit(&["commit", "-q", "-m", "b"]);
+        git_cmd(path, &["checkout", "-q", "main"]).unwrap();
+
+        let git = GitOps::new();
+        assert!(!git.is_squash_merged(path, "refs/heads/feature", "refs/heads/main"));
+
+        git_cmd(path, &["merge", "-q", "--squash", "feature"]).unwrap();
+        commit(&["commit", "-q", "-m", "feature (squashed)"]);
+        let objects = || git.git_output(path, &["count-objects"]).unwrap();
+        let before = objects();
+
+        assert!(git.is_squash_merged(path, "refs/heads/feature", "refs/heads/main"));
+        assert!(git.is_merged(path, "feature", "main", "origin"));
+        assert_eq!(objects(), before);
+    }
+}
@@ -0,0 +1,141 @@
+//! Per-repository lifecycle hooks
+//!
+//! A hook is a shell command run at a lifecycle point: post-clone, post-create,
+//! pre-delete (non-zero exit vetoes the delete), post-delete.
+
+use crate::process::run_streaming;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Default hook timeout in seconds
+pub const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 300;
+
+/// Longest a pre-delete hook may run: the delete request waits for its verdict
+pub const PRE_DELETE_TIMEOUT_SECS: u64 = 30;
+
+/// Lifecycle point a hook runs at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    /// After the repository is cloned and `.main` is installed
+    PostClone,
+    /// After a worktree is created, shared and installed
+    PostCreate,
+    /// Before a worktree is removed; failure vetoes the delete
+    PreDelete,
+    /// After a worktree is removed (runs in the repository directory)
+    PostDelete,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 4] = [
+        Self::PostClone,
+        Self::PostCreate,
+        Self::PreDelete,
+        Self::PostDelete,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PostClone => "post-clone",
+            Self::PostCreate => "post-create",
+            Self::PreDelete => "pre-delete",
+            Self::PostDelete => "post-delete",
+        }
+    }
+}
+
+impl std::str::FromStr for HookEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "post-clone" => Ok(Self::PostClone),
+            "post-create" => Ok(Self::PostCreate),
+            "pre-delete" => Ok(Self::PreDelete),
+            "post-delete" => Ok(Self::PostDelete),
+            _ => bail!("invalid hook event: {}", s),
+        }
+    }
+}
+
+/// Hook configured for a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub repo_id: String,
+    pub event: HookEvent,
+    /// Shell command (run with `sh -c`)
+    pub command: String,
+    pub timeout_secs: u64,
+}
+
+impl Hook {
+    /// How long the hook may run (`timeout_secs`, capped for pre-delete)
+    pub fn timeout(&self) -> Duration {
+        let secs = match self.event {
+            HookEvent::PreDelete => self.timeout_secs.min(PRE_DELETE_TIMEOUT_SECS),
+            _ => self.timeout_secs,
+        };
+        Duration::from_secs(secs)
+    }
+}
+
+/// Environment passed to a hook
+#[derive(Debug, Clone)]
+pub struct HookEnv<'a> {
+    pub repo_id: &'a str,
+    pub repo_name: &'a str,
+    pub repo_path: &'a Path,
+    pub worktree_path: &'a Path,
+    pub branch: &'a str,
+}
+
+/// Run a hook in `cwd`, reporting each output line
+/// Errors on spawn failure, non-zero exit or timeout (the whole process group is killed).
+pub async fn run_hook<F>(hook: &Hook, cwd: &Path, env: &HookEnv<'_>, on_output: F) -> Result<()>
+where
+    F: FnMut(&str),
+{
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(&hook.command)
+        .current_dir(cwd)
+        .env("GROVE_HOOK", hook.event.as_str())
+        .env("GROVE_REPO_ID", env.repo_id)
+        .env("GROVE_REPO_NAME", env.repo_name)
+        .env("GROVE_REPO_PATH", env.repo_path)
+        .env("GROVE_WORKTREE_PATH", env.worktree_path)
+        .env("GROVE_BRANCH", env.branch);
+
+    run_streaming(cmd, hook.timeout(), None, on_output)
+        .await
+        .map_err(|e| anyhow::anyhow!("{} hook {}", hook.event.as_str(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(event: HookEvent, timeout_secs: u64) -> Hook {
+        Hook {
+            repo_id: "r1".to_string(),
+            event,
+            command: "true".to_string(),
+            timeout_secs,
+        }
+    }
+
+    #[test]
+    fn pre_delete_timeout_is_capped() {
+        let cap = Duration::from_secs(PRE_DELETE_TIMEOUT_SECS);
+        assert_eq!(hook(HookEvent::PreDelete, DEFAULT_HOOK_TIMEOUT_SECS).timeout(), cap);
+        assert_eq!(hook(HookEvent::PreDelete, 5).timeout(), Duration::from_secs(5));
+        assert_eq!(
+            hook(HookEvent::PostDelete, DEFAULT_HOOK_TIMEOUT_SECS).timeout(),
+            Duration::from_secs(DEFAULT_HOOK_TIMEOUT_SECS)
+        );
+    }
+}
@@ -1,128 +1,594 @@
 //! Package manager detection and installation
 //!
-//! Supports: bun, pnpm, npm, cargo
-
-use anyhow::Result;
-use std::path::Path;
-use std::process::{Command, Stdio};
-
-/// Detected package manager
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PackageManager {
-    Bun,
-    Pnpm,
-    Npm,
-    Cargo,
+//! Package managers are data, not code: a [`PackageManagerRegistry`] holds an
+//! ordered list of detectors. Built in: bun, pnpm, yarn (berry/classic), npm,
+//! uv, poetry, pip, cargo, go, bundler, composer, mix, gradle, maven.
+//! Custom detectors are read from `package-managers.json` in the grove root.
+//!
+//! Managers that declare `lockfiles` and `reuse` directories can seed a new
+//! worktree from `.main` when the lockfiles match, then run a cheap `verify`.
+//! A [`fingerprint`] of lockfiles and manifests lets unchanged installs be skipped.
+//! In monorepos, nested project roots are installed too, unless an enclosing
+//! workspace covers them (see [`crate::workspace`]).
+
+use crate::process::{run_streaming, RunError};
+use crate::reuse::{self, LinkMethod};
+use crate::workspace;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::watch;
+
+/// A package manager: how to detect it and how to install with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageManager {
+    /// Unique name (e.g. "pnpm", "yarn-berry"); custom entries replace built-ins by name
+    pub name: String,
+    /// At most one manager per ecosystem is used; defaults to the name
+    #[serde(default)]
+    pub ecosystem: String,
+    /// Files in the project root that select this manager (any one matches)
+    pub detect: Vec<String>,
+    /// Program to run
+    pub command: String,
+    /// Install arguments
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Install timeout (defaults to `GROVE_INSTALL_TIMEOUT_MINUTES`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Files that must match `.main` for dependencies to be reused
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lockfiles: Vec<String>,
+    /// Dependency directories seeded from `.main` (e.g. "node_modules")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reuse: Vec<String>,
+    /// Arguments for the check run after seeding (defaults to `args`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify: Option<Vec<String>>,
 }
 
 impl PackageManager {
-    /// Get the command name
-    pub fn command(&self) -> &'static str {
-        match self {
-            Self::Bun => "bun",
-            Self::Pnpm => "pnpm",
-            Self::Npm => "npm",
-            Self::Cargo => "cargo",
+    fn builtin(name: &str, ecosystem: &str, detect: &[&str], command: &str, args: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            ecosystem: ecosystem.to_string(),
+            detect: detect.iter().map(|s| s.to_string()).collect(),
+            command: command.to_string(),
+            args: args.iter().map(|s| s.to_string()).collect(),
+            timeout_secs: None,
+            lockfiles: Vec::new(),
+            reuse: Vec::new(),
+            verify: None,
         }
     }
 
+    /// Reuse `dirs` from `.main` when `lockfiles` match, verifying with `verify`
+    fn reusing(mut self, lockfiles: &[&str], dirs: &[&str], verify: Option<&[&str]>) -> Self {
+        self.lockfiles = lockfiles.iter().map(|s| s.to_string()).collect();
+        self.reuse = dirs.iter().map(|s| s.to_string()).collect();
+        self.verify = verify.map(|args| args.iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    /// Get the command name
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
     /// Get install arguments
-    pub fn install_args(&self) -> &'static [&'static str] {
+    pub fn install_args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Whether this manager applies to a project directory
+    pub fn matches(&self, path: &Path) -> bool {
+        self.detect.iter().any(|f| path.join(f).exists())
+    }
+
+    /// The same manager running its verify arguments instead of a full install
+    pub fn verifier(&self) -> Self {
+        let mut pm = self.clone();
+        if let Some(args) = &self.verify {
+            pm.args = args.clone();
+        }
+        pm
+    }
+
+    /// Seed dependency directories from `main` into `worktree` if lockfiles match
+    /// Directories already present in the worktree are left alone. Returns the
+    /// seeded directories; empty when nothing could be reused.
+    pub fn seed_from(
+        &self,
+        main: &Path,
+        worktree: &Path,
+    ) -> std::io::Result<Vec<(String, LinkMethod)>> {
+        let mut seeded = Vec::new();
+        if self.reuse.is_empty() || !reuse::lockfiles_match(main, worktree, &self.lockfiles) {
+            return Ok(seeded);
+        }
+
+        for dir in &self.reuse {
+            let src = main.join(dir);
+            let dst = worktree.join(dir);
+            if !src.is_dir() || dst.symlink_metadata().is_ok() {
+                continue;
+            }
+            let method = reuse::seed_dir(&src, &dst)?;
+            reuse::relocate(&dst, main, worktree)?;
+            seeded.push((dir.clone(), method));
+        }
+
+        Ok(seeded)
+    }
+}
+
+/// What the cargo install step runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CargoStep {
+    /// `cargo fetch`: download dependencies only
+    Fetch,
+    /// `cargo check`: compile metadata, no codegen
+    Check,
+    /// `cargo build`
+    #[default]
+    Build,
+    /// Skip cargo
+    None,
+}
+
+impl CargoStep {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            Self::Bun => &["install"],
-            Self::Pnpm => &["install"],
-            Self::Npm => &["install"],
-            Self::Cargo => &["build"],
+            Self::Fetch => "fetch",
+            Self::Check => "check",
+            Self::Build => "build",
+            Self::None => "none",
         }
     }
 }
 
-/// Detect package manager(s) for a directory
-/// Returns all detected package managers (a project can have both package.json and Cargo.toml)
-pub fn detect_package_managers(path: &Path) -> Vec<PackageManager> {
-    let mut managers = Vec::new();
+impl std::str::FromStr for CargoStep {
+    type Err = anyhow::Error;
 
-    // JavaScript package managers (mutually exclusive - pick one)
-    if path.join("bun.lock").exists() || path.join("bun.lockb").exists() {
-        managers.push(PackageManager::Bun);
-    } else if path.join("pnpm-lock.yaml").exists() {
-        managers.push(PackageManager::Pnpm);
-    } else if path.join("package-lock.json").exists() {
-        managers.push(PackageManager::Npm);
-    } else if path.join("package.json").exists() {
-        // Default to npm if package.json exists but no lockfile
-        managers.push(PackageManager::Npm);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fetch" => Ok(Self::Fetch),
+            "check" => Ok(Self::Check),
+            "build" => Ok(Self::Build),
+            "none" => Ok(Self::None),
+            _ => anyhow::bail!("invalid cargo step: {} (expected fetch, check, build or none)", s),
+        }
     }
+}
 
-    // Rust (can coexist with JS)
-    if path.join("Cargo.toml").exists() {
-        managers.push(PackageManager::Cargo);
+/// A package manager to run in one project directory of a worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallTarget {
+    /// Project directory relative to the worktree ("" for the root)
+    pub dir: String,
+    pub manager: PackageManager,
+}
+
+impl InstallTarget {
+    /// Manager name, qualified with the directory for nested projects
+    pub fn label(&self) -> String {
+        if self.dir.is_empty() {
+            self.manager.name.clone()
+        } else {
+            format!("{} ({})", self.manager.name, self.dir)
+        }
     }
+}
 
-    managers
+/// Ordered set of package manager detectors
+#[derive(Debug, Clone)]
+pub struct PackageManagerRegistry {
+    managers: Vec<PackageManager>,
 }
 
-/// Run install for a package manager
-/// Returns Ok(()) on success, Err on failure
-pub fn run_install(path: &Path, pm: PackageManager) -> Result<()> {
-    let output = Command::new(pm.command())
-        .args(pm.install_args())
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()?;
-
-    if !output.status.success() {
-        anyhow::bail!(
-            "{} {} failed: {}",
-            pm.command(),
-            pm.install_args().join(" "),
-            String::from_utf8_lossy(&output.stderr)
-        );
+impl Default for PackageManagerRegistry {
+    fn default() -> Self {
+        Self::builtin()
     }
+}
+
+impl PackageManagerRegistry {
+    /// Built-in detectors, in priority order within each ecosystem
+    pub fn builtin() -> Self {
+        let pm = PackageManager::builtin;
+        Self {
+            managers: vec![
+                // JavaScript (lockfile decides; package.json alone falls back to npm)
+                pm("bun", "node", &["bun.lock", "bun.lockb"], "bun", &["install"])
+                    .reusing(&["bun.lock", "bun.lockb"], &["node_modules"], Some(&["install", "--frozen-lockfile"])),
+                pm("pnpm", "node", &["pnpm-lock.yaml"], "pnpm", &["install"])
+                    .reusing(
+                        &["pnpm-lock.yaml"],
+                        &["node_modules"],
+                        Some(&["install", "--frozen-lockfile", "--prefer-offline"]),
+                    ),
+                pm("yarn-berry", "node", &[".yarnrc.yml"], "yarn", &["install"])
+                    .reusing(&["yarn.lock"], &["node_modules"], Some(&["install", "--immutable"])),
+                pm("yarn", "node", &["yarn.lock"], "yarn", &["install"])
+                    .reusing(
+                        &["yarn.lock"],
+                        &["node_modules"],
+                        Some(&["install", "--frozen-lockfile", "--prefer-offline"]),
+                    ),
+                pm("npm", "node", &["package-lock.json", "package.json"], "npm", &["install"])
+                    .reusing(
+                        &["package-lock.json"],
+                        &["node_modules"],
+                        Some(&["install", "--prefer-offline", "--no-audit", "--no-fund"]),
+                    ),
+                // Python
+                pm("uv", "python", &["uv.lock"], "uv", &["sync"])
+                    .reusing(&["uv.lock"], &[".venv"], Some(&["sync", "--frozen"])),
+                pm("poetry", "python", &["poetry.lock"], "poetry", &["install"])
+                    .reusing(&["poetry.lock"], &[".venv"], None),
+                pm(
+                    "pip",
+                    "python",
+                    &["requirements.txt"],
+                    "sh",
+                    &["-c", "python3 -m venv .venv && .venv/bin/pip install -r requirements.txt"],
+                )
+                .reusing(&["requirements.txt"], &[".venv"], None),
+                // Rust
+                pm("cargo", "rust", &["Cargo.toml"], "cargo", &["build"])
+                    .reusing(&["Cargo.lock"], &["target"], None),
+                // Go
+                pm("go", "go", &["go.mod"], "go", &["mod", "download"]),
+                // Ruby
+                pm("bundler", "ruby", &["Gemfile"], "bundle", &["install"]),
+                // PHP
+                pm("composer", "php", &["composer.json"], "composer", &["install"])
+                    .reusing(&["composer.lock"], &["vendor"], None),
+                // Elixir
+                pm("mix", "elixir", &["mix.exs"], "mix", &["deps.get"])
+                    .reusing(&["mix.lock"], &["deps", "_build"], None),
+                // JVM (prefer project wrappers)
+                pm("gradle-wrapper", "jvm", &["gradlew"], "./gradlew", &["--quiet", "dependencies"]),
+                pm("gradle", "jvm", &["build.gradle", "build.gradle.kts"], "gradle", &["--quiet", "dependencies"]),
+                pm("maven-wrapper", "jvm", &["mvnw"], "./mvnw", &["-q", "dependency:go-offline"]),
+                pm("maven", "jvm", &["pom.xml"], "mvn", &["-q", "dependency:go-offline"]),
+            ],
+        }
+    }
+
+    /// Built-in detectors plus custom ones from `package-managers.json`
+    /// Custom entries take priority; an entry with a built-in's name replaces it.
+    pub fn load(config: &Config) -> Self {
+        let mut registry = Self::builtin();
+
+        let path = &config.package_managers_path;
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return registry;
+        };
 
-    Ok(())
+        match serde_json::from_str::<Vec<PackageManager>>(&contents) {
+            Ok(custom) => registry.register_all(custom),
+            Err(e) => tracing::warn!("Ignoring {}: {}", path.display(), e),
+        }
+
+        registry
+    }
+
+    /// Register custom detectors ahead of the existing ones
+    pub fn register_all(&mut self, custom: Vec<PackageManager>) {
+        let mut custom: Vec<PackageManager> = custom
+            .into_iter()
+            .map(|mut pm| {
+                if pm.ecosystem.is_empty() {
+                    pm.ecosystem = pm.name.clone();
+                }
+                pm
+            })
+            .collect();
+
+        self.managers
+            .retain(|existing| !custom.iter().any(|pm| pm.name == existing.name));
+        custom.append(&mut self.managers);
+        self.managers = custom;
+    }
+
+    /// All registered managers in priority order
+    pub fn managers(&self) -> &[PackageManager] {
+        &self.managers
+    }
+
+    /// Install targets for a worktree: the root plus nested projects
+    /// `paths` (relative) replaces discovery of nested projects; otherwise every
+    /// directory down to [`workspace::MAX_PROJECT_DEPTH`] is checked and projects
+    /// that are members of an enclosing workspace are left to that workspace.
+    pub fn discover(&self, root: &Path, paths: &[String], cargo_step: CargoStep) -> Vec<InstallTarget> {
+        let mut dirs = vec![PathBuf::new()];
+        if paths.is_empty() {
+            dirs.extend(workspace::candidate_dirs(root));
+        } else {
+            dirs.extend(
+                paths
+                    .iter()
+                    .map(|p| p.trim().trim_start_matches("./").trim_end_matches('/'))
+                    .filter(|p| !p.is_empty() && *p != ".")
+                    .map(PathBuf::from),
+            );
+        }
+
+        let mut targets: Vec<InstallTarget> = Vec::new();
+        for dir in dirs {
+            for mut pm in self.detect(&root.join(&dir)) {
+                if pm.name == "cargo" {
+                    match cargo_step {
+                        CargoStep::None => continue,
+                        step => pm.args = vec![step.as_str().to_string()],
+                    }
+                }
+                if paths.is_empty() && in_workspace(root, &targets, &dir, &pm.ecosystem) {
+                    continue;
+                }
+                targets.push(InstallTarget {
+                    dir: dir.to_string_lossy().to_string(),
+                    manager: pm,
+                });
+            }
+        }
+
+        targets
+    }
+
+    /// Detect package manager(s) for a directory, at most one per ecosystem
+    /// (a project can have both package.json and Cargo.toml)
+    pub fn detect(&self, path: &Path) -> Vec<PackageManager> {
+        let mut seen = HashSet::new();
+        self.managers
+            .iter()
+            .filter(|pm| pm.matches(path))
+            .filter(|pm| seen.insert(pm.ecosystem.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Whether a nested project is a member of a workspace that an existing
+/// target of the same ecosystem (in an ancestor directory) installs
+fn in_workspace(root: &Path, targets: &[InstallTarget], dir: &Path, ecosystem: &str) -> bool {
+    if dir.as_os_str().is_empty() {
+        return false;
+    }
+    targets
+        .iter()
+        .filter(|t| t.manager.ecosystem == ecosystem)
+        .any(|t| {
+            let Ok(rel) = dir.strip_prefix(&t.dir) else {
+                return false;
+            };
+            workspace::workspace_members(&root.join(&t.dir), ecosystem)
+                .is_some_and(|members| workspace::is_member(&members, rel))
+        })
 }
 
-/// Run install for all detected package managers
-/// Continues even if one fails, returns all errors
-pub fn run_all_installs(path: &Path) -> Vec<(PackageManager, Result<()>)> {
-    let managers = detect_package_managers(path);
-    managers
-        .into_iter()
-        .map(|pm| (pm, run_install(path, pm)))
-        .collect()
+/// Hash of the install targets and their lockfiles and manifests under `path`
+/// None when there is nothing to install.
+pub fn fingerprint(path: &Path, targets: &[InstallTarget]) -> Option<String> {
+    if targets.is_empty() {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    for target in targets {
+        let pm = &target.manager;
+        let dir = path.join(&target.dir);
+        hasher.update(target.dir.as_bytes());
+        hasher.update([0]);
+        hasher.update(pm.name.as_bytes());
+        hasher.update(pm.args.join(" ").as_bytes());
+        let files: BTreeSet<&String> = pm.detect.iter().chain(&pm.lockfiles).collect();
+        for file in files {
+            hasher.update([0]);
+            hasher.update(file.as_bytes());
+            match std::fs::read(dir.join(file)) {
+                Ok(contents) => {
+                    hasher.update([1]);
+                    hasher.update((contents.len() as u64).to_le_bytes());
+                    hasher.update(&contents);
+                }
+                Err(_) => hasher.update([2]),
+            }
+        }
+    }
+
+    Some(format!("{:x}", hasher.finalize()))
 }
 
-/// Run install with progress callback
-/// Callback receives stderr/stdout lines as they come
-pub fn run_install_with_progress<F>(path: &Path, pm: PackageManager, mut on_progress: F) -> Result<()>
+/// Detect package manager(s) for a directory using the built-in detectors
+pub fn detect_package_managers(path: &Path) -> Vec<PackageManager> {
+    PackageManagerRegistry::builtin().detect(path)
+}
+
+/// Run install for a package manager as an async child process
+/// Output lines go to `on_line`; the timeout and `cancel` kill the whole process group.
+pub async fn run_install<F>(
+    path: &Path,
+    pm: &PackageManager,
+    default_timeout: Duration,
+    cancel: Option<watch::Receiver<bool>>,
+    on_line: F,
+) -> Result<(), RunError>
 where
     F: FnMut(&str),
 {
-    use std::io::{BufRead, BufReader};
-
-    let mut child = Command::new(pm.command())
-        .args(pm.install_args())
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    // Read stderr for progress (most package managers output progress there)
-    if let Some(stderr) = child.stderr.take() {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines().map_while(Result::ok) {
-            if !line.is_empty() {
-                on_progress(&line);
-            }
+    let mut cmd = Command::new(pm.command());
+    cmd.args(pm.install_args()).current_dir(path);
+
+    let timeout = pm
+        .timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(default_timeout);
+
+    run_streaming(cmd, timeout, cancel, on_line).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create `files` (relative paths, parents included) under `root`
+    fn touch(root: &Path, files: &[(&str, &str)]) {
+        for (file, contents) in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
         }
     }
 
-    let status = child.wait()?;
-    if !status.success() {
-        anyhow::bail!("{} {} failed", pm.command(), pm.install_args().join(" "));
+    fn names(managers: &[PackageManager]) -> Vec<&str> {
+        managers.iter().map(|pm| pm.name.as_str()).collect()
+    }
+
+    fn labels(targets: &[InstallTarget]) -> Vec<String> {
+        targets.iter().map(InstallTarget::label).collect()
     }
 
-    Ok(())
+    fn load(dir: &Path, contents: &str) -> PackageManagerRegistry {
+        let path = dir.join("package-managers.json");
+        std::fs::write(&path, contents).unwrap();
+        PackageManagerRegistry::load(&Config {
+            package_managers_path: path,
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn detect_picks_one_manager_per_ecosystem_by_lockfile() {
+        let registry = PackageManagerRegistry::builtin();
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["package.json", "yarn.lock"], &["yarn"]),
+            (&["package.json", "yarn.lock", ".yarnrc.yml"], &["yarn-berry"]),
+            (&["package.json", "package-lock.json"], &["npm"]),
+            (&["package.json"], &["npm"]),
+            (&["package.json", "pnpm-lock.yaml", "Cargo.toml"], &["pnpm", "cargo"]),
+            (&["README.md"], &[]),
+        ];
+
+        for (files, expected) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            let files: Vec<(&str, &str)> = files.iter().map(|f| (*f, "")).collect();
+            touch(dir.path(), &files);
+            assert_eq!(names(&registry.detect(dir.path())), *expected, "{:?}", files);
+        }
+    }
+
+    #[test]
+    fn discover_leaves_workspace_members_to_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(
+            dir.path(),
+            &[
+                ("package.json", r#"{ "workspaces": ["packages/*"] }"#),
+                ("yarn.lock", ""),
+                ("packages/web/package.json", "{}"),
+                ("tools/cli/Cargo.toml", "[package]\nname = \"cli\"\n"),
+                ("node_modules/dep/package.json", "{}"),
+            ],
+        );
+        let registry = PackageManagerRegistry::builtin();
+
+        let targets = registry.discover(dir.path(), &[], CargoStep::Check);
+        assert_eq!(labels(&targets), vec!["yarn", "cargo (tools/cli)"]);
+        assert_eq!(targets[1].manager.args, vec!["check"]);
+
+        let targets = registry.discover(dir.path(), &[], CargoStep::None);
+        assert_eq!(labels(&targets), vec!["yarn"]);
+
+        // Declared paths replace discovery, members included
+        let paths = vec!["./packages/web/".to_string()];
+        let targets = registry.discover(dir.path(), &paths, CargoStep::Build);
+        assert_eq!(labels(&targets), vec!["yarn", "npm (packages/web)"]);
+    }
+
+    #[test]
+    fn fingerprint_follows_lockfiles_and_managers() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        touch(root, &[("package.json", "{}"), ("package-lock.json", "v1"), ("src/index.js", "")]);
+        let registry = PackageManagerRegistry::builtin();
+        let targets = registry.discover(root, &[], CargoStep::Build);
+
+        let first = fingerprint(root, &targets).unwrap();
+        assert_eq!(fingerprint(root, &targets).unwrap(), first);
+        assert_eq!(fingerprint(root, &registry.discover(root, &[], CargoStep::Build)).unwrap(), first);
+
+        // Source files don't count
+        touch(root, &[("src/index.js", "changed")]);
+        assert_eq!(fingerprint(root, &targets).unwrap(), first);
+
+        touch(root, &[("package-lock.json", "v2")]);
+        let relocked = fingerprint(root, &targets).unwrap();
+        assert_ne!(relocked, first);
+
+        // Same files, another manager (or other arguments)
+        touch(root, &[("yarn.lock", "")]);
+        let yarn = registry.discover(root, &[], CargoStep::Build);
+        assert_eq!(labels(&yarn), vec!["yarn"]);
+        assert_ne!(fingerprint(root, &yarn).unwrap(), relocked);
+        let mut verifying = targets.clone();
+        verifying[0].manager = verifying[0].manager.verifier();
+        assert_ne!(fingerprint(root, &verifying).unwrap(), relocked);
+
+        touch(root, &[("Cargo.toml", "[package]\nname = \"app\"\n")]);
+        let build = fingerprint(root, &registry.discover(root, &[], CargoStep::Build)).unwrap();
+        let check = fingerprint(root, &registry.discover(root, &[], CargoStep::Check)).unwrap();
+        assert_ne!(build, check);
+
+        assert_eq!(fingerprint(root, &[]), None);
+    }
+
+    #[test]
+    fn load_puts_custom_entries_first_and_replaces_builtins_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = load(
+            dir.path(),
+            r#"[
+                { "name": "npm", "ecosystem": "node", "detect": ["package.json"], "command": "npm", "args": ["ci"] },
+                { "name": "just", "detect": ["justfile"], "command": "just", "args": ["setup"] }
+            ]"#,
+        );
+
+        let managers = registry.managers();
+        assert_eq!(names(&managers[..2]), vec!["npm", "just"]);
+        assert_eq!(managers.iter().filter(|pm| pm.name == "npm").count(), 1);
+        assert_eq!(managers[0].args, vec!["ci"]);
+        assert_eq!(managers[1].ecosystem, "just");
+        assert_eq!(managers.len(), PackageManagerRegistry::builtin().managers().len() + 1);
+
+        // The custom npm now wins over yarn for the node ecosystem
+        touch(dir.path(), &[("package.json", "{}"), ("yarn.lock", "")]);
+        assert_eq!(names(&registry.detect(dir.path())), vec!["npm"]);
+    }
+
+    #[test]
+    fn load_ignores_a_malformed_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let builtin = PackageManagerRegistry::builtin();
+
+        // Missing the required `command`
+        let registry = load(dir.path(), r#"[{ "name": "just", "detect": ["justfile"] }]"#);
+        assert_eq!(registry.managers(), builtin.managers());
+
+        let registry = load(dir.path(), "{ not json");
+        assert_eq!(registry.managers(), builtin.managers());
+
+        let missing = PackageManagerRegistry::load(&Config {
+            package_managers_path: dir.path().join("missing.json"),
+            ..Config::default()
+        });
+        assert_eq!(missing.managers(), builtin.managers());
+    }
 }
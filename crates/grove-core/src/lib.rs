@@ -5,14 +5,46 @@
 
 pub mod config;
 pub mod db;
+pub mod disk;
+pub mod failure;
 pub mod git;
+pub mod hooks;
 pub mod install;
+pub mod logs;
+pub mod maintenance;
+pub mod migrations;
+pub mod operations;
+pub mod process;
+pub mod progress;
+pub mod rebuild;
+pub mod reuse;
+pub mod search;
+pub mod stale;
 pub mod state;
+pub mod toolchain;
+pub mod trash;
 pub mod types;
+pub mod workspace;
 
 pub use config::Config;
 pub use db::Database;
+pub use disk::DiskUsage;
+pub use failure::{ErrorCategory, SetupError, SetupStep};
 pub use git::{share_files, GitOps};
-pub use install::{detect_package_managers, run_install, PackageManager};
-pub use state::{FullState, StateManager};
+pub use hooks::{Hook, HookEvent};
+pub use install::{
+    detect_package_managers, fingerprint, run_install, CargoStep, InstallTarget, PackageManager,
+    PackageManagerRegistry,
+};
+pub use logs::{InstallLog, LogHub};
+pub use maintenance::{MaintenanceRun, MaintenanceTask};
+pub use operations::{Operation, OperationFilter, OperationKind, OperationOutcome, Requester};
+pub use progress::{Progress, ProgressPhase, ProgressUpdate, Severity, TargetKind};
+pub use rebuild::{Difference, RebuildReport};
+pub use reuse::LinkMethod;
+pub use search::{SearchHit, SearchHitKind, SearchQuery};
+pub use stale::{CleanupMode, CleanupReport, StaleWorktree};
+pub use state::{EventFilter, FullState, SequencedEvent, Snapshot, StateEvent, StateManager};
+pub use toolchain::{ToolchainStatus, VersionManager};
+pub use trash::TrashedRepository;
 pub use types::*;
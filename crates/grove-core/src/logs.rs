@@ -0,0 +1,125 @@
+//! Persisted, streamable operation logs
+//!
+//! Each install run writes its output to `data_dir/logs/{id}.log`. While the
+//! run is active, lines are also broadcast (with their 0-based line number) so
+//! clients can read the file and then follow along without gaps or repeats.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Log record from database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallLog {
+    pub id: String,
+    pub repo_id: String,
+    /// Worktree the install ran in
+    pub worktree_path: String,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    /// None while running
+    pub success: Option<bool>,
+}
+
+/// Registry of log files and live log streams
+pub struct LogHub {
+    dir: PathBuf,
+    live: Mutex<HashMap<String, broadcast::Sender<(usize, String)>>>,
+}
+
+impl LogHub {
+    /// Create hub storing logs in `dir`
+    pub fn new(dir: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            dir,
+            live: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Path of a log file
+    pub fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.log", id))
+    }
+
+    /// Start a new log; lines are streamed until the writer is dropped
+    pub fn create(self: &Arc<Self>, id: &str) -> Result<LogWriter> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(id);
+        let file = File::create(&path)
+            .with_context(|| format!("failed to create log {}", path.display()))?;
+
+        let (tx, _) = broadcast::channel(256);
+        self.live
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), tx.clone());
+
+        Ok(LogWriter {
+            hub: Arc::clone(self),
+            id: id.to_string(),
+            file,
+            lines: 0,
+            tx,
+        })
+    }
+
+    /// Subscribe to a running log (None once it has finished)
+    pub fn subscribe(&self, id: &str) -> Option<broadcast::Receiver<(usize, String)>> {
+        self.live.lock().unwrap().get(id).map(|tx| tx.subscribe())
+    }
+
+    /// Read a log, optionally only the last `tail` lines
+    pub fn read(&self, id: &str, tail: Option<usize>) -> Result<String> {
+        let contents = std::fs::read_to_string(self.path(id))?;
+        Ok(match tail {
+            Some(n) => {
+                let lines: Vec<&str> = contents.lines().collect();
+                let start = lines.len().saturating_sub(n);
+                lines[start..].join("\n")
+            }
+            None => contents,
+        })
+    }
+
+    /// Delete a log file
+    pub fn remove(&self, id: &str) {
+        let _ = std::fs::remove_file(self.path(id));
+    }
+}
+
+/// Appends lines to a log file and broadcasts them to subscribers
+pub struct LogWriter {
+    hub: Arc<LogHub>,
+    id: String,
+    file: File,
+    lines: usize,
+    tx: broadcast::Sender<(usize, String)>,
+}
+
+impl LogWriter {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Append a line (written through so readers can tail the file)
+    pub fn line(&mut self, line: &str) {
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            tracing::warn!("Failed to write log {}: {}", self.id, e);
+        }
+        // Ignore send errors (no subscribers)
+        let _ = self.tx.send((self.lines, line.to_string()));
+        self.lines += 1;
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        // Dropping the sender ends every subscriber's stream
+        self.hub.live.lock().unwrap().remove(&self.id);
+    }
+}
@@ -0,0 +1,121 @@
+//! Repository maintenance for bare repos
+//!
+//! Frequent fetches leave loose objects and many small packs under `.bare`.
+//! Supports: gc, repack, commit-graph, multi-pack-index
+
+use crate::disk::dir_size;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// Maintenance task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaintenanceTask {
+    Gc,
+    Repack,
+    CommitGraph,
+    MultiPackIndex,
+}
+
+impl MaintenanceTask {
+    /// All tasks in the order they should run
+    pub const ALL: [MaintenanceTask; 4] = [
+        Self::Gc,
+        Self::Repack,
+        Self::CommitGraph,
+        Self::MultiPackIndex,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gc => "gc",
+            Self::Repack => "repack",
+            Self::CommitGraph => "commit-graph",
+            Self::MultiPackIndex => "multi-pack-index",
+        }
+    }
+
+    /// git invocations for this task
+    fn commands(&self) -> &'static [&'static [&'static str]] {
+        match self {
+            Self::Gc => &[&["gc", "--quiet"]],
+            Self::Repack => &[&["repack", "-a", "-d", "-q"]],
+            Self::CommitGraph => &[&["commit-graph", "write", "--reachable", "--split"]],
+            Self::MultiPackIndex => &[
+                &["multi-pack-index", "write"],
+                &["multi-pack-index", "expire"],
+            ],
+        }
+    }
+}
+
+impl std::str::FromStr for MaintenanceTask {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gc" => Ok(Self::Gc),
+            "repack" => Ok(Self::Repack),
+            "commit-graph" => Ok(Self::CommitGraph),
+            "multi-pack-index" => Ok(Self::MultiPackIndex),
+            _ => bail!("invalid maintenance task: {}", s),
+        }
+    }
+}
+
+/// Result of one maintenance task on one repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRun {
+    pub repo_id: String,
+    pub task: MaintenanceTask,
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// Size of `.bare` in bytes before the task
+    pub size_before: u64,
+    /// Size of `.bare` in bytes after the task
+    pub size_after: u64,
+    pub error: Option<String>,
+}
+
+/// Run a maintenance task against a bare repository (blocking)
+pub fn run_task(repo_id: &str, bare_path: &Path, task: MaintenanceTask) -> MaintenanceRun {
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let size_before = dir_size(bare_path);
+
+    let error = task
+        .commands()
+        .iter()
+        .try_for_each(|args| git(bare_path, args))
+        .err()
+        .map(|e| e.to_string());
+
+    MaintenanceRun {
+        repo_id: repo_id.to_string(),
+        task,
+        started_at,
+        finished_at: chrono::Utc::now().timestamp_millis(),
+        size_before,
+        size_after: dir_size(bare_path),
+        error,
+    }
+}
+
+fn git(cwd: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .with_context(|| format!("failed to execute git {}", args.join(" ")))?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,576 @@
+//! Versioned schema migrations
+//!
+//! The schema version lives in `PRAGMA user_version`. Each migration runs in
+//! its own transaction together with the version bump, so a failed migration
+//! leaves the database at the previous version. The database file is backed up
+//! before any migration runs, and a database written by a newer grove (higher
+//! version than [`LATEST_VERSION`]) is refused.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+
+/// One schema change, applied in version order
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// All migrations, oldest first; append only
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        up: initial,
+    },
+    Migration {
+        version: 2,
+        description: "operation history",
+        up: operations,
+    },
+    Migration {
+        version: 3,
+        description: "repository tags, groups and favorites",
+        up: labels,
+    },
+    Migration {
+        version: 4,
+        description: "worktree notes, issue links and owners",
+        up: worktree_notes,
+    },
+    Migration {
+        version: 5,
+        description: "search index",
+        up: search_index,
+    },
+    Migration {
+        version: 6,
+        description: "worktree activity",
+        up: worktree_activity,
+    },
+    Migration {
+        version: 7,
+        description: "sharing patterns as lists",
+        up: pattern_lists,
+    },
+    Migration {
+        version: 8,
+        description: "setup errors",
+        up: setup_errors,
+    },
+    Migration {
+        version: 9,
+        description: "search worktrees under renamed repositories",
+        up: search_repository_rename,
+    },
+    Migration {
+        version: 10,
+        description: "install paths as lists",
+        up: install_path_lists,
+    },
+];
+
+/// Schema version this binary writes
+pub const LATEST_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Bring a database up to [`LATEST_VERSION`]
+/// Returns the backup taken before migrating (None if nothing ran or the
+/// database was empty).
+pub fn migrate(conn: &mut Connection, backup_dir: &Path) -> Result<Option<PathBuf>> {
+    let current = user_version(conn)?;
+    if current > LATEST_VERSION {
+        bail!(
+            "Database schema version {} is newer than this grove supports ({}); upgrade grove",
+            current,
+            LATEST_VERSION
+        );
+    }
+    if current == LATEST_VERSION {
+        return Ok(None);
+    }
+
+    let backup = if has_tables(conn)? {
+        Some(backup(conn, backup_dir, current)?)
+    } else {
+        None
+    };
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx).with_context(|| {
+            format!(
+                "Migration {} ({}) failed",
+                migration.version, migration.description
+            )
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        tracing::info!(
+            "Migrated database to version {} ({})",
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(backup)
+}
+
+/// Schema version of the database at `path` without opening it for writing
+/// (None if the file doesn't exist)
+pub fn schema_version(path: &Path) -> Result<Option<i64>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    Ok(Some(user_version(&conn)?))
+}
+
+fn user_version(conn: &Connection) -> Result<i64> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+fn has_tables(conn: &Connection) -> Result<bool> {
+    Ok(conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table'")?
+        .exists([])?)
+}
+
+/// Copy the database to `backup_dir/repos.v{version}.{timestamp}.db`
+fn backup(conn: &Connection, backup_dir: &Path, version: i64) -> Result<PathBuf> {
+    std::fs::create_dir_all(backup_dir)?;
+    let path = backup_dir.join(format!(
+        "repos.v{}.{}.db",
+        version,
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    conn.execute("VACUUM INTO ?", [path.to_string_lossy()])
+        .context("Failed to back up database before migrating")?;
+    tracing::info!("Backed up database to {}", path.display());
+    Ok(path)
+}
+
+// ─────────────────────────────────────────────────────────────
+// Migrations
+// ─────────────────────────────────────────────────────────────
+
+/// Tables as of the first versioned release
+/// Databases from before versioning have user_version 0 and may lack columns
+/// that were added in place, so those are added when missing.
+fn initial(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("schema.sql"))?;
+
+    add_column_if_missing(conn, "worktrees", "package_managers", "TEXT")?;
+    add_column_if_missing(conn, "worktrees", "install_status", "TEXT")?;
+    add_column_if_missing(conn, "worktrees", "deps_fingerprint", "TEXT")?;
+    add_column_if_missing(conn, "worktrees", "deps_state", "TEXT")?;
+    add_column_if_missing(conn, "worktrees", "toolchains", "TEXT")?;
+    add_column_if_missing(conn, "worktree_config", "install_paths", "TEXT")?;
+    add_column_if_missing(conn, "worktree_config", "cargo_step", "TEXT")?;
+    Ok(())
+}
+
+/// History of mutations; kept when their repository is deleted
+fn operations(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE operations (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            requester TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            finished_at INTEGER,
+            outcome TEXT NOT NULL,
+            error TEXT
+        );
+        CREATE INDEX idx_operations_started ON operations(started_at);
+        CREATE INDEX idx_operations_target ON operations(target);",
+    )?;
+    Ok(())
+}
+
+/// Tags (many per repository), a group (one) and a favorite flag
+fn labels(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE repository_tags (
+            repo_id TEXT NOT NULL REFERENCES repositories(id),
+            tag TEXT NOT NULL,
+            PRIMARY KEY (repo_id, tag)
+        );
+        CREATE INDEX idx_repository_tags_tag ON repository_tags(tag);
+        CREATE TABLE repository_groups (
+            repo_id TEXT PRIMARY KEY REFERENCES repositories(id),
+            name TEXT NOT NULL
+        );
+        CREATE INDEX idx_repository_groups_name ON repository_groups(name);
+        CREATE TABLE repository_favorites (
+            repo_id TEXT PRIMARY KEY REFERENCES repositories(id),
+            pinned_at INTEGER NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// Free-form context on a worktree; moves with the row when it is renamed
+fn worktree_notes(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE worktrees ADD COLUMN note TEXT;
+        ALTER TABLE worktrees ADD COLUMN issue_url TEXT;
+        ALTER TABLE worktrees ADD COLUMN owner TEXT;",
+    )?;
+    Ok(())
+}
+
+/// Last time a worktree was opened in an editor (stale detection)
+fn worktree_activity(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE worktrees ADD COLUMN last_opened_at INTEGER;")?;
+    Ok(())
+}
+
+/// Search labels of the repository `{id}`: its tags and group
+const REPO_LABELS: &str = "trim(
+    COALESCE((SELECT group_concat(tag, ' ') FROM repository_tags WHERE repo_id = {id}), '')
+    || ' ' || COALESCE((SELECT name FROM repository_groups WHERE repo_id = {id}), ''))";
+
+/// Full-text index over repositories and worktrees (`search.rs`)
+/// One row per live repository and worktree, kept current by triggers so every
+/// write path (including trash and restore) updates it. The trigram tokenizer
+/// matches substrings, which the fuzzy ranking builds on.
+fn search_index(conn: &Connection) -> Result<()> {
+    let repo_labels = |id: &str| REPO_LABELS.replace("{id}", id);
+
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE search_index USING fts5(
+            kind UNINDEXED, repo_id UNINDEXED, path UNINDEXED,
+            name, url, branch, subject, labels, note, issue, owner,
+            tokenize = 'trigram'
+        );
+
+        CREATE TRIGGER search_repository_insert AFTER INSERT ON repositories
+        WHEN NEW.deleted_at IS NULL
+        BEGIN
+            INSERT INTO search_index (kind, repo_id, name, url, labels)
+            VALUES ('repository', NEW.id, NEW.username || '/' || NEW.name, NEW.clone_url, {new_labels});
+        END;
+
+        CREATE TRIGGER search_repository_update AFTER UPDATE OF username, name, clone_url, deleted_at ON repositories
+        BEGIN
+            DELETE FROM search_index WHERE kind = 'repository' AND repo_id = OLD.id;
+            INSERT INTO search_index (kind, repo_id, name, url, labels)
+            SELECT 'repository', NEW.id, NEW.username || '/' || NEW.name, NEW.clone_url, {new_labels}
+            WHERE NEW.deleted_at IS NULL;
+        END;
+
+        CREATE TRIGGER search_repository_delete AFTER DELETE ON repositories
+        BEGIN
+            DELETE FROM search_index WHERE repo_id = OLD.id;
+        END;
+
+        CREATE TRIGGER search_tag_insert AFTER INSERT ON repository_tags
+        BEGIN
+            UPDATE search_index SET labels = {new_labels_of}
+            WHERE kind = 'repository' AND repo_id = NEW.repo_id;
+        END;
+
+        CREATE TRIGGER search_tag_delete AFTER DELETE ON repository_tags
+        BEGIN
+            UPDATE search_index SET labels = {old_labels_of}
+            WHERE kind = 'repository' AND repo_id = OLD.repo_id;
+        END;
+
+        CREATE TRIGGER search_group_insert AFTER INSERT ON repository_groups
+        BEGIN
+            UPDATE search_index SET labels = {new_labels_of}
+            WHERE kind = 'repository' AND repo_id = NEW.repo_id;
+        END;
+
+        CREATE TRIGGER search_group_update AFTER UPDATE ON repository_groups
+        BEGIN
+            UPDATE search_index SET labels = {new_labels_of}
+            WHERE kind = 'repository' AND repo_id = NEW.repo_id;
+        END;
+
+        CREATE TRIGGER search_group_delete AFTER DELETE ON repository_groups
+        BEGIN
+            UPDATE search_index SET labels = {old_labels_of}
+            WHERE kind = 'repository' AND repo_id = OLD.repo_id;
+        END;
+
+        CREATE TRIGGER search_worktree_insert AFTER INSERT ON worktrees
+        WHEN NEW.deleted_at IS NULL
+        BEGIN
+            INSERT INTO search_index (kind, repo_id, path, name, branch, subject, note, issue, owner)
+            SELECT 'worktree', NEW.repo_id, NEW.path, r.username || '/' || r.name, NEW.branch,
+                   NEW.commit_message, NEW.note, NEW.issue_url, NEW.owner
+            FROM repositories r WHERE r.id = NEW.repo_id;
+        END;
+
+        CREATE TRIGGER search_worktree_update
+        AFTER UPDATE OF path, branch, commit_message, note, issue_url, owner, deleted_at ON worktrees
+        BEGIN
+            DELETE FROM search_index WHERE kind = 'worktree' AND path = OLD.path;
+            INSERT INTO search_index (kind, repo_id, path, name, branch, subject, note, issue, owner)
+            SELECT 'worktree', NEW.repo_id, NEW.path, r.username || '/' || r.name, NEW.branch,
+                   NEW.commit_message, NEW.note, NEW.issue_url, NEW.owner
+            FROM repositories r WHERE r.id = NEW.repo_id AND NEW.deleted_at IS NULL;
+        END;
+
+        CREATE TRIGGER search_worktree_delete AFTER DELETE ON worktrees
+        BEGIN
+            DELETE FROM search_index WHERE kind = 'worktree' AND path = OLD.path;
+        END;
+
+        INSERT INTO search_index (kind, repo_id, name, url, labels)
+        SELECT 'repository', r.id, r.username || '/' || r.name, r.clone_url, {r_labels}
+        FROM repositories r WHERE r.deleted_at IS NULL;
+
+        INSERT INTO search_index (kind, repo_id, path, name, branch, subject, note, issue, owner)
+        SELECT 'worktree', w.repo_id, w.path, r.username || '/' || r.name, w.branch,
+               w.commit_message, w.note, w.issue_url, w.owner
+        FROM worktrees w JOIN repositories r ON r.id = w.repo_id
+        WHERE w.deleted_at IS NULL AND r.deleted_at IS NULL;",
+        new_labels = repo_labels("NEW.id"),
+        new_labels_of = repo_labels("NEW.repo_id"),
+        old_labels_of = repo_labels("OLD.repo_id"),
+        r_labels = repo_labels("r.id"),
+    ))?;
+    Ok(())
+}
+
+/// Comma-joined text as a JSON array of its trimmed, non-empty items
+fn comma_list_to_json(value: Option<String>) -> Result<String> {
+    let items: Vec<&str> = value
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    Ok(serde_json::to_string(&items)?)
+}
+
+/// `worktree_config` symlink and copy patterns: comma-joined text -> JSON arrays
+fn pattern_lists(conn: &Connection) -> Result<()> {
+    let rows: Vec<(String, Option<String>, Option<String>)> = conn
+        .prepare("SELECT repo_id, symlink_patterns, copy_patterns FROM worktree_config")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut update = conn.prepare(
+        "UPDATE worktree_config SET symlink_patterns = ?, copy_patterns = ? WHERE repo_id = ?",
+    )?;
+    for (repo_id, symlink, copy) in rows {
+        update.execute(rusqlite::params![
+            comma_list_to_json(symlink)?,
+            comma_list_to_json(copy)?,
+            repo_id
+        ])?;
+    }
+    Ok(())
+}
+
+/// Why a clone or worktree creation failed (`failure::SetupError` as JSON)
+fn setup_errors(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE repositories ADD COLUMN error TEXT;
+         ALTER TABLE worktrees ADD COLUMN error TEXT;",
+    )?;
+    Ok(())
+}
+
+/// Worktree search rows carry their repository's name; renaming the repository
+/// now renames them too, and rows that missed a rename are brought up to date
+fn search_repository_rename(conn: &Connection) -> Result<()> {
+    conn.execute_batch(&format!(
+        "DROP TRIGGER search_repository_update;
+
+        CREATE TRIGGER search_repository_update AFTER UPDATE OF username, name, clone_url, deleted_at ON repositories
+        BEGIN
+            DELETE FROM search_index WHERE kind = 'repository' AND repo_id = OLD.id;
+            INSERT INTO search_index (kind, repo_id, name, url, labels)
+            SELECT 'repository', NEW.id, NEW.username || '/' || NEW.name, NEW.clone_url, {new_labels}
+            WHERE NEW.deleted_at IS NULL;
+            UPDATE search_index SET name = NEW.username || '/' || NEW.name
+            WHERE kind = 'worktree' AND repo_id = NEW.id;
+        END;
+
+        UPDATE search_index SET name = (
+            SELECT r.username || '/' || r.name FROM repositories r WHERE r.id = search_index.repo_id
+        )
+        WHERE kind = 'worktree';",
+        new_labels = REPO_LABELS.replace("{id}", "NEW.id"),
+    ))?;
+    Ok(())
+}
+
+/// `worktree_config` install paths: comma-joined text -> JSON arrays
+/// NULL (discover) stays NULL.
+fn install_path_lists(conn: &Connection) -> Result<()> {
+    let rows: Vec<(String, String)> = conn
+        .prepare("SELECT repo_id, install_paths FROM worktree_config WHERE install_paths IS NOT NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut update = conn.prepare("UPDATE worktree_config SET install_paths = ? WHERE repo_id = ?")?;
+    for (repo_id, paths) in rows {
+        update.execute(rusqlite::params![comma_list_to_json(Some(paths))?, repo_id])?;
+    }
+    Ok(())
+}
+
+/// Add a column to an existing table (CREATE TABLE IF NOT EXISTS won't)
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?", table))?
+        .exists([column])?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `schema.sql` as shipped before versioning (user_version 0)
+    const BASELINE_SCHEMA: &str = "
+        CREATE TABLE repositories (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            username TEXT NOT NULL,
+            name TEXT NOT NULL,
+            clone_url TEXT NOT NULL,
+            local_path TEXT NOT NULL UNIQUE,
+            type TEXT DEFAULT 'bare',
+            default_branch TEXT NOT NULL DEFAULT 'main',
+            last_synced INTEGER NOT NULL,
+            created_at INTEGER NOT NULL DEFAULT (unixepoch() * 1000),
+            deleted_at INTEGER,
+            UNIQUE(provider, username, name)
+        );
+        CREATE TABLE worktrees (
+            path TEXT PRIMARY KEY,
+            repo_id TEXT NOT NULL REFERENCES repositories(id),
+            branch TEXT NOT NULL,
+            head TEXT,
+            status TEXT NOT NULL CHECK(status IN ('creating', 'ready', 'error', 'deleting')),
+            commit_message TEXT,
+            dirty INTEGER DEFAULT 0,
+            ahead INTEGER DEFAULT 0,
+            behind INTEGER DEFAULT 0,
+            last_status_check INTEGER,
+            created_at INTEGER NOT NULL DEFAULT (unixepoch() * 1000),
+            deleted_at INTEGER
+        );
+        CREATE TABLE worktree_config (
+            repo_id TEXT PRIMARY KEY REFERENCES repositories(id),
+            symlink_patterns TEXT,
+            copy_patterns TEXT,
+            upstream_remote TEXT DEFAULT 'origin'
+        );
+        INSERT INTO repositories (id, provider, username, name, clone_url, local_path, last_synced)
+        VALUES ('r1', 'github', 'user', 'repo', 'https://github.com/user/repo.git', '/code/user/repo', 1);
+        INSERT INTO worktrees (path, repo_id, branch, status)
+        VALUES ('/code/user/repo/feature', 'r1', 'feature/login', 'ready');
+        INSERT INTO worktree_config (repo_id, symlink_patterns, copy_patterns)
+        VALUES ('r1', '.env, .env.local', NULL);
+    ";
+
+    fn backup_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("grove-migrations-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn upgrades_baseline_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+        let dir = backup_dir("baseline");
+
+        let backup = migrate(&mut conn, &dir).unwrap();
+
+        assert_eq!(user_version(&conn).unwrap(), LATEST_VERSION);
+        let backup = backup.expect("a populated database is backed up");
+        assert!(backup.file_name().unwrap().to_string_lossy().starts_with("repos.v0."));
+        assert!(backup.is_file());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Rows survive, with the columns later migrations added
+        let (branch, note, error): (String, Option<String>, Option<String>) = conn
+            .query_row("SELECT branch, note, error FROM worktrees", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(branch, "feature/login");
+        assert_eq!((note, error), (None, None));
+
+        let patterns: String = conn
+            .query_row("SELECT symlink_patterns FROM worktree_config", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(patterns, r#"[".env",".env.local"]"#);
+
+        let indexed: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM search_index WHERE name = 'user/repo'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(indexed, 2);
+
+        // Nothing left to do the second time
+        assert_eq!(migrate(&mut conn, &dir).unwrap(), None);
+    }
+
+    #[test]
+    fn converts_install_paths_to_lists() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().take_while(|m| m.version < 10) {
+            (migration.up)(&conn).unwrap();
+        }
+        conn.pragma_update(None, "user_version", 9).unwrap();
+        conn.execute_batch(
+            "INSERT INTO repositories (id, provider, username, name, clone_url, local_path, last_synced)
+             VALUES ('r1', 'github', 'user', 'a', 'u1', '/code/user/a', 1),
+                    ('r2', 'github', 'user', 'b', 'u2', '/code/user/b', 1);
+             INSERT INTO worktree_config (repo_id, install_paths)
+             VALUES ('r1', 'apps/web, tools/cli,'), ('r2', NULL);",
+        )
+        .unwrap();
+
+        migrate(&mut conn, &backup_dir("install-paths")).unwrap();
+        std::fs::remove_dir_all(backup_dir("install-paths")).ok();
+
+        let paths: Vec<Option<String>> = conn
+            .prepare("SELECT install_paths FROM worktree_config ORDER BY repo_id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(paths, vec![Some(r#"["apps/web","tools/cli"]"#.to_string()), None]);
+    }
+
+    #[test]
+    fn creates_empty_database_without_backup() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let dir = backup_dir("empty");
+
+        assert_eq!(migrate(&mut conn, &dir).unwrap(), None);
+        assert_eq!(user_version(&conn).unwrap(), LATEST_VERSION);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn refuses_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", LATEST_VERSION + 1).unwrap();
+        let dir = backup_dir("newer");
+
+        let error = migrate(&mut conn, &dir).unwrap_err();
+
+        assert!(error.to_string().contains("upgrade grove"));
+        assert_eq!(user_version(&conn).unwrap(), LATEST_VERSION + 1);
+        assert!(!dir.exists());
+    }
+}
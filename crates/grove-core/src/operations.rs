@@ -0,0 +1,201 @@
+//! Operation history
+//!
+//! Every mutation (clone, create/delete/move worktree, refresh, ...) is
+//! recorded with who asked for it, when it ran and how it ended, so failures of
+//! background work can be inspected after the fact.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// Header clients set to identify themselves (`cli`, `tui`); plain HTTP otherwise
+pub const REQUESTER_HEADER: &str = "x-grove-client";
+
+/// What an operation did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Clone,
+    DeleteRepository,
+    RestoreRepository,
+    PurgeRepository,
+    CreateWorktree,
+    DeleteWorktree,
+    MoveWorktree,
+    LockWorktree,
+    UnlockWorktree,
+    Refresh,
+    Maintenance,
+    /// Database rebuilt from disk
+    Rebuild,
+    /// Shared files re-applied to a repository's worktrees
+    ShareFiles,
+    /// Failed clone or worktree creation resumed at the failed step
+    Retry,
+}
+
+impl OperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Clone => "clone",
+            Self::DeleteRepository => "delete_repository",
+            Self::RestoreRepository => "restore_repository",
+            Self::PurgeRepository => "purge_repository",
+            Self::CreateWorktree => "create_worktree",
+            Self::DeleteWorktree => "delete_worktree",
+            Self::MoveWorktree => "move_worktree",
+            Self::LockWorktree => "lock_worktree",
+            Self::UnlockWorktree => "unlock_worktree",
+            Self::Refresh => "refresh",
+            Self::Maintenance => "maintenance",
+            Self::Rebuild => "rebuild",
+            Self::ShareFiles => "share_files",
+            Self::Retry => "retry",
+        }
+    }
+}
+
+impl std::str::FromStr for OperationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clone" => Ok(Self::Clone),
+            "delete_repository" => Ok(Self::DeleteRepository),
+            "restore_repository" => Ok(Self::RestoreRepository),
+            "purge_repository" => Ok(Self::PurgeRepository),
+            "create_worktree" => Ok(Self::CreateWorktree),
+            "delete_worktree" => Ok(Self::DeleteWorktree),
+            "move_worktree" => Ok(Self::MoveWorktree),
+            "lock_worktree" => Ok(Self::LockWorktree),
+            "unlock_worktree" => Ok(Self::UnlockWorktree),
+            "refresh" => Ok(Self::Refresh),
+            "maintenance" => Ok(Self::Maintenance),
+            "rebuild" => Ok(Self::Rebuild),
+            "share_files" => Ok(Self::ShareFiles),
+            "retry" => Ok(Self::Retry),
+            _ => bail!("invalid operation kind: {}", s),
+        }
+    }
+}
+
+/// Who asked for an operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Requester {
+    /// HTTP API (web UI, scripts)
+    #[default]
+    Http,
+    Cli,
+    Tui,
+    Mcp,
+    /// The server's own scheduled jobs
+    Server,
+}
+
+impl Requester {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Cli => "cli",
+            Self::Tui => "tui",
+            Self::Mcp => "mcp",
+            Self::Server => "server",
+        }
+    }
+}
+
+impl std::str::FromStr for Requester {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(Self::Http),
+            "cli" => Ok(Self::Cli),
+            "tui" => Ok(Self::Tui),
+            "mcp" => Ok(Self::Mcp),
+            "server" => Ok(Self::Server),
+            _ => bail!("invalid requester: {}", s),
+        }
+    }
+}
+
+/// How an operation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationOutcome {
+    Running,
+    Succeeded,
+    Failed,
+    /// Still running when the server stopped
+    Interrupted,
+}
+
+impl OperationOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Interrupted => "interrupted",
+        }
+    }
+}
+
+impl std::str::FromStr for OperationOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(Self::Running),
+            "succeeded" => Ok(Self::Succeeded),
+            "failed" => Ok(Self::Failed),
+            "interrupted" => Ok(Self::Interrupted),
+            _ => bail!("invalid operation outcome: {}", s),
+        }
+    }
+}
+
+/// Operation record from database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: String,
+    pub kind: OperationKind,
+    /// Repository ID or worktree path
+    pub target: String,
+    pub requester: Requester,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub outcome: OperationOutcome,
+    pub error: Option<String>,
+}
+
+impl Operation {
+    /// A new running operation
+    pub fn start(kind: OperationKind, target: &str, requester: Requester) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            target: target.to_string(),
+            requester,
+            started_at: chrono::Utc::now().timestamp_millis(),
+            finished_at: None,
+            outcome: OperationOutcome::Running,
+            error: None,
+        }
+    }
+}
+
+/// Filter for listing operations (all fields optional)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationFilter {
+    #[serde(default)]
+    pub kind: Option<OperationKind>,
+    /// Exact repository ID or worktree path
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub outcome: Option<OperationOutcome>,
+    /// Most recent N (default 50)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
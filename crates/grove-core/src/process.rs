@@ -0,0 +1,137 @@
+//! Async child processes with streamed output, timeouts and cancellation
+//!
+//! Children run in their own process group so a timeout or cancel kills
+//! everything they spawned (e.g. the node processes behind `pnpm install`).
+
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::{mpsc, watch};
+
+/// Number of output lines kept for error messages
+const OUTPUT_TAIL: usize = 20;
+
+/// Why a process run did not succeed
+#[derive(Debug, Error)]
+pub enum RunError {
+    #[error("failed to start: {0}")]
+    Spawn(std::io::Error),
+    #[error("failed ({status}): {tail}")]
+    Failed { status: ExitStatus, tail: String },
+    #[error("timed out after {0}s")]
+    TimedOut(u64),
+    #[error("cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Run a command to completion, reporting each non-empty stdout/stderr line
+/// `cancel` flipping to `true` kills the process group, as does the timeout.
+pub async fn run_streaming<F>(
+    mut cmd: Command,
+    timeout: Duration,
+    cancel: Option<watch::Receiver<bool>>,
+    mut on_line: F,
+) -> Result<(), RunError>
+where
+    F: FnMut(&str),
+{
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(RunError::Spawn)?;
+
+    let pid = child.id();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let mut tail: Vec<String> = Vec::new();
+
+    let run = async {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let collect = async {
+            while let Some(line) = rx.recv().await {
+                on_line(&line);
+                tail.push(line);
+                if tail.len() > OUTPUT_TAIL {
+                    tail.remove(0);
+                }
+            }
+        };
+        tokio::join!(
+            forward_lines(stdout, tx.clone()),
+            forward_lines(stderr, tx),
+            collect
+        );
+        child.wait().await
+    };
+
+    let cancelled = async {
+        match cancel {
+            Some(mut rx) => {
+                // A dropped sender means nobody can cancel any more
+                if rx.wait_for(|c| *c).await.is_err() {
+                    std::future::pending::<()>().await;
+                }
+            }
+            None => std::future::pending().await,
+        }
+    };
+
+    let outcome = tokio::select! {
+        result = tokio::time::timeout(timeout, run) => match result {
+            Ok(status) => Ok(status?),
+            Err(_) => Err(RunError::TimedOut(timeout.as_secs())),
+        },
+        _ = cancelled => Err(RunError::Cancelled),
+    };
+
+    let status = match outcome {
+        Ok(status) => status,
+        Err(e) => {
+            kill_group(pid);
+            return Err(e);
+        }
+    };
+
+    if !status.success() {
+        return Err(RunError::Failed {
+            status,
+            tail: tail.join("\n"),
+        });
+    }
+
+    Ok(())
+}
+
+/// Kill a process group we spawned with `process_group(0)`
+fn kill_group(pid: Option<u32>) {
+    if let Some(pid) = pid {
+        // SAFETY: signalling a process group we spawned
+        unsafe {
+            libc::killpg(pid as i32, libc::SIGKILL);
+        }
+    }
+}
+
+/// Send each non-empty line of a reader to a channel
+async fn forward_lines<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    tx: mpsc::UnboundedSender<String>,
+) {
+    let Some(reader) = reader else {
+        return;
+    };
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if !line.is_empty() {
+            let _ = tx.send(line);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Structured progress of running operations
+//!
+//! Progress is keyed by what it is about: a repository id or a worktree path.
+//! Each entry names the operation it belongs to, the phase it is in, how far a
+//! countable phase got and whether it is a warning. Its `message` is the text
+//! clients showed before entries were structured; `FullState.progress` still
+//! carries that text for clients that don't read the entries yet.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What a progress key refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetKind {
+    Repository,
+    Worktree,
+}
+
+impl TargetKind {
+    /// Worktrees are keyed by their absolute path, repositories by their id
+    pub fn of(key: &str) -> Self {
+        if Path::new(key).is_absolute() {
+            Self::Worktree
+        } else {
+            Self::Repository
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Repository => "repository",
+            Self::Worktree => "worktree",
+        }
+    }
+}
+
+/// What a running operation is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressPhase {
+    /// Clearing a leftover directory before cloning
+    Preparing,
+    Cloning,
+    /// `.git` pointer, fetch refspec and default branch
+    Configuring,
+    Fetching,
+    /// Pulling the default branch into `.main`
+    Pulling,
+    CreatingWorktree,
+    /// Symlinking/copying files from `.main`
+    Sharing,
+    /// Reusing or installing dependencies (counted per install target)
+    Installing,
+    /// Running a hook; its output lines become the message
+    Hook,
+    /// Reading git status and toolchain versions
+    Status,
+    /// Moving a worktree or renaming its branch
+    Moving,
+    Deleting,
+    /// Repository maintenance (counted per task)
+    Maintenance,
+    /// Retry scheduled, before it resumes at the failed step
+    Retrying,
+}
+
+impl ProgressPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Preparing => "preparing",
+            Self::Cloning => "cloning",
+            Self::Configuring => "configuring",
+            Self::Fetching => "fetching",
+            Self::Pulling => "pulling",
+            Self::CreatingWorktree => "creating_worktree",
+            Self::Sharing => "sharing",
+            Self::Installing => "installing",
+            Self::Hook => "hook",
+            Self::Status => "status",
+            Self::Moving => "moving",
+            Self::Deleting => "deleting",
+            Self::Maintenance => "maintenance",
+            Self::Retrying => "retrying",
+        }
+    }
+}
+
+/// How a progress entry should be shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Info,
+    /// Something failed that didn't fail the operation (install, hook)
+    Warning,
+}
+
+/// Progress of one repository or worktree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Operation in the history this belongs to, once it is recorded
+    pub operation_id: Option<String>,
+    pub target_kind: TargetKind,
+    pub phase: ProgressPhase,
+    /// Text for display, e.g. "Installing (pnpm)..."
+    pub message: String,
+    /// Steps of the phase finished so far, for countable phases
+    pub current: Option<u32>,
+    /// Steps of the phase in total
+    pub total: Option<u32>,
+    pub severity: Severity,
+    /// When progress of this target started (ms since epoch)
+    pub started_at: i64,
+}
+
+impl Progress {
+    /// The entry as text, the way clients got progress before
+    pub fn text(&self) -> String {
+        match self.severity {
+            Severity::Info => self.message.clone(),
+            Severity::Warning => format!("Warning: {}", self.message),
+        }
+    }
+}
+
+/// A progress report; the state manager fills in the operation, target kind and start
+#[derive(Debug, Clone)]
+pub struct ProgressUpdate {
+    pub phase: ProgressPhase,
+    pub message: String,
+    pub current: Option<u32>,
+    pub total: Option<u32>,
+    pub severity: Severity,
+}
+
+impl ProgressUpdate {
+    pub fn new(phase: ProgressPhase, message: impl Into<String>) -> Self {
+        Self {
+            phase,
+            message: message.into(),
+            current: None,
+            total: None,
+            severity: Severity::Info,
+        }
+    }
+
+    /// A warning; `message` goes without the "Warning: " prefix
+    pub fn warning(phase: ProgressPhase, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(phase, message)
+        }
+    }
+
+    /// `current` of `total` steps finished
+    pub fn steps(mut self, current: usize, total: usize) -> Self {
+        self.current = Some(current as u32);
+        self.total = Some(total as u32);
+        self
+    }
+}
@@ -0,0 +1,664 @@
+//! Rebuild the database from disk
+//!
+//! Everything grove tracks can be recovered from `code_dir`: a repository is a
+//! `{username}/{name}` directory holding `.bare` and a `.git` pointer file, its
+//! identity comes from the `origin` URL and its worktrees from
+//! `git worktree list`. `check` compares that with the database without
+//! writing; `rebuild` makes the database match it in one transaction. Repositories and registered
+//! worktrees whose directory is gone are only reported (it may be on an
+//! unmounted disk), as are worktrees grove is still creating, deleting or
+//! retrying, and dot directories (`.grove-trash`, ...) are never scanned.
+
+use crate::db::Database;
+use crate::git::{GitOps, WorktreeEntry};
+use crate::types::{
+    NewRepository, NewWorktree, ParsedGitUrl, Repository, Worktree, WorktreeConfig,
+    WorktreeStatus,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A repository found under `code_dir`
+#[derive(Debug, Clone)]
+pub struct DiskRepository {
+    pub local_path: String,
+    pub origin: ParsedGitUrl,
+    pub default_branch: String,
+    /// `.main` first, then as git lists them
+    pub worktrees: Vec<WorktreeEntry>,
+    /// When `.bare` was last fetched (FETCH_HEAD), else when it was created
+    pub last_synced: i64,
+}
+
+/// A way the database differs from disk
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Difference {
+    /// On disk, not in the database
+    MissingRepository { local_path: String, clone_url: String },
+    /// In the database, directory gone (never removed by a rebuild)
+    RepositoryNotOnDisk { id: String, local_path: String },
+    CloneUrl { id: String, database: String, disk: String },
+    DefaultBranch { id: String, database: String, disk: String },
+    /// No worktree config (symlink/copy patterns, upstream remote)
+    MissingConfig { id: String },
+    /// Registered with git, not in the database
+    MissingWorktree { path: String, branch: String },
+    /// In the database, not a registered worktree with a directory
+    WorktreeNotOnDisk {
+        path: String,
+        /// Why a rebuild keeps the row (None: it is dropped)
+        kept: Option<String>,
+    },
+    WorktreeBranch { path: String, database: String, disk: String },
+    /// Found on disk but can't be recovered
+    Skipped { path: String, reason: String },
+}
+
+impl Difference {
+    /// Whether a rebuild changes the database for it
+    pub fn is_fixable(&self) -> bool {
+        !matches!(
+            self,
+            Self::RepositoryNotOnDisk { .. }
+                | Self::WorktreeNotOnDisk { kept: Some(_), .. }
+                | Self::Skipped { .. }
+        )
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRepository { local_path, clone_url } => {
+                write!(f, "+ repository {} ({})", local_path, clone_url)
+            }
+            Self::RepositoryNotOnDisk { id, local_path } => {
+                write!(f, "? repository {} ({}) is not on disk", id, local_path)
+            }
+            Self::CloneUrl { id, database, disk } => {
+                write!(f, "~ clone URL of {}: {} -> {}", id, database, disk)
+            }
+            Self::DefaultBranch { id, database, disk } => {
+                write!(f, "~ default branch of {}: {} -> {}", id, database, disk)
+            }
+            Self::MissingConfig { id } => write!(f, "+ worktree config of {}", id),
+            Self::MissingWorktree { path, branch } => {
+                write!(f, "+ worktree {} ({})", path, branch)
+            }
+            Self::WorktreeNotOnDisk { path, kept: None } => {
+                write!(f, "- worktree {} is not on disk", path)
+            }
+            Self::WorktreeNotOnDisk { path, kept: Some(reason) } => {
+                write!(f, "? worktree {} is not on disk ({})", path, reason)
+            }
+            Self::WorktreeBranch { path, database, disk } => {
+                write!(f, "~ branch of {}: {} -> {}", path, database, disk)
+            }
+            Self::Skipped { path, reason } => write!(f, "! skipped {}: {}", path, reason),
+        }
+    }
+}
+
+/// What a check or rebuild found
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildReport {
+    /// Repositories found on disk
+    pub repositories: usize,
+    /// Worktrees found on disk
+    pub worktrees: usize,
+    pub differences: Vec<Difference>,
+    /// Whether the fixable differences were written
+    pub applied: bool,
+}
+
+/// Compare the database with `code_dir` without writing (blocking)
+pub fn check(db: &Database, git: &GitOps, code_dir: &Path) -> Result<RebuildReport> {
+    run(db, git, code_dir, false)
+}
+
+/// Make the database match `code_dir` (blocking)
+/// Nothing is written unless every fix succeeds.
+pub fn rebuild(db: &Database, git: &GitOps, code_dir: &Path) -> Result<RebuildReport> {
+    db.transaction(|db| run(db, git, code_dir, true))
+}
+
+fn run(db: &Database, git: &GitOps, code_dir: &Path, apply: bool) -> Result<RebuildReport> {
+    let (found, mut differences) = scan(git, code_dir);
+    let mut tracked: HashMap<String, (Repository, Vec<Worktree>)> = db
+        .list_repositories_with_worktrees()?
+        .into_iter()
+        .map(|(repo, worktrees)| (repo.local_path.clone(), (repo, worktrees)))
+        .collect();
+
+    // Tracked worktrees whose directory is gone are reported by `compare`
+    let tracked_worktrees: HashSet<&str> = tracked
+        .values()
+        .flat_map(|(_, worktrees)| worktrees.iter().map(|w| w.path.as_str()))
+        .collect();
+    differences.retain(|d| {
+        !matches!(d, Difference::Skipped { path, .. } if tracked_worktrees.contains(path.as_str()))
+    });
+
+    let repositories = found.len();
+    let worktrees = found
+        .iter()
+        .flat_map(|r| &r.worktrees)
+        .filter(|w| !w.prunable)
+        .count();
+
+    // Reads don't see this run's inserts until it commits, so names taken
+    // by repositories added here are tracked separately
+    let mut added: HashMap<(String, String, String), String> = HashMap::new();
+
+    for disk in &found {
+        match tracked.remove(&disk.local_path) {
+            Some((repo, worktrees)) => {
+                differences.extend(compare(db, git, disk, &repo, &worktrees, apply)?);
+            }
+            None => {
+                if let Some(skipped) = conflict(db, disk, &added)? {
+                    differences.push(skipped);
+                    continue;
+                }
+                let origin = &disk.origin;
+                added.insert(
+                    (origin.provider.clone(), origin.username.clone(), origin.name.clone()),
+                    disk.local_path.clone(),
+                );
+                differences.push(Difference::MissingRepository {
+                    local_path: disk.local_path.clone(),
+                    clone_url: disk.origin.url.clone(),
+                });
+                let worktrees = disk.worktrees.iter().filter(|w| !w.prunable);
+                differences.extend(worktrees.map(|w| Difference::MissingWorktree {
+                    path: w.path.clone(),
+                    branch: branch_name(w),
+                }));
+                if apply {
+                    insert(db, git, disk)?;
+                }
+            }
+        }
+    }
+
+    // Whatever is left wasn't scanned; only report those whose directory is
+    // gone (skipped directories already have their own entry)
+    let mut missing: Vec<_> = tracked
+        .into_values()
+        .map(|(repo, _)| repo)
+        .filter(|repo| !Path::new(&repo.local_path).exists())
+        .collect();
+    missing.sort_by(|a, b| a.local_path.cmp(&b.local_path));
+    differences.extend(missing.into_iter().map(|repo| Difference::RepositoryNotOnDisk {
+        id: repo.id,
+        local_path: repo.local_path,
+    }));
+
+    Ok(RebuildReport {
+        repositories,
+        worktrees,
+        differences,
+        applied: apply,
+    })
+}
+
+/// Walk `code_dir/{username}/{name}` for the `.bare` + `.git` layout
+/// Returns the repositories found and the directories that had to be skipped.
+pub fn scan(git: &GitOps, code_dir: &Path) -> (Vec<DiskRepository>, Vec<Difference>) {
+    let mut found = Vec::new();
+    let mut skipped = Vec::new();
+
+    for owner in subdirectories(code_dir) {
+        for dir in subdirectories(&owner) {
+            let path = dir.to_string_lossy().to_string();
+            let bare = dir.join(".bare");
+            let pointer = dir.join(".git");
+            if !bare.is_dir() {
+                continue;
+            }
+            if !pointer.is_file() {
+                skipped.push(Difference::Skipped {
+                    path,
+                    reason: "has .bare but no .git pointer file".to_string(),
+                });
+                continue;
+            }
+
+            let Some(url) = git.remote_url(&dir, "origin") else {
+                skipped.push(Difference::Skipped {
+                    path,
+                    reason: "no origin remote".to_string(),
+                });
+                continue;
+            };
+            let Some(origin) = git.parse_url(&url) else {
+                skipped.push(Difference::Skipped {
+                    path,
+                    reason: format!("origin URL {} is not a supported git URL", url),
+                });
+                continue;
+            };
+
+            let mut worktrees = match git.list_worktrees(&dir) {
+                Ok(worktrees) => worktrees,
+                Err(e) => {
+                    skipped.push(Difference::Skipped {
+                        path,
+                        reason: format!("can't list worktrees: {}", e),
+                    });
+                    continue;
+                }
+            };
+            worktrees.sort_by_key(|w| !w.path.ends_with("/.main"));
+            skipped.extend(worktrees.iter().filter(|w| w.prunable).map(|w| {
+                Difference::Skipped {
+                    path: w.path.clone(),
+                    reason: "registered with git but the directory is gone (git worktree prune)"
+                        .to_string(),
+                }
+            }));
+
+            let default_branch = git
+                .detect_default_branch(&dir)
+                .unwrap_or_else(|_| "main".to_string());
+            // Never 0: that marks a clone in progress, which jobs skip
+            let last_synced = std::fs::metadata(bare.join("FETCH_HEAD"))
+                .or_else(|_| std::fs::metadata(&bare))
+                .and_then(|m| m.modified())
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).timestamp_millis())
+                .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis());
+
+            found.push(DiskRepository {
+                local_path: path,
+                origin,
+                default_branch,
+                worktrees,
+                last_synced,
+            });
+        }
+    }
+
+    (found, skipped)
+}
+
+/// Non-hidden subdirectories, sorted
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// A repository on disk whose name is taken by another (or a trashed) record
+/// `added` holds the names (provider, username, name) added earlier in the run.
+fn conflict(
+    db: &Database,
+    disk: &DiskRepository,
+    added: &HashMap<(String, String, String), String>,
+) -> Result<Option<Difference>> {
+    let origin = &disk.origin;
+    let key = (origin.provider.clone(), origin.username.clone(), origin.name.clone());
+    let other = match added.get(&key) {
+        Some(local_path) => Some(local_path.clone()),
+        None => db
+            .get_repository_by_name(&origin.provider, &origin.username, &origin.name)?
+            .map(|repo| repo.local_path),
+    };
+    if let Some(local_path) = other {
+        return Ok(Some(Difference::Skipped {
+            path: disk.local_path.clone(),
+            reason: format!(
+                "{}/{} is already tracked at {}",
+                origin.username, origin.name, local_path
+            ),
+        }));
+    }
+    if let Some(trashed) = db.find_trashed_repository(
+        &origin.provider,
+        &origin.username,
+        &origin.name,
+        &disk.local_path,
+    )? {
+        return Ok(Some(Difference::Skipped {
+            path: disk.local_path.clone(),
+            reason: format!(
+                "repository {} is in the trash; restore or purge it first",
+                trashed.id
+            ),
+        }));
+    }
+    Ok(None)
+}
+
+/// Differences of a tracked repository (fixed when `apply`)
+fn compare(
+    db: &Database,
+    git: &GitOps,
+    disk: &DiskRepository,
+    repo: &Repository,
+    worktrees: &[Worktree],
+    apply: bool,
+) -> Result<Vec<Difference>> {
+    let mut differences = Vec::new();
+
+    if repo.clone_url != disk.origin.url {
+        differences.push(Difference::CloneUrl {
+            id: repo.id.clone(),
+            database: repo.clone_url.clone(),
+            disk: disk.origin.url.clone(),
+        });
+        if apply {
+            db.update_repository_clone_url(&repo.id, &disk.origin.url)?;
+        }
+    }
+    if repo.default_branch != disk.default_branch {
+        differences.push(Difference::DefaultBranch {
+            id: repo.id.clone(),
+            database: repo.default_branch.clone(),
+            disk: disk.default_branch.clone(),
+        });
+        if apply {
+            db.update_repository_default_branch(&repo.id, &disk.default_branch)?;
+        }
+    }
+    if db.get_worktree_config(&repo.id)?.is_none() {
+        differences.push(Difference::MissingConfig { id: repo.id.clone() });
+        if apply {
+            db.upsert_worktree_config(&WorktreeConfig::defaults(&repo.id))?;
+        }
+    }
+
+    for worktree in worktrees {
+        let entry = disk.worktrees.iter().find(|w| w.path == worktree.path);
+        if entry.is_some_and(|w| !w.prunable) {
+            continue;
+        }
+        // Only ready rows git doesn't know are dropped: a create may not have
+        // registered yet, and a prunable directory may be on an unmounted disk
+        let kept = if worktree.status != WorktreeStatus::Ready {
+            Some(format!("worktree is {}", worktree.status.as_str()))
+        } else if entry.is_some() {
+            Some("registered with git, directory may be on an unmounted disk".to_string())
+        } else {
+            None
+        };
+        if apply && kept.is_none() {
+            db.delete_worktree(&worktree.path)?;
+        }
+        differences.push(Difference::WorktreeNotOnDisk {
+            path: worktree.path.clone(),
+            kept,
+        });
+    }
+
+    for entry in &disk.worktrees {
+        let branch = branch_name(entry);
+        match worktrees.iter().find(|w| w.path == entry.path) {
+            _ if entry.prunable => {}
+            Some(worktree) if worktree.branch != branch => {
+                differences.push(Difference::WorktreeBranch {
+                    path: entry.path.clone(),
+                    database: worktree.branch.clone(),
+                    disk: branch.clone(),
+                });
+                if apply {
+                    db.rename_worktree(&entry.path, &entry.path, &branch)?;
+                }
+            }
+            Some(_) => {}
+            None => {
+                differences.push(Difference::MissingWorktree {
+                    path: entry.path.clone(),
+                    branch,
+                });
+                if apply {
+                    insert_worktree(db, git, &repo.id, entry)?;
+                }
+            }
+        }
+    }
+
+    Ok(differences)
+}
+
+/// Add a repository found on disk with its worktrees and default config
+fn insert(db: &Database, git: &GitOps, disk: &DiskRepository) -> Result<()> {
+    let origin = &disk.origin;
+    let repo_id = db.insert_repository(&NewRepository {
+        provider: origin.provider.clone(),
+        username: origin.username.clone(),
+        name: origin.name.clone(),
+        clone_url: origin.url.clone(),
+        local_path: disk.local_path.clone(),
+        repo_type: "bare".to_string(),
+        default_branch: disk.default_branch.clone(),
+        last_synced: disk.last_synced,
+    })?;
+    db.upsert_worktree_config(&WorktreeConfig::defaults(&repo_id))?;
+
+    for entry in disk.worktrees.iter().filter(|w| !w.prunable) {
+        insert_worktree(db, git, &repo_id, entry)?;
+    }
+    Ok(())
+}
+
+/// Add a worktree registered with git, ready, with its current git status
+fn insert_worktree(db: &Database, git: &GitOps, repo_id: &str, entry: &WorktreeEntry) -> Result<()> {
+    db.insert_worktree(&NewWorktree {
+        path: entry.path.clone(),
+        repo_id: repo_id.to_string(),
+        branch: branch_name(entry),
+        status: WorktreeStatus::Ready,
+    })?;
+
+    match git.get_status(Path::new(&entry.path)) {
+        Ok(status) => {
+            db.update_worktree_status(
+                &entry.path,
+                WorktreeStatus::Ready,
+                status.head.as_deref(),
+                status.commit_message.as_deref(),
+            )?;
+            db.update_worktree_git_status(&entry.path, status.dirty, status.ahead, status.behind)?;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to read git status of {}: {}", entry.path, e);
+            db.update_worktree_status(&entry.path, WorktreeStatus::Ready, entry.head.as_deref(), None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checked-out branch, "HEAD" when detached (as `get_status` reports it)
+fn branch_name(entry: &WorktreeEntry) -> String {
+    entry.branch.clone().unwrap_or_else(|| "HEAD".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    const REPO: &str = "/code/user/repo";
+
+    fn database(dir: &Path) -> Database {
+        let config = Config {
+            code_dir: dir.join("code"),
+            data_dir: dir.join("data"),
+            db_path: dir.join("data").join("repos.db"),
+            ..Config::default()
+        };
+        Database::open(&config).unwrap()
+    }
+
+    fn add_repository(db: &Database, name: &str) -> Repository {
+        let id = db
+            .insert_repository(&NewRepository {
+                provider: "github".to_string(),
+                username: "user".to_string(),
+                name: name.to_string(),
+                clone_url: format!("git@github.com:user/{}.git", name),
+                local_path: format!("/code/user/{}", name),
+                repo_type: "bare".to_string(),
+                default_branch: "main".to_string(),
+                last_synced: 1,
+            })
+            .unwrap();
+        db.get_repository(&id).unwrap().unwrap()
+    }
+
+    fn add_worktree(db: &Database, repo: &Repository, name: &str, branch: &str, status: WorktreeStatus) {
+        db.insert_worktree(&NewWorktree {
+            path: format!("{}/{}", repo.local_path, name),
+            repo_id: repo.id.clone(),
+            branch: branch.to_string(),
+            status,
+        })
+        .unwrap();
+    }
+
+    fn entry(name: &str, branch: &str, prunable: bool) -> WorktreeEntry {
+        WorktreeEntry {
+            path: format!("{}/{}", REPO, name),
+            branch: Some(branch.to_string()),
+            head: None,
+            prunable,
+        }
+    }
+
+    /// A tracked repository that drifted from `disk()` in every way `compare` handles
+    fn tracked(db: &Database) -> Repository {
+        let repo = add_repository(db, "repo");
+        add_worktree(db, &repo, ".main", "main", WorktreeStatus::Ready);
+        add_worktree(db, &repo, "switched", "old", WorktreeStatus::Ready);
+        add_worktree(db, &repo, "gone", "gone", WorktreeStatus::Ready);
+        add_worktree(db, &repo, "creating", "creating", WorktreeStatus::Creating);
+        add_worktree(db, &repo, "unmounted", "unmounted", WorktreeStatus::Ready);
+        repo
+    }
+
+    fn disk(git: &GitOps) -> DiskRepository {
+        DiskRepository {
+            local_path: REPO.to_string(),
+            origin: git.parse_url("https://github.com/user/repo.git").unwrap(),
+            default_branch: "main".to_string(),
+            worktrees: vec![
+                entry(".main", "main", false),
+                entry("switched", "new", false),
+                entry("unmounted", "unmounted", true),
+                entry("added", "added", false),
+            ],
+            last_synced: 1,
+        }
+    }
+
+    fn path(name: &str) -> String {
+        format!("{}/{}", REPO, name)
+    }
+
+    fn paths(db: &Database, repo: &Repository) -> Vec<String> {
+        let mut paths: Vec<String> = db
+            .list_worktrees(&repo.id)
+            .unwrap()
+            .into_iter()
+            .map(|w| w.path)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn compare_reports_differences_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = database(dir.path());
+        let git = GitOps::new();
+        let repo = tracked(&db);
+        let worktrees = db.list_worktrees(&repo.id).unwrap();
+        let before = paths(&db, &repo);
+
+        let mut differences: Vec<String> = compare(&db, &git, &disk(&git), &repo, &worktrees, false)
+            .unwrap()
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        differences.sort();
+
+        assert_eq!(
+            differences,
+            vec![
+                format!("+ worktree {} (added)", path("added")),
+                format!("+ worktree config of {}", repo.id),
+                format!("- worktree {} is not on disk", path("gone")),
+                format!("? worktree {} is not on disk (worktree is creating)", path("creating")),
+                format!("? worktree {} is not on disk (registered with git, directory may be on an unmounted disk)", path("unmounted")),
+                format!("~ branch of {}: old -> new", path("switched")),
+                format!("~ clone URL of {}: git@github.com:user/repo.git -> https://github.com/user/repo.git", repo.id),
+            ]
+        );
+        assert_eq!(paths(&db, &repo), before);
+        assert!(db.get_worktree_config(&repo.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn compare_applies_only_fixable_differences() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = database(dir.path());
+        let git = GitOps::new();
+        let repo = tracked(&db);
+        let worktrees = db.list_worktrees(&repo.id).unwrap();
+
+        compare(&db, &git, &disk(&git), &repo, &worktrees, true).unwrap();
+
+        let mut expected: Vec<String> = [".main", "added", "creating", "switched", "unmounted"]
+            .iter()
+            .map(|name| path(name))
+            .collect();
+        expected.sort();
+        assert_eq!(paths(&db, &repo), expected);
+        assert_eq!(db.get_worktree(&path("switched")).unwrap().unwrap().branch, "new");
+        let added = db.get_worktree(&path("added")).unwrap().unwrap();
+        assert_eq!(added.status, WorktreeStatus::Ready);
+        let repo = db.get_repository(&repo.id).unwrap().unwrap();
+        assert_eq!(repo.clone_url, "https://github.com/user/repo.git");
+        assert!(db.get_worktree_config(&repo.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn failed_apply_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = database(dir.path());
+        let git = GitOps::new();
+        let repo = tracked(&db);
+        let worktrees = db.list_worktrees(&repo.id).unwrap();
+        // The worktree to add is already a row of another repository
+        let other = add_repository(&db, "other");
+        db.insert_worktree(&NewWorktree {
+            path: path("added"),
+            repo_id: other.id.clone(),
+            branch: "added".to_string(),
+            status: WorktreeStatus::Ready,
+        })
+        .unwrap();
+        let before = paths(&db, &repo);
+
+        let result = db.transaction(|db| compare(db, &git, &disk(&git), &repo, &worktrees, true));
+
+        assert!(result.is_err());
+        assert_eq!(paths(&db, &repo), before);
+        assert_eq!(db.get_worktree(&path("switched")).unwrap().unwrap().branch, "old");
+        let unchanged = db.get_repository(&repo.id).unwrap().unwrap();
+        assert_eq!(unchanged.clone_url, repo.clone_url);
+        assert!(db.get_worktree_config(&repo.id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! Dependency reuse between worktrees
+//!
+//! When a worktree's lockfiles match `.main`, its dependency directories
+//! (`node_modules`, `.venv`, `target`, ...) are seeded from `.main` instead of
+//! installed from scratch. Files are reflinked where the filesystem supports
+//! it (btrfs, XFS, APFS), else copied. They are never hardlinked: installers
+//! write into these directories in place, which would change `.main` too.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+/// Largest file checked for absolute `.main` paths
+const RELOCATE_MAX_BYTES: u64 = 1024 * 1024;
+
+/// How files were seeded, from cheapest to most expensive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMethod {
+    /// Copy-on-write clone sharing data blocks
+    Reflink,
+    /// Full copy
+    Copy,
+}
+
+impl LinkMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reflink => "reflink",
+            Self::Copy => "copy",
+        }
+    }
+}
+
+/// Whether every lockfile present in `src` exists in `dst` with identical
+/// contents (and at least one lockfile is present)
+pub fn lockfiles_match(src: &Path, dst: &Path, lockfiles: &[String]) -> bool {
+    let mut any = false;
+    for name in lockfiles {
+        let Ok(ours) = fs::read(src.join(name)) else {
+            continue;
+        };
+        match fs::read(dst.join(name)) {
+            Ok(theirs) if theirs == ours => any = true,
+            _ => return false,
+        }
+    }
+    any
+}
+
+/// Recreate the directory tree `src` at `dst` (which must not exist)
+/// Returns the most expensive method needed; a partial tree is removed on error.
+pub fn seed_dir(src: &Path, dst: &Path) -> io::Result<LinkMethod> {
+    let mut method = LinkMethod::Reflink;
+    if let Err(e) = seed_tree(src, dst, &mut method) {
+        let _ = fs::remove_dir_all(dst);
+        return Err(e);
+    }
+    Ok(method)
+}
+
+fn seed_tree(src: &Path, dst: &Path, method: &mut LinkMethod) -> io::Result<()> {
+    fs::create_dir(dst)?;
+    fs::set_permissions(dst, fs::metadata(src)?.permissions())?;
+
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(&from)?, &to)?;
+        } else if file_type.is_dir() {
+            seed_tree(&from, &to, method)?;
+        } else {
+            seed_file(&from, &to, method)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reflink or copy one file, downgrading `method` when the filesystem refuses
+fn seed_file(src: &Path, dst: &Path, method: &mut LinkMethod) -> io::Result<()> {
+    if *method == LinkMethod::Reflink {
+        match reflink(src, dst) {
+            Ok(()) => return Ok(()),
+            Err(_) => *method = LinkMethod::Copy,
+        }
+    }
+    fs::copy(src, dst)?;
+    preserve_mtime(src, dst)
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dst: &Path) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let from = File::open(src)?;
+    let to = File::options().write(true).create_new(true).open(dst)?;
+    // SAFETY: both descriptors are open for the duration of the call
+    let result = unsafe { libc::ioctl(to.as_raw_fd(), libc::FICLONE, from.as_raw_fd()) };
+    if result != 0 {
+        let e = io::Error::last_os_error();
+        drop(to);
+        let _ = fs::remove_file(dst);
+        return Err(e);
+    }
+    to.set_permissions(from.metadata()?.permissions())?;
+    drop(to);
+    preserve_mtime(src, dst)
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(src: &Path, dst: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let from = CString::new(src.as_os_str().as_bytes())?;
+    let to = CString::new(dst.as_os_str().as_bytes())?;
+    // SAFETY: both paths are valid NUL-terminated strings
+    if unsafe { libc::clonefile(from.as_ptr(), to.as_ptr(), 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_src: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Keep modification times so mtime-based freshness checks (cargo build
+/// scripts, make) treat seeded files as up to date
+fn preserve_mtime(src: &Path, dst: &Path) -> io::Result<()> {
+    let modified = fs::metadata(src)?.modified()?;
+    File::options()
+        .write(true)
+        .open(dst)?
+        .set_modified(modified)
+}
+
+/// Rewrite absolute `from` paths to `to` in a seeded directory
+/// Only small files where tools bake in absolute paths are checked: scripts in
+/// `bin/` (virtualenv shebangs, activate), `*.pth` (editable installs) and
+/// cargo build script `output`. Returns the number of files rewritten.
+pub fn relocate(dir: &Path, from: &Path, to: &Path) -> io::Result<usize> {
+    let from = from.as_os_str().as_encoded_bytes();
+    let to = to.as_os_str().as_encoded_bytes();
+    let mut rewritten = 0;
+    relocate_tree(dir, dir, from, to, &mut rewritten)?;
+    Ok(rewritten)
+}
+
+fn relocate_tree(
+    root: &Path,
+    dir: &Path,
+    from: &[u8],
+    to: &[u8],
+    rewritten: &mut usize,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            relocate_tree(root, &path, from, to, rewritten)?;
+            continue;
+        }
+        if !file_type.is_file() || !needs_relocation(root, &path) {
+            continue;
+        }
+
+        let metadata = entry.metadata()?;
+        if metadata.len() > RELOCATE_MAX_BYTES {
+            continue;
+        }
+        let contents = fs::read(&path)?;
+        if !contents.windows(from.len()).any(|w| w == from) {
+            continue;
+        }
+
+        let replaced = replace_bytes(&contents, from, to);
+        fs::remove_file(&path)?;
+        fs::write(&path, replaced)?;
+        fs::set_permissions(&path, metadata.permissions())?;
+        *rewritten += 1;
+    }
+
+    Ok(())
+}
+
+fn needs_relocation(root: &Path, path: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(root) else {
+        return false;
+    };
+    let parts: Vec<_> = rel.iter().collect();
+
+    // .venv/bin/*
+    let venv_script = parts.len() == 2 && parts[0] == "bin";
+    // site-packages/*.pth
+    let pth = rel.extension().is_some_and(|ext| ext == "pth");
+    // target/{profile}/build/{crate}/output
+    let build_output = parts.len() >= 3
+        && parts[parts.len() - 1] == "output"
+        && parts[parts.len() - 3] == "build";
+
+    venv_script || pth || build_output
+}
+
+fn replace_bytes(haystack: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(haystack.len());
+    let mut i = 0;
+    while i < haystack.len() {
+        if haystack[i..].starts_with(from) {
+            out.extend_from_slice(to);
+            i += from.len();
+        } else {
+            out.push(haystack[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::PackageManagerRegistry;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    /// `.main` and a new worktree next to it, as `seed_from` sees them
+    fn worktrees() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join(".main");
+        let worktree = dir.path().join("feature");
+        fs::create_dir(&main).unwrap();
+        fs::create_dir(&worktree).unwrap();
+        (dir, main, worktree)
+    }
+
+    #[test]
+    fn lockfiles_match_needs_every_present_lockfile_identical() {
+        let (_dir, main, worktree) = worktrees();
+        let lockfiles = vec!["package-lock.json".to_string(), "yarn.lock".to_string()];
+
+        // Nothing to compare
+        assert!(!lockfiles_match(&main, &worktree, &lockfiles));
+
+        write(&main.join("package-lock.json"), "v1");
+        assert!(!lockfiles_match(&main, &worktree, &lockfiles));
+
+        write(&worktree.join("package-lock.json"), "v1");
+        assert!(lockfiles_match(&main, &worktree, &lockfiles));
+
+        write(&worktree.join("package-lock.json"), "v2");
+        assert!(!lockfiles_match(&main, &worktree, &lockfiles));
+    }
+
+    #[test]
+    fn mismatched_lockfiles_refuse_to_seed() {
+        let (_dir, main, worktree) = worktrees();
+        let registry = PackageManagerRegistry::builtin();
+        let npm = registry.managers().iter().find(|pm| pm.name == "npm").unwrap();
+        write(&main.join("node_modules/dep/index.js"), "module.exports = 1");
+        write(&main.join("package-lock.json"), "v1");
+        write(&worktree.join("package-lock.json"), "v2");
+
+        assert!(npm.seed_from(&main, &worktree).unwrap().is_empty());
+        assert!(!worktree.join("node_modules").exists());
+
+        write(&worktree.join("package-lock.json"), "v1");
+        let seeded = npm.seed_from(&main, &worktree).unwrap();
+        assert_eq!(seeded.len(), 1);
+        assert_eq!(seeded[0].0, "node_modules");
+        assert_eq!(read(&worktree.join("node_modules/dep/index.js")), "module.exports = 1");
+    }
+
+    #[test]
+    fn relocate_rewrites_baked_in_paths() {
+        let (_dir, main, worktree) = worktrees();
+        let from = main.to_string_lossy().to_string();
+        let to = worktree.to_string_lossy().to_string();
+        let venv = worktree.join(".venv");
+        let target = worktree.join("target");
+
+        let activate = venv.join("bin/activate");
+        write(&activate, &format!("VIRTUAL_ENV=\"{}/.venv\"\n", from));
+        fs::set_permissions(&activate, fs::Permissions::from_mode(0o755)).unwrap();
+        let pth = venv.join("lib/python3.12/site-packages/app.pth");
+        write(&pth, &format!("{}/src\n", from));
+        let module = venv.join("lib/python3.12/site-packages/app/paths.py");
+        write(&module, &format!("ROOT = \"{}\"\n", from));
+        let output = target.join("debug/build/app-1a2b/output");
+        write(&output, &format!("cargo:rustc-link-search={0}/lib\ncargo:rerun-if-changed={0}/build.rs\n", from));
+        let stderr = target.join("debug/build/app-1a2b/stderr");
+        write(&stderr, &from);
+
+        assert_eq!(relocate(&venv, &main, &worktree).unwrap(), 2);
+        assert_eq!(relocate(&target, &main, &worktree).unwrap(), 1);
+
+        assert_eq!(read(&activate), format!("VIRTUAL_ENV=\"{}/.venv\"\n", to));
+        assert_eq!(fs::metadata(&activate).unwrap().permissions().mode() & 0o777, 0o755);
+        assert_eq!(read(&pth), format!("{}/src\n", to));
+        assert_eq!(
+            read(&output),
+            format!("cargo:rustc-link-search={0}/lib\ncargo:rerun-if-changed={0}/build.rs\n", to)
+        );
+        // Only the files tools bake paths into are touched
+        assert_eq!(read(&module), format!("ROOT = \"{}\"\n", from));
+        assert_eq!(read(&stderr), from);
+
+        // Nothing left to rewrite
+        assert_eq!(relocate(&venv, &main, &worktree).unwrap(), 0);
+    }
+}
@@ -28,16 +28,64 @@ CREATE TABLE IF NOT EXISTS worktrees (
     behind INTEGER DEFAULT 0,
     last_status_check INTEGER,
     created_at INTEGER NOT NULL DEFAULT (unixepoch() * 1000),
-    deleted_at INTEGER
+    deleted_at INTEGER,
+    package_managers TEXT,
+    install_status TEXT,
+    deps_fingerprint TEXT,
+    deps_state TEXT,
+    toolchains TEXT
 );
 
 CREATE TABLE IF NOT EXISTS worktree_config (
     repo_id TEXT PRIMARY KEY REFERENCES repositories(id),
     symlink_patterns TEXT,
     copy_patterns TEXT,
-    upstream_remote TEXT DEFAULT 'origin'
+    upstream_remote TEXT DEFAULT 'origin',
+    install_paths TEXT,
+    cargo_step TEXT
+);
+
+CREATE TABLE IF NOT EXISTS maintenance_runs (
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    task TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL,
+    size_before INTEGER NOT NULL,
+    size_after INTEGER NOT NULL,
+    error TEXT,
+    PRIMARY KEY (repo_id, task)
+);
+
+CREATE TABLE IF NOT EXISTS hooks (
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    event TEXT NOT NULL CHECK(event IN ('post-clone', 'post-create', 'pre-delete', 'post-delete')),
+    command TEXT NOT NULL,
+    timeout_secs INTEGER NOT NULL DEFAULT 300,
+    PRIMARY KEY (repo_id, event)
+);
+
+-- Install runs; output lives in data_dir/logs/{id}.log
+CREATE TABLE IF NOT EXISTS install_logs (
+    id TEXT PRIMARY KEY,
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    worktree_path TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER,
+    success INTEGER
+);
+
+-- Cached disk usage; path is the repo's .bare or a worktree path
+CREATE TABLE IF NOT EXISTS disk_usage (
+    path TEXT PRIMARY KEY,
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    total INTEGER NOT NULL,
+    tracked INTEGER NOT NULL,
+    dependencies INTEGER NOT NULL,
+    artifacts INTEGER NOT NULL,
+    computed_at INTEGER NOT NULL
 );
 
 CREATE INDEX IF NOT EXISTS idx_worktrees_repo_id ON worktrees(repo_id);
 CREATE INDEX IF NOT EXISTS idx_repositories_deleted ON repositories(deleted_at);
 CREATE INDEX IF NOT EXISTS idx_worktrees_deleted ON worktrees(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_install_logs_worktree ON install_logs(worktree_path);
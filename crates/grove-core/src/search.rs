@@ -0,0 +1,370 @@
+//! Search over repositories and worktrees
+//!
+//! `search_index` (an FTS5 trigram table maintained by triggers, see
+//! migration 5) narrows the rows down to those sharing a trigram with the
+//! query; they are then ranked here. Every query term has to match some field:
+//! as a word, a prefix, a substring, or fuzzily (a word within a typo or two,
+//! or most of its trigrams present), so "lgoin bug" still finds the
+//! `fix/login-bug` worktree. A typo can share no trigram with the word it
+//! misspells, so when the index turns up nothing every row is ranked.
+
+use serde::{Deserialize, Serialize};
+
+/// Results returned when no limit is given
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// Candidates fetched from the index before ranking
+pub const CANDIDATE_LIMIT: usize = 500;
+
+/// Share of a term's trigrams a field must contain to count as a fuzzy match
+const FUZZY_THRESHOLD: f64 = 0.5;
+
+/// Search parameters (`GET /api/search?q=...&limit=...`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+    /// Most N results (default 20)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// What a search result points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchHitKind {
+    Repository,
+    Worktree,
+}
+
+/// A ranked search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    pub repo_id: String,
+    /// `username/name` of the repository
+    pub name: String,
+    /// Worktree path (None for repositories)
+    pub path: Option<String>,
+    pub branch: Option<String>,
+    /// Higher is better
+    pub score: f64,
+    /// Fields the query matched ("name", "branch", "subject", "note", ...)
+    pub matched: Vec<String>,
+}
+
+/// An indexed row as read from `search_index`
+#[derive(Debug, Clone)]
+pub struct IndexedRow {
+    pub kind: SearchHitKind,
+    pub repo_id: String,
+    pub path: Option<String>,
+    /// (field, text) in index column order
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl IndexedRow {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+/// Indexed text columns, in table order
+pub const FIELDS: &[&str] = &[
+    "name", "url", "branch", "subject", "labels", "note", "issue", "owner",
+];
+
+/// How much a match in each field counts
+fn weight(kind: SearchHitKind, field: &str) -> f64 {
+    match (kind, field) {
+        // Worktrees carry their repository's name for multi-word queries
+        // ("grove login"); the repository itself should rank above them
+        (SearchHitKind::Worktree, "name") => 1.5,
+        (_, "name" | "branch") => 3.0,
+        (_, "labels" | "note" | "owner") => 2.0,
+        _ => 1.0,
+    }
+}
+
+/// Lowercase query terms
+pub fn terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// FTS5 MATCH expression selecting rows that share a trigram with any term
+/// None when no term is long enough for the trigram index (scan everything).
+pub fn match_expression(terms: &[String]) -> Option<String> {
+    let trigrams: Vec<String> = terms
+        .iter()
+        .flat_map(|term| trigrams(term))
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    if trigrams.is_empty() {
+        None
+    } else {
+        Some(trigrams.join(" OR "))
+    }
+}
+
+/// Rank candidate rows; rows where some term matches nothing are dropped
+pub fn rank(rows: Vec<IndexedRow>, terms: &[String], limit: usize) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = rows
+        .into_iter()
+        .filter_map(|row| score_row(&row, terms).map(|(score, matched)| (row, score, matched)))
+        .map(|(row, score, matched)| SearchHit {
+            kind: row.kind,
+            name: row.field("name").unwrap_or_default().to_string(),
+            branch: row
+                .field("branch")
+                .filter(|b| !b.is_empty())
+                .map(str::to_string),
+            repo_id: row.repo_id,
+            path: row.path,
+            score,
+            matched,
+        })
+        .collect();
+
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+/// Sum of each term's best weighted match, or None if a term matches nowhere
+fn score_row(row: &IndexedRow, terms: &[String]) -> Option<(f64, Vec<String>)> {
+    let mut total = 0.0;
+    let mut matched: Vec<String> = Vec::new();
+
+    for term in terms {
+        let (score, field) = row
+            .fields
+            .iter()
+            .map(|(field, text)| {
+                let score = term_score(term, &text.to_lowercase()) * weight(row.kind, field);
+                (score, *field)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+        if score <= 0.0 {
+            return None;
+        }
+        total += score;
+        if !matched.iter().any(|m| m == field) {
+            matched.push(field.to_string());
+        }
+    }
+
+    Some((total, matched))
+}
+
+/// How well one term matches one (lowercased) field, from 0 to 1
+/// Whole word 1.0, word prefix 0.9, substring 0.75; otherwise the better of a
+/// word within the allowed typos (0.6 for one, 0.5 for two) and the share of
+/// the term's trigrams found in the field (scaled to at most 0.6).
+fn term_score(term: &str, text: &str) -> f64 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    let words = || text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty());
+    if words().any(|w| w == term) {
+        return 1.0;
+    }
+    if words().any(|w| w.starts_with(term)) {
+        return 0.9;
+    }
+    if text.contains(term) {
+        return 0.75;
+    }
+
+    let typos = match term.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let typo_score = words()
+        .filter_map(|w| {
+            let d = edit_distance(term, w);
+            (d <= typos).then_some(0.7 - 0.1 * d as f64)
+        })
+        .fold(0.0, f64::max);
+
+    let wanted = trigrams(term);
+    let found = wanted.iter().filter(|t| text.contains(t.as_str())).count();
+    let share = if wanted.is_empty() {
+        0.0
+    } else {
+        found as f64 / wanted.len() as f64
+    };
+    let trigram_score = if share >= FUZZY_THRESHOLD { share * 0.6 } else { 0.0 };
+
+    typo_score.max(trigram_score)
+}
+
+/// Edits (insert, delete, substitute, swap neighbours) turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > 2 {
+        return usize::MAX;
+    }
+
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Overlapping three-character windows of a term
+fn trigrams(term: &str) -> Vec<String> {
+    let chars: Vec<char> = term.chars().collect();
+    chars.windows(3).map(|w| w.iter().collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository(name: &str, labels: &str) -> IndexedRow {
+        IndexedRow {
+            kind: SearchHitKind::Repository,
+            repo_id: name.to_string(),
+            path: None,
+            fields: vec![
+                ("name", name.to_string()),
+                ("url", format!("https://github.com/{}.git", name)),
+                ("labels", labels.to_string()),
+            ],
+        }
+    }
+
+    fn worktree(repo: &str, branch: &str, note: &str) -> IndexedRow {
+        IndexedRow {
+            kind: SearchHitKind::Worktree,
+            repo_id: repo.to_string(),
+            path: Some(format!("/code/{}/{}", repo, branch.replace('/', "-"))),
+            fields: vec![
+                ("name", repo.to_string()),
+                ("branch", branch.to_string()),
+                ("note", note.to_string()),
+            ],
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn term_score_prefers_closer_matches() {
+        assert_eq!(term_score("login", "fix/login-bug"), 1.0);
+        assert_eq!(term_score("log", "fix/login-bug"), 0.9);
+        assert_eq!(term_score("ogin", "fix/login-bug"), 0.75);
+        assert_close(term_score("lgoin", "fix/login-bug"), 0.6);
+        assert_close(term_score("authentcaton", "authentication"), 0.5);
+        assert_eq!(term_score("payment", "fix/login-bug"), 0.0);
+        assert_eq!(term_score("login", ""), 0.0);
+    }
+
+    #[test]
+    fn short_terms_need_exact_matches() {
+        assert_eq!(term_score("bgu", "fix/login-bug"), 0.0);
+        assert_close(term_score("lgin", "login"), 0.6);
+    }
+
+    #[test]
+    fn edit_distance_counts_swaps_as_one_edit() {
+        assert_eq!(edit_distance("login", "login"), 0);
+        assert_eq!(edit_distance("lgoin", "login"), 1);
+        assert_eq!(edit_distance("logn", "login"), 1);
+        assert_eq!(edit_distance("lgon", "login"), 2);
+        assert_eq!(edit_distance("lo", "login"), usize::MAX);
+    }
+
+    #[test]
+    fn typos_still_find_the_worktree() {
+        let rows = vec![
+            worktree("user/app", "fix/login-bug", ""),
+            worktree("user/app", "feature/payments", ""),
+            repository("user/app", ""),
+        ];
+
+        let hits = rank(rows, &terms("lgoin bug"), DEFAULT_LIMIT);
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].branch.as_deref(), Some("fix/login-bug"));
+        assert_eq!(hits[0].matched, vec!["branch"]);
+    }
+
+    #[test]
+    fn every_term_must_match_some_field() {
+        let rows = vec![worktree("user/app", "fix/login-bug", "waiting on review")];
+
+        assert_eq!(rank(rows.clone(), &terms("login review"), DEFAULT_LIMIT).len(), 1);
+        assert!(rank(rows, &terms("login payments"), DEFAULT_LIMIT).is_empty());
+    }
+
+    #[test]
+    fn repositories_rank_above_their_worktrees_by_name() {
+        let rows = vec![
+            worktree("user/grove", "main", ""),
+            repository("user/grove", ""),
+            repository("user/other", "grove"),
+        ];
+
+        let hits = rank(rows, &terms("grove"), DEFAULT_LIMIT);
+
+        let order: Vec<(SearchHitKind, &str)> =
+            hits.iter().map(|h| (h.kind, h.name.as_str())).collect();
+        assert_eq!(
+            order,
+            vec![
+                (SearchHitKind::Repository, "user/grove"),
+                (SearchHitKind::Repository, "user/other"),
+                (SearchHitKind::Worktree, "user/grove"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rank_truncates_to_limit() {
+        let rows = (0..5)
+            .map(|i| worktree("user/app", &format!("fix/login-{}", i), ""))
+            .collect();
+
+        assert_eq!(rank(rows, &terms("login"), 3).len(), 3);
+    }
+
+    #[test]
+    fn match_expression_quotes_trigrams() {
+        assert_eq!(
+            match_expression(&terms("Bug a\"b")),
+            Some("\"bug\" OR \"a\"\"b\"".to_string())
+        );
+        assert_eq!(match_expression(&terms("ab")), None);
+    }
+}
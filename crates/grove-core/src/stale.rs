@@ -0,0 +1,501 @@
+//! Stale worktree detection and cleanup policy
+//!
+//! A worktree's last activity is the latest of its HEAD commit, its newest
+//! file modification (`.git`, dependency and ignored directories excluded) and the last
+//! time it was opened in an editor. It is stale once idle longer than the
+//! threshold, or once its branch is merged (or squash-merged) into the default
+//! branch or its upstream was deleted (after a day of grace, so a branch merged
+//! an hour ago isn't pulled from under its owner). A fresh branch without an
+//! upstream or commits of its own never counts as merged. Stale worktrees that
+//! are unlocked, clean and fully pushed can be removed; `.main` and the default
+//! branch never are.
+
+use crate::db::Database;
+use crate::disk::{ignored_dirs, is_dependency_dir};
+use crate::git::GitOps;
+use crate::types::{Repository, Worktree, WorktreeStatus};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Idle time before a merged branch or one with a deleted upstream counts as stale
+pub const MERGED_GRACE: Duration = Duration::from_secs(24 * 3600);
+
+const DAY_MS: i64 = 86_400_000;
+
+/// What the scheduled cleanup does with removable stale worktrees
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CleanupMode {
+    /// No scheduled check
+    Off,
+    /// Log what would be removed
+    #[default]
+    DryRun,
+    Remove,
+}
+
+impl CleanupMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::DryRun => "dry-run",
+            Self::Remove => "remove",
+        }
+    }
+}
+
+impl std::str::FromStr for CleanupMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "dry-run" => Ok(Self::DryRun),
+            "remove" => Ok(Self::Remove),
+            _ => bail!("invalid cleanup mode: {} (expected off, dry-run or remove)", s),
+        }
+    }
+}
+
+/// Activity signals of a worktree (ms since epoch, None if unknown)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Activity {
+    pub last_commit_at: Option<i64>,
+    pub last_modified_at: Option<i64>,
+    pub last_opened_at: Option<i64>,
+}
+
+impl Activity {
+    /// Most recent of the signals
+    pub fn latest(&self) -> Option<i64> {
+        [self.last_commit_at, self.last_modified_at, self.last_opened_at]
+            .into_iter()
+            .flatten()
+            .max()
+    }
+}
+
+/// Why a worktree is considered stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleReason {
+    /// Idle longer than the threshold
+    Inactive,
+    /// Every commit of the branch is in the default branch
+    Merged,
+    /// The branch tracks an upstream that was deleted on the remote
+    UpstreamGone,
+}
+
+impl StaleReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Inactive => "inactive",
+            Self::Merged => "merged",
+            Self::UpstreamGone => "upstream gone",
+        }
+    }
+}
+
+/// A stale worktree and whether the cleanup may remove it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleWorktree {
+    pub path: String,
+    pub repo_id: String,
+    /// `username/name` of the repository
+    pub repo_name: String,
+    pub branch: String,
+    pub activity: Activity,
+    /// Whole days since the latest activity (None if there is none)
+    pub idle_days: Option<i64>,
+    pub reasons: Vec<StaleReason>,
+    /// Unlocked, clean and fully pushed
+    pub removable: bool,
+    /// Why it is kept when not removable ("locked", "uncommitted changes", ...)
+    pub kept: Option<String>,
+}
+
+/// Outcome of a cleanup run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupReport {
+    pub dry_run: bool,
+    /// Every stale worktree found
+    pub stale: Vec<StaleWorktree>,
+    /// Paths removed (on a dry run: that would be removed)
+    pub removed: Vec<String>,
+    pub failed: Vec<CleanupFailure>,
+}
+
+/// A removal that was refused or failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Stale worktrees of all repositories, longest idle first (blocking)
+pub fn find_stale(db: &Database, git: &GitOps, stale_after: Duration) -> Result<Vec<StaleWorktree>> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut stale = Vec::new();
+
+    for (repo, worktrees) in db.list_repositories_with_worktrees()? {
+        // Still cloning, or the clone failed
+        if repo.last_synced == 0 || repo.error.is_some() {
+            continue;
+        }
+        let remote = db
+            .get_worktree_config(&repo.id)?
+            .map(|c| c.upstream_remote)
+            .unwrap_or_else(|| "origin".to_string());
+        let locks = git.worktree_locks(Path::new(&repo.local_path));
+
+        for worktree in &worktrees {
+            if let Some(found) = assess(git, &repo, worktree, &remote, &locks, stale_after, now) {
+                stale.push(found);
+            }
+        }
+    }
+
+    // No activity at all sorts first
+    stale.sort_by_key(|s| std::cmp::Reverse(s.idle_days.unwrap_or(i64::MAX)));
+    Ok(stale)
+}
+
+/// Check one worktree; None if it isn't stale
+fn assess(
+    git: &GitOps,
+    repo: &Repository,
+    worktree: &Worktree,
+    remote: &str,
+    locks: &HashMap<String, Option<String>>,
+    stale_after: Duration,
+    now: i64,
+) -> Option<StaleWorktree> {
+    let path = Path::new(&worktree.path);
+    // Missing directories are for `grove rebuild`
+    if is_main(repo, worktree) || !path.is_dir() {
+        return None;
+    }
+
+    // Anything newer than the shorter threshold means it isn't stale either way
+    let recent = now - stale_after.min(MERGED_GRACE).as_millis() as i64;
+    let activity = Activity {
+        last_commit_at: git.last_commit_time(path),
+        last_modified_at: last_modified(path, recent),
+        last_opened_at: worktree.last_opened_at,
+    };
+    let idle = activity.latest().map_or(i64::MAX, |t| now - t);
+
+    let repo_path = Path::new(&repo.local_path);
+    let mut reasons = Vec::new();
+    if idle >= stale_after.as_millis() as i64 {
+        reasons.push(StaleReason::Inactive);
+    }
+    if idle >= MERGED_GRACE.as_millis() as i64 {
+        if git.is_merged(repo_path, &worktree.branch, &repo.default_branch, remote) {
+            reasons.push(StaleReason::Merged);
+        }
+        if git.upstream_gone(repo_path, &worktree.branch) {
+            reasons.push(StaleReason::UpstreamGone);
+        }
+    }
+    if reasons.is_empty() {
+        return None;
+    }
+
+    let kept = keep_reason(git, worktree, locks);
+    Some(StaleWorktree {
+        path: worktree.path.clone(),
+        repo_id: repo.id.clone(),
+        repo_name: format!("{}/{}", repo.username, repo.name),
+        branch: worktree.branch.clone(),
+        activity,
+        idle_days: (idle != i64::MAX).then_some(idle / DAY_MS),
+        reasons,
+        removable: kept.is_none(),
+        kept,
+    })
+}
+
+/// Why the cleanup must leave a worktree alone, None if it may remove it
+/// Called again right before removing, since the worktree may have changed.
+/// Commits on no remote always keep it, even when the branch looks merged: a
+/// squash match is a heuristic, not proof the local commits are safe.
+pub fn keep_reason(
+    git: &GitOps,
+    worktree: &Worktree,
+    locks: &HashMap<String, Option<String>>,
+) -> Option<String> {
+    if let Some(reason) = locks.get(&worktree.path) {
+        return Some(match reason {
+            Some(reason) => format!("locked: {}", reason),
+            None => "locked".to_string(),
+        });
+    }
+    if worktree.status != WorktreeStatus::Ready {
+        return Some(format!("status is {}", worktree.status.as_str()));
+    }
+
+    let path = Path::new(&worktree.path);
+    match git.uncommitted_changes(path) {
+        Ok(false) => {}
+        Ok(true) => return Some("uncommitted changes".to_string()),
+        Err(e) => return Some(format!("could not check for changes: {}", e)),
+    }
+    match git.unpushed_commits(path) {
+        Ok(0) => None,
+        Ok(1) => Some("1 unpushed commit".to_string()),
+        Ok(n) => Some(format!("{} unpushed commits", n)),
+        Err(e) => Some(format!("could not check for unpushed commits: {}", e)),
+    }
+}
+
+/// The repository's main checkout, never stale
+fn is_main(repo: &Repository, worktree: &Worktree) -> bool {
+    worktree.branch == repo.default_branch
+        || Path::new(&worktree.path).file_name() == Some(OsStr::new(".main"))
+}
+
+/// Newest modification time of any file or directory in a worktree (blocking)
+/// Skips `.git`, dependency directories and ignored build output, which installs
+/// and builds touch on their own. Stops at the first entry newer than `recent`,
+/// so an active worktree isn't walked in full.
+pub fn last_modified(worktree_path: &Path, recent: i64) -> Option<i64> {
+    /// Returns true once an entry newer than `recent` was found
+    fn walk(dir: &Path, ignored: &HashSet<PathBuf>, recent: i64, latest: &mut Option<i64>) -> bool {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return false;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name() == OsStr::new(".git")
+                || is_dependency_dir(&path)
+                || ignored.contains(&path)
+            {
+                continue;
+            }
+            // symlink_metadata: shared files from `.main` count as the link itself
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if let Some(ms) = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+            {
+                *latest = (*latest).max(Some(ms));
+                if ms > recent {
+                    return true;
+                }
+            }
+            if metadata.is_dir() && walk(&path, ignored, recent, latest) {
+                return true;
+            }
+        }
+        false
+    }
+
+    let ignored = ignored_dirs(worktree_path);
+    let mut latest = None;
+    walk(worktree_path, &ignored, recent, &mut latest);
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    /// `repo` with `main` pushed to origin and a `feature` worktree next to it
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        repo: PathBuf,
+        worktree: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let repo = dir.path().join("repo");
+            let worktree = dir.path().join("feature");
+            std::fs::create_dir(&repo).unwrap();
+            git(&repo, &["init", "-q", "-b", "main"]);
+            git(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+            git(&repo, &["update-ref", "refs/remotes/origin/main", "main"]);
+            git(&repo, &["worktree", "add", "-q", "-b", "feature", &worktree.to_string_lossy()]);
+            Self { _dir: dir, repo, worktree }
+        }
+
+        /// Commit a file in the feature worktree
+        fn commit(&self, file: &str) {
+            std::fs::write(self.worktree.join(file), file).unwrap();
+            git(&self.worktree, &["add", file]);
+            git(&self.worktree, &["commit", "-q", "-m", file]);
+        }
+
+        fn push(&self) {
+            git(&self.repo, &["update-ref", "refs/remotes/origin/feature", "feature"]);
+        }
+
+        fn squash_merge(&self) {
+            git(&self.repo, &["merge", "-q", "--squash", "feature"]);
+            git(&self.repo, &["commit", "-q", "-m", "feature (squashed)"]);
+            git(&self.repo, &["update-ref", "refs/remotes/origin/main", "main"]);
+        }
+
+        fn repository(&self) -> Repository {
+            serde_json::from_value(serde_json::json!({
+                "id": "r1",
+                "provider": "github",
+                "username": "user",
+                "name": "repo",
+                "clone_url": "https://github.com/user/repo.git",
+                "local_path": self.repo.to_string_lossy(),
+                "type": "bare",
+                "default_branch": "main",
+                "last_synced": 1,
+                "created_at": 1,
+            }))
+            .unwrap()
+        }
+
+        fn worktree(&self, status: &str) -> Worktree {
+            serde_json::from_value(serde_json::json!({
+                "path": self.worktree.to_string_lossy(),
+                "repo_id": "r1",
+                "branch": "feature",
+                "status": status,
+                "dirty": false,
+                "ahead": 0,
+                "behind": 0,
+                "created_at": 1,
+            }))
+            .unwrap()
+        }
+
+        fn keep_reason(&self, locks: &HashMap<String, Option<String>>) -> Option<String> {
+            keep_reason(&GitOps::new(), &self.worktree("ready"), locks)
+        }
+
+        /// Assess as if `days` passed since now
+        fn assess(&self, days: u32, stale_after: Duration) -> Option<StaleWorktree> {
+            let now = chrono::Utc::now().timestamp_millis() + (DAY * days).as_millis() as i64;
+            assess(
+                &GitOps::new(),
+                &self.repository(),
+                &self.worktree("ready"),
+                "origin",
+                &HashMap::new(),
+                stale_after,
+                now,
+            )
+        }
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn clean_pushed_worktree_is_removable() {
+        let fixture = Fixture::new();
+        fixture.commit("a.txt");
+        fixture.push();
+
+        assert_eq!(fixture.keep_reason(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn uncommitted_changes_keep_it() {
+        let fixture = Fixture::new();
+        std::fs::write(fixture.worktree.join("scratch.txt"), "wip").unwrap();
+
+        assert_eq!(
+            fixture.keep_reason(&HashMap::new()).as_deref(),
+            Some("uncommitted changes")
+        );
+    }
+
+    #[test]
+    fn unpushed_commits_keep_it_even_when_squash_merged() {
+        let fixture = Fixture::new();
+        fixture.commit("a.txt");
+        fixture.commit("b.txt");
+        assert_eq!(
+            fixture.keep_reason(&HashMap::new()).as_deref(),
+            Some("2 unpushed commits")
+        );
+
+        fixture.squash_merge();
+        let stale = fixture.assess(2, 30 * DAY).expect("merged a day ago counts");
+        assert_eq!(stale.reasons, vec![StaleReason::Merged]);
+        assert!(!stale.removable);
+        assert_eq!(stale.kept.as_deref(), Some("2 unpushed commits"));
+    }
+
+    #[test]
+    fn locks_and_status_keep_it() {
+        let fixture = Fixture::new();
+        let path = fixture.worktree.to_string_lossy().to_string();
+
+        let locked = HashMap::from([(path.clone(), Some("on external disk".to_string()))]);
+        assert_eq!(
+            fixture.keep_reason(&locked).as_deref(),
+            Some("locked: on external disk")
+        );
+        let locked = HashMap::from([(path, None)]);
+        assert_eq!(fixture.keep_reason(&locked).as_deref(), Some("locked"));
+
+        let creating = keep_reason(&GitOps::new(), &fixture.worktree("creating"), &HashMap::new());
+        assert_eq!(creating.as_deref(), Some("status is creating"));
+    }
+
+    #[test]
+    fn assess_needs_idle_time() {
+        let fixture = Fixture::new();
+        fixture.commit("a.txt");
+        fixture.push();
+
+        assert!(fixture.assess(0, 30 * DAY).is_none());
+        assert!(fixture.assess(10, 30 * DAY).is_none());
+
+        let stale = fixture.assess(31, 30 * DAY).expect("idle past the threshold");
+        assert_eq!(stale.reasons, vec![StaleReason::Inactive]);
+        assert_eq!(stale.idle_days, Some(31));
+        assert!(stale.removable);
+    }
+
+    #[test]
+    fn main_worktree_is_never_stale() {
+        let fixture = Fixture::new();
+        let mut main = fixture.worktree("ready");
+        main.path = fixture.repo.to_string_lossy().to_string();
+        main.branch = "main".to_string();
+
+        let now = chrono::Utc::now().timestamp_millis() + (DAY * 365).as_millis() as i64;
+        let found = assess(
+            &GitOps::new(),
+            &fixture.repository(),
+            &main,
+            "origin",
+            &HashMap::new(),
+            DAY,
+            now,
+        );
+        assert!(found.is_none());
+    }
+}
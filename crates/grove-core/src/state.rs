@@ -1,121 +1,764 @@
 //! Server-side state manager
 //!
-//! Maintains in-memory progress tracking and pushes full state to clients via broadcast.
+//! Maintains in-memory progress tracking and publishes state changes to clients.
+//! Changes are debounced: a burst of `set_progress` / `on_db_change` calls ends in
+//! one flush, which diffs the state against the last published one and broadcasts
+//! typed events with increasing sequence numbers. Recent events are kept so a
+//! client that reconnects can resume after the last event it saw.
+//! Progress is structured (see `progress.rs`) and also sent as plain text.
 //! See README.md for pseudocode and diagrams.
 
-use crate::types::RepoWithWorktrees;
-use crate::Database;
+use crate::progress::{Progress, ProgressUpdate, TargetKind};
+use crate::types::{RepoWithWorktrees, RepositoryFilter, Worktree};
+use crate::{Database, GitOps};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
 use tokio::sync::broadcast;
 
+/// Delay between the first change and the flush that publishes it
+pub const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Events kept for clients resuming with `Last-Event-ID`
+const HISTORY: usize = 1024;
+
+/// Events buffered per subscriber before it lags
+const EVENT_CAPACITY: usize = 256;
+
 /// Full state sent to clients
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FullState {
     pub repositories: Vec<RepoWithWorktrees>,
+    /// Progress as text: id/path -> message (for clients not reading `progress_entries` yet)
     pub progress: HashMap<String, String>,
+    /// Structured progress: id/path -> entry
+    #[serde(default)]
+    pub progress_entries: HashMap<String, Progress>,
+}
+
+impl FullState {
+    fn new(repositories: Vec<RepoWithWorktrees>, progress: HashMap<String, Progress>) -> Self {
+        Self {
+            repositories,
+            progress: progress.iter().map(|(key, p)| (key.clone(), p.text())).collect(),
+            progress_entries: progress,
+        }
+    }
+
+    /// Only the repositories matching `filter`, and the progress of those
+    pub fn filtered(mut self, filter: &RepositoryFilter) -> Self {
+        if !filter.is_empty() {
+            self.repositories.retain(|r| filter.matches(&r.repo));
+            let roots = repository_roots(&self.repositories);
+            self.progress.retain(|key, _| shows_progress(&roots, key));
+            self.progress_entries.retain(|key, _| shows_progress(&roots, key));
+        }
+        self
+    }
+}
+
+/// Local path of each repository by id
+fn repository_roots(repositories: &[RepoWithWorktrees]) -> HashMap<String, String> {
+    repositories
+        .iter()
+        .map(|r| (r.repo.id.clone(), r.repo.local_path.clone()))
+        .collect()
+}
+
+/// Whether a progress key (repository id or worktree path) belongs to one of `roots`
+/// Worktrees live inside their repository's directory, also while being created.
+fn shows_progress(roots: &HashMap<String, String>, key: &str) -> bool {
+    match TargetKind::of(key) {
+        TargetKind::Repository => roots.contains_key(key),
+        TargetKind::Worktree => roots.values().any(|root| Path::new(key).starts_with(root)),
+    }
+}
+
+/// A change of the published state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StateEvent {
+    /// A repository appeared (or started matching the client's filter)
+    RepoAdded { repository: RepoWithWorktrees },
+    /// Repository fields or its disk usage changed; replaces the whole entry
+    RepoUpdated { repository: RepoWithWorktrees },
+    /// A repository was deleted (or stopped matching the client's filter)
+    RepoRemoved { id: String },
+    /// A worktree was added or changed; `worktree` is None once it is removed
+    WorktreeChanged {
+        repo_id: String,
+        path: String,
+        worktree: Option<Worktree>,
+    },
+    /// Progress of a repository id or worktree path; None once done
+    /// `message` is the entry as text, as in `FullState.progress`.
+    ProgressChanged {
+        key: String,
+        message: Option<String>,
+        progress: Option<Progress>,
+    },
+}
+
+impl StateEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RepoAdded { .. } => "repo_added",
+            Self::RepoUpdated { .. } => "repo_updated",
+            Self::RepoRemoved { .. } => "repo_removed",
+            Self::WorktreeChanged { .. } => "worktree_changed",
+            Self::ProgressChanged { .. } => "progress_changed",
+        }
+    }
+}
+
+/// An event with its position in the stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedEvent {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub event: StateEvent,
+}
+
+/// Full state at a sequence number, sent when a client connects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Sequence number of the last event included
+    pub seq: u64,
+    #[serde(flatten)]
+    pub state: FullState,
+}
+
+/// Rewrites events for a client that only sees repositories matching a filter
+pub struct EventFilter {
+    filter: RepositoryFilter,
+    /// Repositories the client currently has: id -> local path
+    visible: HashMap<String, String>,
+}
+
+impl EventFilter {
+    /// Start from a snapshot already filtered with `filter`
+    pub fn new(filter: RepositoryFilter, snapshot: &FullState) -> Self {
+        let visible = repository_roots(&snapshot.repositories);
+        Self { filter, visible }
+    }
+
+    /// The event as this client should see it, None to skip it
+    /// A repository that starts matching is added, one that stops is removed.
+    /// Progress is only passed on for the repositories (and their worktrees) shown.
+    pub fn apply(&mut self, event: &StateEvent) -> Option<StateEvent> {
+        match event {
+            StateEvent::RepoAdded { repository } | StateEvent::RepoUpdated { repository } => {
+                let id = &repository.repo.id;
+                let was_visible = self.visible.contains_key(id);
+                if !self.filter.matches(&repository.repo) {
+                    self.visible.remove(id);
+                    return was_visible.then(|| StateEvent::RepoRemoved { id: id.clone() });
+                }
+                self.visible
+                    .insert(id.clone(), repository.repo.local_path.clone());
+                Some(if was_visible {
+                    StateEvent::RepoUpdated {
+                        repository: repository.clone(),
+                    }
+                } else {
+                    StateEvent::RepoAdded {
+                        repository: repository.clone(),
+                    }
+                })
+            }
+            StateEvent::RepoRemoved { id } => self.visible.remove(id).map(|_| event.clone()),
+            StateEvent::WorktreeChanged { repo_id, .. } => {
+                self.visible.contains_key(repo_id).then(|| event.clone())
+            }
+            StateEvent::ProgressChanged { key, .. } => {
+                shows_progress(&self.visible, key).then(|| event.clone())
+            }
+        }
+    }
+}
+
+/// Changes waiting for the debounced flush
+#[derive(Default)]
+struct PendingPush {
+    /// A flush is scheduled
+    scheduled: bool,
+    /// Repositories must be reloaded from the database
+    repositories: bool,
+}
+
+/// What clients have been sent so far
+struct Published {
+    /// Sequence number of the last event
+    seq: u64,
+    repositories: Vec<RepoWithWorktrees>,
+    progress: HashMap<String, Progress>,
+    /// Most recent events, oldest first
+    history: VecDeque<Arc<SequencedEvent>>,
+}
+
+impl Published {
+    fn full_state(&self) -> FullState {
+        FullState::new(self.repositories.clone(), self.progress.clone())
+    }
 }
 
 /// State manager with broadcast capability
 pub struct StateManager {
-    /// Broadcast sender for state updates
+    /// Handle on itself for the debounce task
+    this: Weak<StateManager>,
+    /// Broadcast sender for full state updates (one per flush)
     tx: broadcast::Sender<FullState>,
-    /// In-memory progress tracking: path -> message
-    progress: RwLock<HashMap<String, String>>,
+    /// Broadcast sender for incremental events
+    events: broadcast::Sender<Arc<SequencedEvent>>,
+    /// In-memory progress tracking: id/path -> entry
+    progress: RwLock<HashMap<String, Progress>>,
+    /// Running operations by target (id/path) -> operation id
+    operations: Mutex<HashMap<String, String>>,
     /// Database reference
     db: Arc<Database>,
-    /// Debounce state (pending push) - reserved for future use
-    #[allow(dead_code)]
-    pending_push: RwLock<bool>,
+    /// Debounce state
+    pending_push: Mutex<PendingPush>,
+    /// Held for a whole flush so flushes publish in the order they read
+    flushing: Mutex<()>,
+    /// Last published state, sequence number and event history
+    published: Mutex<Published>,
 }
 
 impl StateManager {
-    /// Create new state manager
+    /// Create new state manager and load the initial state (blocking)
     pub fn new(db: Arc<Database>) -> Arc<Self> {
         let (tx, _) = broadcast::channel(16);
-        Arc::new(Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        // Starting at the current time keeps ids from a previous server run out
+        // of this run's history, so resuming clients get a snapshot instead
+        let seq = chrono::Utc::now().timestamp_millis() as u64;
+
+        let manager = Arc::new_cyclic(|this| Self {
+            this: this.clone(),
             tx,
+            events,
             progress: RwLock::new(HashMap::new()),
+            operations: Mutex::new(HashMap::new()),
             db,
-            pending_push: RwLock::new(false),
-        })
+            pending_push: Mutex::new(PendingPush::default()),
+            flushing: Mutex::new(()),
+            published: Mutex::new(Published {
+                seq,
+                repositories: vec![],
+                progress: HashMap::new(),
+                history: VecDeque::new(),
+            }),
+        });
+
+        match manager.get_repos_with_worktrees() {
+            Ok(repositories) => manager.published.lock().unwrap().repositories = repositories,
+            Err(e) => tracing::error!("Failed to list repositories: {}", e),
+        }
+        manager
     }
 
-    /// Subscribe to state changes
+    /// Subscribe to full state updates
     pub fn subscribe(&self) -> broadcast::Receiver<FullState> {
         self.tx.subscribe()
     }
 
-    /// Set progress message for a path (repo.id or worktree.path)
-    /// Pass None to clear progress
-    pub fn set_progress(&self, path: &str, message: Option<&str>) {
+    /// Subscribe to incremental events
+    /// Subscribe before taking a snapshot or replaying history, then skip
+    /// events at or below the sequence number already sent.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Arc<SequencedEvent>> {
+        self.events.subscribe()
+    }
+
+    /// Last published state and its sequence number
+    pub fn snapshot(&self) -> Snapshot {
+        let published = self.published.lock().unwrap();
+        Snapshot {
+            seq: published.seq,
+            state: published.full_state(),
+        }
+    }
+
+    /// Events after `seq`, None if the history no longer reaches back that far
+    pub fn events_since(&self, seq: u64) -> Option<Vec<Arc<SequencedEvent>>> {
+        let published = self.published.lock().unwrap();
+        if seq > published.seq {
+            return None;
+        }
+        let oldest = published.history.front().map_or(published.seq, |e| e.seq - 1);
+        if seq < oldest {
+            return None;
+        }
+        Some(
+            published
+                .history
+                .iter()
+                .filter(|e| e.seq > seq)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Set progress for a repo.id or worktree.path
+    /// Keeps `started_at` while the same operation reports on the target.
+    pub fn set_progress(&self, key: &str, update: ProgressUpdate) {
+        let operation_id = self.operations.lock().unwrap().get(key).cloned();
         {
             let mut progress = self.progress.write().unwrap();
-            match message {
-                Some(msg) => {
-                    progress.insert(path.to_string(), msg.to_string());
-                }
-                None => {
-                    progress.remove(path);
-                }
-            }
+            let started_at = progress
+                .get(key)
+                .filter(|p| p.operation_id.is_none() || p.operation_id == operation_id)
+                .map_or_else(|| chrono::Utc::now().timestamp_millis(), |p| p.started_at);
+            progress.insert(
+                key.to_string(),
+                Progress {
+                    operation_id,
+                    target_kind: TargetKind::of(key),
+                    phase: update.phase,
+                    message: update.message,
+                    current: update.current,
+                    total: update.total,
+                    severity: update.severity,
+                    started_at,
+                },
+            );
         }
         self.schedule_push();
     }
 
-    /// Schedule a debounced state push
+    /// Clear progress of a repo.id or worktree.path
+    pub fn clear_progress(&self, key: &str) {
+        self.progress.write().unwrap().remove(key);
+        self.schedule_push();
+    }
+
+    /// Attribute progress of `target` (repo.id or worktree.path) to an operation
+    pub fn start_operation(&self, target: &str, operation_id: &str) {
+        self.operations
+            .lock()
+            .unwrap()
+            .insert(target.to_string(), operation_id.to_string());
+    }
+
+    /// The operation ended; a newer one on the same target is kept
+    pub fn finish_operation(&self, target: &str, operation_id: &str) {
+        let mut operations = self.operations.lock().unwrap();
+        if operations.get(target).is_some_and(|id| id == operation_id) {
+            operations.remove(target);
+        }
+    }
+
+    /// Schedule a debounced flush
+    /// Changes made before the flush starts are published together.
     fn schedule_push(&self) {
-        // For now, push immediately. Can add debouncing later with tokio::spawn
-        self.push_state();
+        {
+            let mut pending = self.pending_push.lock().unwrap();
+            if pending.scheduled {
+                return;
+            }
+            pending.scheduled = true;
+        }
+        let Some(this) = self.this.upgrade() else {
+            return;
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    tokio::time::sleep(DEBOUNCE).await;
+                    if let Err(e) = tokio::task::spawn_blocking(move || this.flush()).await {
+                        tracing::error!("Failed to publish state: {}", e);
+                    }
+                });
+            }
+            // Outside a runtime there is nothing to debounce with
+            Err(_) => this.flush(),
+        }
     }
 
-    /// Push current state to all subscribers
-    pub fn push_state(&self) {
-        let state = self.get_full_state();
-        // Ignore send errors (no receivers)
-        let _ = self.tx.send(state);
+    /// Publish what changed since the last flush (blocking)
+    /// Repositories are loaded before taking `published`, so snapshots and
+    /// resuming clients don't wait on the database.
+    fn flush(&self) {
+        let _flushing = self.flushing.lock().unwrap();
+        let reload = {
+            let mut pending = self.pending_push.lock().unwrap();
+            pending.scheduled = false;
+            std::mem::take(&mut pending.repositories)
+        };
+
+        let repositories = if reload {
+            self.get_repos_with_worktrees()
+                // Keep what clients have rather than removing every repository
+                .inspect_err(|e| tracing::error!("Failed to list repositories: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let progress = self.progress.read().unwrap().clone();
+
+        let mut published = self.published.lock().unwrap();
+        let mut events = Vec::new();
+        if let Some(repositories) = repositories {
+            diff_repositories(&published.repositories, &repositories, &mut events);
+            published.repositories = repositories;
+        }
+        diff_progress(&published.progress, &progress, &mut events);
+        published.progress = progress;
+
+        if events.is_empty() {
+            return;
+        }
+        for event in events {
+            published.seq += 1;
+            let event = Arc::new(SequencedEvent {
+                seq: published.seq,
+                event,
+            });
+            if published.history.len() == HISTORY {
+                published.history.pop_front();
+            }
+            published.history.push_back(Arc::clone(&event));
+            // Ignore send errors (no receivers)
+            let _ = self.events.send(event);
+        }
+        if self.tx.receiver_count() > 0 {
+            let _ = self.tx.send(published.full_state());
+        }
     }
 
-    /// Get current full state
+    /// Get current full state, read fresh from the database (blocking)
     pub fn get_full_state(&self) -> FullState {
-        let repositories = self.get_repos_with_worktrees();
+        let repositories = self.get_repos_with_worktrees().unwrap_or_else(|e| {
+            tracing::error!("Failed to list repositories: {}", e);
+            vec![]
+        });
         let progress = self.progress.read().unwrap().clone();
 
-        FullState {
-            repositories,
-            progress,
-        }
+        FullState::new(repositories, progress)
+    }
+
+    /// Get current full state without blocking an async runtime worker
+    pub async fn full_state(self: &Arc<Self>) -> FullState {
+        let this = Arc::clone(self);
+        tokio::task::spawn_blocking(move || this.get_full_state())
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to build state: {}", e);
+                FullState::new(vec![], self.progress.read().unwrap().clone())
+            })
     }
 
     /// Get all repositories with their worktrees
-    fn get_repos_with_worktrees(&self) -> Vec<RepoWithWorktrees> {
-        let repos = match self.db.list_repositories() {
-            Ok(r) => r,
-            Err(e) => {
-                tracing::error!("Failed to list repositories: {}", e);
-                return vec![];
-            }
-        };
+    fn get_repos_with_worktrees(&self) -> Result<Vec<RepoWithWorktrees>> {
+        let repos = self.db.list_repositories_with_worktrees()?;
+
+        let git = GitOps::new();
+        let mut usage = self.db.list_disk_usage().unwrap_or_else(|e| {
+            tracing::error!("Failed to load disk usage: {}", e);
+            HashMap::new()
+        });
 
-        repos
+        Ok(repos
             .into_iter()
-            .map(|repo| {
-                let worktrees = self
-                    .db
-                    .list_worktrees(&repo.id)
-                    .unwrap_or_else(|e| {
-                        tracing::error!("Failed to list worktrees for {}: {}", repo.name, e);
-                        vec![]
-                    });
-
-                RepoWithWorktrees { repo, worktrees }
+            .map(|(repo, mut worktrees)| {
+                // Lock state lives in git, not the database
+                let locks = git.worktree_locks(Path::new(&repo.local_path));
+                for wt in &mut worktrees {
+                    if let Some(reason) = locks.get(&wt.path) {
+                        wt.locked = true;
+                        wt.lock_reason = reason.clone();
+                    }
+                    wt.disk_usage = usage.remove(&wt.path);
+                }
+
+                let bare_path = Path::new(&repo.local_path).join(".bare");
+                let disk_usage = usage.remove(bare_path.to_string_lossy().as_ref());
+
+                RepoWithWorktrees {
+                    repo,
+                    worktrees,
+                    disk_usage,
+                }
             })
-            .collect()
+            .collect())
     }
 
     /// Notify that database changed (call after mutations)
     pub fn on_db_change(&self) {
-        self.push_state();
+        self.pending_push.lock().unwrap().repositories = true;
+        self.schedule_push();
+    }
+}
+
+/// Events turning `old` repositories into `new`
+/// A repository whose own fields changed is sent whole; otherwise only the
+/// worktrees that changed are.
+fn diff_repositories(old: &[RepoWithWorktrees], new: &[RepoWithWorktrees], events: &mut Vec<StateEvent>) {
+    let before: HashMap<&str, &RepoWithWorktrees> = old.iter().map(|r| (r.repo.id.as_str(), r)).collect();
+
+    for repository in new {
+        let Some(previous) = before.get(repository.repo.id.as_str()) else {
+            events.push(StateEvent::RepoAdded {
+                repository: repository.clone(),
+            });
+            continue;
+        };
+        if *previous == repository {
+            continue;
+        }
+        if previous.repo != repository.repo || previous.disk_usage != repository.disk_usage {
+            events.push(StateEvent::RepoUpdated {
+                repository: repository.clone(),
+            });
+            continue;
+        }
+
+        let worktrees: HashMap<&str, &Worktree> =
+            previous.worktrees.iter().map(|w| (w.path.as_str(), w)).collect();
+        for worktree in &repository.worktrees {
+            if worktrees.get(worktree.path.as_str()) != Some(&worktree) {
+                events.push(StateEvent::WorktreeChanged {
+                    repo_id: repository.repo.id.clone(),
+                    path: worktree.path.clone(),
+                    worktree: Some(worktree.clone()),
+                });
+            }
+        }
+        for worktree in &previous.worktrees {
+            if !repository.worktrees.iter().any(|w| w.path == worktree.path) {
+                events.push(StateEvent::WorktreeChanged {
+                    repo_id: repository.repo.id.clone(),
+                    path: worktree.path.clone(),
+                    worktree: None,
+                });
+            }
+        }
+    }
+
+    let ids: HashSet<&str> = new.iter().map(|r| r.repo.id.as_str()).collect();
+    for repository in old {
+        if !ids.contains(repository.repo.id.as_str()) {
+            events.push(StateEvent::RepoRemoved {
+                id: repository.repo.id.clone(),
+            });
+        }
+    }
+}
+
+/// Events turning `old` progress into `new`
+fn diff_progress(old: &HashMap<String, Progress>, new: &HashMap<String, Progress>, events: &mut Vec<StateEvent>) {
+    for (key, progress) in new {
+        if old.get(key) != Some(progress) {
+            events.push(StateEvent::ProgressChanged {
+                key: key.clone(),
+                message: Some(progress.text()),
+                progress: Some(progress.clone()),
+            });
+        }
+    }
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            events.push(StateEvent::ProgressChanged {
+                key: key.clone(),
+                message: None,
+                progress: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::{ProgressPhase, Severity};
+    use crate::types::WorktreeStatus;
+
+    fn repository(id: &str, tags: &[&str]) -> RepoWithWorktrees {
+        RepoWithWorktrees {
+            repo: serde_json::from_value(serde_json::json!({
+                "id": id,
+                "provider": "github",
+                "username": "user",
+                "name": id,
+                "clone_url": format!("https://github.com/user/{}.git", id),
+                "local_path": format!("/code/user/{}", id),
+                "type": "bare",
+                "default_branch": "main",
+                "last_synced": 1,
+                "created_at": 1,
+                "tags": tags,
+            }))
+            .unwrap(),
+            worktrees: vec![worktree(id, ".main")],
+            disk_usage: None,
+        }
+    }
+
+    fn worktree(repo_id: &str, dir: &str) -> Worktree {
+        serde_json::from_value(serde_json::json!({
+            "path": format!("/code/user/{}/{}", repo_id, dir),
+            "repo_id": repo_id,
+            "branch": dir.trim_start_matches('.'),
+            "status": "ready",
+            "dirty": false,
+            "ahead": 0,
+            "behind": 0,
+            "created_at": 1,
+        }))
+        .unwrap()
+    }
+
+    fn progress(message: &str) -> Progress {
+        Progress {
+            operation_id: None,
+            target_kind: TargetKind::Repository,
+            phase: ProgressPhase::Fetching,
+            message: message.to_string(),
+            current: None,
+            total: None,
+            severity: Severity::Info,
+            started_at: 1,
+        }
+    }
+
+    fn names(events: &[StateEvent]) -> Vec<&'static str> {
+        events.iter().map(StateEvent::name).collect()
+    }
+
+    #[test]
+    fn diff_sends_added_updated_and_removed_repositories() {
+        let old = vec![repository("a", &[]), repository("b", &[])];
+        let mut renamed = repository("a", &[]);
+        renamed.repo.default_branch = "develop".to_string();
+        let new = vec![renamed, repository("c", &[])];
+
+        let mut events = Vec::new();
+        diff_repositories(&old, &new, &mut events);
+
+        assert_eq!(names(&events), vec!["repo_updated", "repo_added", "repo_removed"]);
+        assert!(matches!(&events[2], StateEvent::RepoRemoved { id } if id == "b"));
+    }
+
+    #[test]
+    fn diff_sends_only_changed_worktrees() {
+        let old = vec![repository("a", &[])];
+        let mut new = old.clone();
+        new[0].worktrees[0].status = WorktreeStatus::Deleting;
+        new[0].worktrees.push(worktree("a", "feature"));
+
+        let mut events = Vec::new();
+        diff_repositories(&old, &new, &mut events);
+        assert_eq!(names(&events), vec!["worktree_changed", "worktree_changed"]);
+
+        let mut events = Vec::new();
+        diff_repositories(&new, &old, &mut events);
+        let removed: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                StateEvent::WorktreeChanged { path, worktree: None, .. } => Some(path.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(removed, vec!["/code/user/a/feature"]);
+
+        let mut events = Vec::new();
+        diff_repositories(&old, &old, &mut events);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn diff_progress_sends_changes_and_clears() {
+        let old = HashMap::from([
+            ("a".to_string(), progress("Fetching...")),
+            ("b".to_string(), progress("Fetching...")),
+        ]);
+        let new = HashMap::from([
+            ("a".to_string(), progress("Fetching...")),
+            ("b".to_string(), progress("Pulling...")),
+            ("c".to_string(), progress("Fetching...")),
+        ]);
+
+        let mut events = Vec::new();
+        diff_progress(&old, &new, &mut events);
+        let mut keys: Vec<_> = events
+            .iter()
+            .map(|e| match e {
+                StateEvent::ProgressChanged { key, message, .. } => (key.as_str(), message.clone()),
+                _ => unreachable!(),
+            })
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                ("b", Some("Pulling...".to_string())),
+                ("c", Some("Fetching...".to_string())),
+            ]
+        );
+
+        let mut events = Vec::new();
+        diff_progress(&new, &HashMap::new(), &mut events);
+        assert_eq!(events.len(), 3);
+        assert!(events
+            .iter()
+            .all(|e| matches!(e, StateEvent::ProgressChanged { message: None, progress: None, .. })));
+    }
+
+    #[test]
+    fn event_filter_adds_and_removes_repositories_as_they_match() {
+        let filter = RepositoryFilter {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        let snapshot = FullState::new(vec![repository("a", &["work"]), repository("b", &[])], HashMap::new())
+            .filtered(&filter);
+        assert_eq!(snapshot.repositories.len(), 1);
+        let mut events = EventFilter::new(filter, &snapshot);
+
+        // b starts matching: added, then updated
+        let tagged = repository("b", &["work"]);
+        let added = events.apply(&StateEvent::RepoUpdated { repository: tagged.clone() });
+        assert_eq!(added.map(|e| e.name()), Some("repo_added"));
+        let updated = events.apply(&StateEvent::RepoUpdated { repository: tagged });
+        assert_eq!(updated.map(|e| e.name()), Some("repo_updated"));
+
+        // a stops matching: removed once, then ignored
+        let untagged = repository("a", &[]);
+        let removed = events.apply(&StateEvent::RepoUpdated { repository: untagged.clone() });
+        assert!(matches!(removed, Some(StateEvent::RepoRemoved { id }) if id == "a"));
+        assert!(events.apply(&StateEvent::RepoUpdated { repository: untagged }).is_none());
+        assert!(events.apply(&StateEvent::RepoRemoved { id: "a".to_string() }).is_none());
+    }
+
+    #[test]
+    fn event_filter_passes_worktrees_and_progress_of_visible_repositories() {
+        let filter = RepositoryFilter {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        let snapshot = FullState::new(vec![repository("a", &["work"])], HashMap::new());
+        let mut events = EventFilter::new(filter, &snapshot);
+
+        let changed = |repo_id: &str| StateEvent::WorktreeChanged {
+            repo_id: repo_id.to_string(),
+            path: format!("/code/user/{}/x", repo_id),
+            worktree: None,
+        };
+        assert!(events.apply(&changed("a")).is_some());
+        assert!(events.apply(&changed("b")).is_none());
+
+        let progress_of = |key: &str| StateEvent::ProgressChanged {
+            key: key.to_string(),
+            message: None,
+            progress: None,
+        };
+        assert!(events.apply(&progress_of("a")).is_some());
+        assert!(events.apply(&progress_of("/code/user/a/new-worktree")).is_some());
+        assert!(events.apply(&progress_of("b")).is_none());
+        assert!(events.apply(&progress_of("/code/user/ab/x")).is_none());
     }
 }
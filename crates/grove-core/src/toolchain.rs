@@ -0,0 +1,271 @@
+//! Toolchain version requirements per worktree
+//!
+//! Reads `.nvmrc`, `.node-version`, `rust-toolchain(.toml)`, `.python-version`
+//! and `.tool-versions`, and compares them with the versions that actually run
+//! in the worktree (so version manager shims and rustup overrides apply).
+
+use crate::install::PackageManager;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// Tools whose versions are checked
+const TOOLS: &[&str] = &["node", "rust", "python"];
+
+/// A required toolchain version and how it compares with the installed one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolchainStatus {
+    /// "node", "rust" or "python"
+    pub tool: String,
+    /// Version or channel as written (e.g. "20", "1.75.0", "stable")
+    pub required: String,
+    /// File the requirement came from
+    pub source: String,
+    /// Version that runs in the worktree (None if the tool is missing)
+    pub installed: Option<String>,
+    /// None when the requirement can't be compared (e.g. "lts/*")
+    pub matches: Option<bool>,
+}
+
+impl ToolchainStatus {
+    pub fn is_mismatch(&self) -> bool {
+        self.matches == Some(false)
+    }
+}
+
+/// Version manager used to run installs with the required toolchains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionManager {
+    Mise,
+    Asdf,
+    Rustup,
+}
+
+/// Toolchain requirements declared in a directory (tool, required, source)
+/// Tool-specific files win over `.tool-versions`.
+pub fn requirements(path: &Path) -> Vec<(String, String, String)> {
+    let mut found: Vec<(String, String, String)> = Vec::new();
+    let mut add = |tool: &str, version: Option<String>, source: &str| {
+        if let Some(version) = version
+            && !found.iter().any(|(t, _, _)| t == tool)
+        {
+            found.push((tool.to_string(), version, source.to_string()));
+        }
+    };
+
+    add("node", first_line(path, ".nvmrc"), ".nvmrc");
+    add("node", first_line(path, ".node-version"), ".node-version");
+    add("rust", rust_toolchain_toml(path), "rust-toolchain.toml");
+    add("rust", first_line(path, "rust-toolchain"), "rust-toolchain");
+    add(
+        "python",
+        first_line(path, ".python-version"),
+        ".python-version",
+    );
+
+    if let Ok(contents) = std::fs::read_to_string(path.join(".tool-versions")) {
+        for line in contents.lines() {
+            let mut parts = line.split('#').next().unwrap_or("").split_whitespace();
+            let (Some(tool), Some(version)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let tool = if tool == "nodejs" { "node" } else { tool };
+            if TOOLS.contains(&tool) {
+                add(tool, Some(version.to_string()), ".tool-versions");
+            }
+        }
+    }
+
+    found
+}
+
+/// Compare the requirements in `path` with the toolchains that run there (blocking)
+pub fn check(path: &Path) -> Vec<ToolchainStatus> {
+    requirements(path)
+        .into_iter()
+        .map(|(tool, required, source)| {
+            let installed = installed_version(path, &tool);
+            let matches = installed
+                .as_deref()
+                .map_or(Some(false), |v| version_matches(&tool, &required, v));
+            ToolchainStatus {
+                tool,
+                required,
+                source,
+                installed,
+                matches,
+            }
+        })
+        .collect()
+}
+
+/// Pick a version manager for a project with requirements, if one is installed
+/// mise reads every file above; asdf needs `.tool-versions`; rustup only pins rust.
+pub fn version_manager(path: &Path, ecosystem: &str) -> Option<VersionManager> {
+    let requirements = requirements(path);
+    if requirements.is_empty() {
+        return None;
+    }
+    if on_path("mise") {
+        return Some(VersionManager::Mise);
+    }
+    if on_path("asdf") && path.join(".tool-versions").exists() {
+        return Some(VersionManager::Asdf);
+    }
+    if ecosystem == "rust" && on_path("rustup") && requirements.iter().any(|(t, _, _)| t == "rust")
+    {
+        return Some(VersionManager::Rustup);
+    }
+    None
+}
+
+/// The same install run through a version manager
+pub fn wrap(pm: &PackageManager, manager: VersionManager, path: &Path) -> PackageManager {
+    let mut args: Vec<String> = match manager {
+        VersionManager::Mise => vec!["exec".into(), "--".into()],
+        VersionManager::Asdf => vec!["exec".into()],
+        VersionManager::Rustup => {
+            let channel = requirements(path)
+                .into_iter()
+                .find(|(t, _, _)| t == "rust")
+                .map(|(_, v, _)| v)
+                .unwrap_or_else(|| "stable".to_string());
+            vec!["run".into(), channel]
+        }
+    };
+    args.push(pm.command.clone());
+    args.extend(pm.args.iter().cloned());
+
+    let command = match manager {
+        VersionManager::Mise => "mise",
+        VersionManager::Asdf => "asdf",
+        VersionManager::Rustup => "rustup",
+    };
+    PackageManager {
+        command: command.to_string(),
+        args,
+        ..pm.clone()
+    }
+}
+
+/// Version of a tool as run in `path`, without letting rustup install anything
+fn installed_version(path: &Path, tool: &str) -> Option<String> {
+    let (program, arg) = match tool {
+        "node" => ("node", "--version"),
+        "rust" => ("rustc", "--version"),
+        "python" => ("python3", "--version"),
+        _ => return None,
+    };
+    let output = Command::new(program)
+        .arg(arg)
+        .current_dir(path)
+        .env("RUSTUP_AUTO_INSTALL", "0")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // "v20.11.0", "rustc 1.75.0 (82e1608df 2023-12-21)", "Python 3.12.1"
+    let text = String::from_utf8_lossy(&output.stdout);
+    let text = if text.trim().is_empty() {
+        String::from_utf8_lossy(&output.stderr)
+    } else {
+        text
+    };
+    text.split_whitespace()
+        .map(|w| w.trim_start_matches('v'))
+        .find(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Whether an installed version satisfies a requirement
+/// Numeric requirements match by prefix ("20" ~ "20.11.0"); rust channels by
+/// name; anything else (aliases, ranges) can't be compared.
+fn version_matches(tool: &str, required: &str, installed: &str) -> Option<bool> {
+    let required = required.trim().trim_start_matches('v');
+
+    if tool == "rust" {
+        let channel = required.split('-').next().unwrap_or(required);
+        match channel {
+            "stable" => return Some(!installed.contains('-')),
+            "beta" | "nightly" => return Some(installed.contains(channel)),
+            _ => {}
+        }
+    }
+
+    // python "3.12.1" from pyenv may carry a variant suffix ("3.12.1/envs/x")
+    let required = required.split('/').next().unwrap_or(required);
+    let wanted: Vec<&str> = required.split('.').collect();
+    if wanted.is_empty() || !wanted.iter().all(|p| p.parse::<u64>().is_ok()) {
+        return None;
+    }
+    let have: Vec<&str> = installed
+        .split(['-', '+'])
+        .next()
+        .unwrap_or(installed)
+        .split('.')
+        .collect();
+    Some(wanted.len() <= have.len() && wanted.iter().zip(&have).all(|(w, h)| w == h))
+}
+
+fn first_line(path: &Path, file: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(path.join(file)).ok()?;
+    contents
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or("").trim())
+        .find(|l| !l.is_empty())
+        .and_then(|l| l.split_whitespace().next())
+        .map(str::to_string)
+}
+
+/// `channel` from `[toolchain]` in rust-toolchain.toml
+fn rust_toolchain_toml(path: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(path.join("rust-toolchain.toml")).ok()?;
+    contents.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "channel").then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_requirements_match_by_prefix() {
+        assert_eq!(version_matches("node", "20", "20.11.0"), Some(true));
+        assert_eq!(version_matches("node", "v20.11", "20.11.0"), Some(true));
+        assert_eq!(version_matches("node", "20.11.0", "20.11.0"), Some(true));
+        assert_eq!(version_matches("node", "18", "20.11.0"), Some(false));
+        assert_eq!(version_matches("node", "20.1", "20.11.0"), Some(false));
+        assert_eq!(version_matches("node", "20.11.0.1", "20.11.0"), Some(false));
+    }
+
+    #[test]
+    fn suffixes_are_ignored() {
+        assert_eq!(version_matches("python", "3.12.1/envs/app", "3.12.1"), Some(true));
+        assert_eq!(version_matches("rust", "1.75", "1.75.0-nightly"), Some(true));
+        assert_eq!(version_matches("ruby", "3.3", "3.3.0+0"), Some(true));
+    }
+
+    #[test]
+    fn rust_channels_match_by_name() {
+        assert_eq!(version_matches("rust", "stable", "1.75.0"), Some(true));
+        assert_eq!(version_matches("rust", "stable", "1.76.0-nightly"), Some(false));
+        assert_eq!(version_matches("rust", "nightly-2024-01-01", "1.76.0-nightly"), Some(true));
+        assert_eq!(version_matches("rust", "beta", "1.76.0-nightly"), Some(false));
+    }
+
+    #[test]
+    fn aliases_and_ranges_cannot_be_compared() {
+        assert_eq!(version_matches("node", "lts/iron", "20.11.0"), None);
+        assert_eq!(version_matches("node", ">=18", "20.11.0"), None);
+        assert_eq!(version_matches("python", "3.x", "3.12.1"), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! Repository trash
+//!
+//! Deleting a repository moves its directory (`.bare`, `.main` and every
+//! worktree) to `trash_dir/{repo_id}` and soft-deletes its rows, so it can be
+//! restored until the retention period runs out. Directories are renamed, not
+//! copied, so the trash lives on the same filesystem as the code directory.
+//! Worktree `.git` files hold absolute paths; they are valid again once the
+//! directory is back at its original location.
+
+use crate::types::Repository;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A repository in the trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedRepository {
+    #[serde(flatten)]
+    pub repository: Repository,
+    /// Where the directory is kept until restored or purged
+    pub trash_path: String,
+    /// When the background purge removes it (None if automatic purging is off)
+    pub purge_at: Option<i64>,
+}
+
+impl TrashedRepository {
+    pub fn new(trash_dir: &Path, repository: Repository, retention: Option<Duration>) -> Self {
+        let trash_path = trash_path(trash_dir, &repository.id)
+            .to_string_lossy()
+            .to_string();
+        let purge_at = repository
+            .deleted_at
+            .zip(retention)
+            .map(|(deleted_at, retention)| deleted_at + retention.as_millis() as i64);
+        Self {
+            repository,
+            trash_path,
+            purge_at,
+        }
+    }
+
+    /// Whether the retention period has run out
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.purge_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// Trash location of a repository's directory
+pub fn trash_path(trash_dir: &Path, repo_id: &str) -> PathBuf {
+    trash_dir.join(repo_id)
+}
+
+/// Move a repository directory into the trash (nothing to move if it's gone)
+pub fn move_to_trash(local_path: &Path, trash_path: &Path) -> Result<()> {
+    if !local_path.exists() {
+        return Ok(());
+    }
+    if trash_path.exists() {
+        bail!("{} is already in the trash", trash_path.display());
+    }
+    if let Some(parent) = trash_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(local_path, trash_path).with_context(|| {
+        format!(
+            "Failed to move {} to the trash (GROVE_TRASH_DIR must be on the same filesystem)",
+            local_path.display()
+        )
+    })
+}
+
+/// Move a repository directory back to where it was
+pub fn restore(trash_path: &Path, local_path: &Path) -> Result<()> {
+    if !trash_path.exists() {
+        bail!("Trashed directory {} is missing", trash_path.display());
+    }
+    if local_path.exists() {
+        bail!("{} already exists", local_path.display());
+    }
+    if let Some(parent) = local_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(trash_path, local_path)
+        .with_context(|| format!("Failed to restore {}", local_path.display()))
+}
+
+/// Remove a repository directory from the trash for good
+pub fn purge(trash_path: &Path) -> Result<()> {
+    if trash_path.exists() {
+        std::fs::remove_dir_all(trash_path)
+            .with_context(|| format!("Failed to remove {}", trash_path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_restore_and_purge_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().join("code/user/repo");
+        let trashed = trash_path(&dir.path().join("code/.grove-trash"), "r1");
+        std::fs::create_dir_all(local_path.join(".main")).unwrap();
+        std::fs::write(local_path.join(".main/README.md"), "hello").unwrap();
+
+        move_to_trash(&local_path, &trashed).unwrap();
+        assert!(!local_path.exists());
+        assert_eq!(std::fs::read_to_string(trashed.join(".main/README.md")).unwrap(), "hello");
+
+        restore(&trashed, &local_path).unwrap();
+        assert!(!trashed.exists());
+        assert_eq!(std::fs::read_to_string(local_path.join(".main/README.md")).unwrap(), "hello");
+
+        move_to_trash(&local_path, &trashed).unwrap();
+        purge(&trashed).unwrap();
+        assert!(!trashed.exists());
+        assert!(!local_path.exists());
+        // Purging twice, or trashing what is already gone, is a no-op
+        purge(&trashed).unwrap();
+        move_to_trash(&local_path, &trashed).unwrap();
+    }
+
+    #[test]
+    fn restore_refuses_to_overwrite_the_original_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().join("code/user/repo");
+        let trashed = trash_path(&dir.path().join("trash"), "r1");
+        std::fs::create_dir_all(&local_path).unwrap();
+        std::fs::write(local_path.join("old"), "").unwrap();
+        move_to_trash(&local_path, &trashed).unwrap();
+
+        // Cloned again while the old one was in the trash
+        std::fs::create_dir_all(&local_path).unwrap();
+        std::fs::write(local_path.join("new"), "").unwrap();
+
+        let error = restore(&trashed, &local_path).unwrap_err();
+        assert!(error.to_string().contains("already exists"), "{}", error);
+        assert!(local_path.join("new").exists());
+        assert!(trashed.join("old").exists());
+
+        // Nor is a second repository trashed over the first
+        let error = move_to_trash(&local_path, &trashed).unwrap_err();
+        assert!(error.to_string().contains("already in the trash"), "{}", error);
+        assert!(local_path.join("new").exists());
+        assert!(trashed.join("old").exists());
+    }
+}
@@ -1,9 +1,13 @@
 //! Shared types for grove
 
+use crate::disk::DiskUsage;
+use crate::failure::SetupError;
+use crate::install::CargoStep;
+use crate::toolchain::ToolchainStatus;
 use serde::{Deserialize, Serialize};
 
 /// Repository record from database
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Repository {
     pub id: String,
     pub provider: String,
@@ -17,6 +21,18 @@ pub struct Repository {
     pub last_synced: i64,
     pub created_at: i64,
     pub deleted_at: Option<i64>,
+    /// User-defined tags, sorted
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// User-defined group (one per repository)
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Pinned to the top of lists
+    #[serde(default)]
+    pub favorite: bool,
+    /// Why the clone failed (None while cloning and once cloned)
+    #[serde(default)]
+    pub error: Option<SetupError>,
 }
 
 /// New repository for insertion
@@ -33,7 +49,7 @@ pub struct NewRepository {
 }
 
 /// Worktree record from database
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Worktree {
     pub path: String,
     pub repo_id: String,
@@ -47,6 +63,44 @@ pub struct Worktree {
     pub last_status_check: Option<i64>,
     pub created_at: i64,
     pub deleted_at: Option<i64>,
+    /// Package managers detected at install time (e.g. "pnpm", "cargo")
+    #[serde(default)]
+    pub package_managers: Vec<String>,
+    /// Outcome of the last install (None if never installed)
+    #[serde(default)]
+    pub install_status: Option<InstallStatus>,
+    /// Hash of lockfiles and manifests at the last successful install
+    #[serde(default)]
+    pub deps_fingerprint: Option<String>,
+    /// Whether lockfiles/manifests changed since then (None if never installed)
+    #[serde(default)]
+    pub deps_state: Option<DepsState>,
+    /// Required Node/Rust/Python versions and whether the installed ones match
+    #[serde(default)]
+    pub toolchains: Vec<ToolchainStatus>,
+    /// Locked via `git worktree lock` (read from git, not stored)
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub lock_reason: Option<String>,
+    /// Last computed disk usage (filled from the disk_usage cache)
+    #[serde(default)]
+    pub disk_usage: Option<DiskUsage>,
+    /// Why the worktree exists (free-form)
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Linked issue or ticket
+    #[serde(default)]
+    pub issue_url: Option<String>,
+    /// Who is working in it
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Last time it was opened in an editor (ms since epoch)
+    #[serde(default)]
+    pub last_opened_at: Option<i64>,
+    /// Why creating it failed (set with status `error`)
+    #[serde(default)]
+    pub error: Option<SetupError>,
 }
 
 /// New worktree for insertion
@@ -93,13 +147,108 @@ impl std::str::FromStr for WorktreeStatus {
     }
 }
 
+/// Installed dependencies compared with the current lockfiles and manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepsState {
+    UpToDate,
+    Stale,
+}
+
+impl DepsState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UpToDate => "up_to_date",
+            Self::Stale => "stale",
+        }
+    }
+}
+
+impl std::str::FromStr for DepsState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up_to_date" => Ok(Self::UpToDate),
+            "stale" => Ok(Self::Stale),
+            _ => anyhow::bail!("invalid deps state: {}", s),
+        }
+    }
+}
+
+/// Outcome of the most recent dependency install in a worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+impl InstallStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::TimedOut => "timed_out",
+        }
+    }
+}
+
+impl std::str::FromStr for InstallStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(Self::Running),
+            "succeeded" => Ok(Self::Succeeded),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            "timed_out" => Ok(Self::TimedOut),
+            _ => anyhow::bail!("invalid install status: {}", s),
+        }
+    }
+}
+
 /// Worktree configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorktreeConfig {
     pub repo_id: String,
-    pub symlink_patterns: Option<String>,
-    pub copy_patterns: Option<String>,
+    /// Files symlinked from `.main` into new worktrees (globs)
+    #[serde(default)]
+    pub symlink_patterns: Vec<String>,
+    /// Files copied from `.main` into new worktrees (globs)
+    #[serde(default)]
+    pub copy_patterns: Vec<String>,
     pub upstream_remote: String,
+    /// Project directories to install (empty = discover)
+    #[serde(default)]
+    pub install_paths: Vec<String>,
+    /// Overrides `GROVE_CARGO_STEP`
+    #[serde(default)]
+    pub cargo_step: Option<CargoStep>,
+}
+
+impl WorktreeConfig {
+    /// Config saved for a newly cloned (or rebuilt) repository
+    pub fn defaults(repo_id: &str) -> Self {
+        Self {
+            repo_id: repo_id.to_string(),
+            symlink_patterns: vec![
+                ".env".to_string(),
+                ".env.*".to_string(),
+                ".claude/**".to_string(),
+            ],
+            copy_patterns: Vec::new(),
+            upstream_remote: "origin".to_string(),
+            install_paths: Vec::new(),
+            cargo_step: None,
+        }
+    }
 }
 
 /// Git status for a worktree
@@ -113,12 +262,245 @@ pub struct GitStatus {
     pub commit_message: Option<String>,
 }
 
+/// Changes to a repository's tags, group and favorite flag (unset fields are kept)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LabelUpdate {
+    /// Replace all tags
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+    /// Move to a group ("" removes the repository from its group)
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+}
+
+impl LabelUpdate {
+    /// Trim tag and group names and reject unusable ones
+    pub fn normalize(mut self) -> anyhow::Result<Self> {
+        if let Some(tags) = self.tags.take() {
+            self.tags = Some(
+                tags.iter()
+                    .map(|t| normalize_label(t))
+                    .collect::<anyhow::Result<_>>()?,
+            );
+        }
+        self.add_tags = self
+            .add_tags
+            .iter()
+            .map(|t| normalize_label(t))
+            .collect::<anyhow::Result<_>>()?;
+        self.remove_tags = self.remove_tags.iter().map(|t| t.trim().to_string()).collect();
+        if let Some(group) = self.group.take() {
+            let group = group.trim();
+            self.group = Some(if group.is_empty() {
+                String::new()
+            } else {
+                normalize_label(group)?
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_none()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+            && self.group.is_none()
+            && self.favorite.is_none()
+    }
+}
+
+/// A tag or group name: trimmed, at most 64 characters, no commas or control characters
+pub fn normalize_label(label: &str) -> anyhow::Result<String> {
+    let label = label.trim();
+    if label.is_empty() {
+        anyhow::bail!("tag or group name is empty");
+    }
+    if label.chars().count() > 64 {
+        anyhow::bail!("tag or group name is longer than 64 characters: {}", label);
+    }
+    if label.contains(',') || label.chars().any(char::is_control) {
+        anyhow::bail!("tag or group name contains a comma or control character: {}", label);
+    }
+    Ok(label.to_string())
+}
+
+/// Changes to a worktree's note, issue link and owner
+/// Unset fields are kept; "" clears a field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorktreeNotes {
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub issue_url: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+impl WorktreeNotes {
+    /// Trim the fields and reject unusable values
+    pub fn normalize(mut self) -> anyhow::Result<Self> {
+        if let Some(note) = self.note.take() {
+            let note = note.trim();
+            if note.chars().count() > 4000 {
+                anyhow::bail!("note is longer than 4000 characters");
+            }
+            self.note = Some(note.to_string());
+        }
+        if let Some(url) = self.issue_url.take() {
+            let url = url.trim();
+            if !url.is_empty()
+                && (!(url.starts_with("https://") || url.starts_with("http://"))
+                    || url.chars().any(|c| c.is_whitespace() || c.is_control()))
+            {
+                anyhow::bail!("issue URL must be an http(s) URL: {}", url);
+            }
+            self.issue_url = Some(url.to_string());
+        }
+        if let Some(owner) = self.owner.take() {
+            let owner = owner.trim();
+            if owner.chars().count() > 64 || owner.chars().any(char::is_control) {
+                anyhow::bail!("owner must be at most 64 characters without control characters");
+            }
+            self.owner = Some(owner.to_string());
+        }
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.note.is_none() && self.issue_url.is_none() && self.owner.is_none()
+    }
+}
+
+/// Changes to a repository's file sharing and upstream remote
+/// Unset fields are kept; an empty list clears the patterns.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorktreeConfigUpdate {
+    #[serde(default)]
+    pub symlink_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    pub copy_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    pub upstream_remote: Option<String>,
+}
+
+impl WorktreeConfigUpdate {
+    /// Most patterns per list
+    pub const MAX_PATTERNS: usize = 50;
+
+    /// Trim the fields, drop blank and duplicate patterns and reject unusable values
+    /// Whether the remote exists in the repository is up to the caller.
+    pub fn normalize(mut self) -> anyhow::Result<Self> {
+        if let Some(patterns) = self.symlink_patterns.take() {
+            self.symlink_patterns = Some(normalize_patterns(patterns)?);
+        }
+        if let Some(patterns) = self.copy_patterns.take() {
+            self.copy_patterns = Some(normalize_patterns(patterns)?);
+        }
+        if let Some(remote) = self.upstream_remote.take() {
+            let remote = remote.trim();
+            if remote.is_empty()
+                || remote.starts_with(['-', '.'])
+                || !remote
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                anyhow::bail!("invalid remote name: {:?}", remote);
+            }
+            self.upstream_remote = Some(remote.to_string());
+        }
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symlink_patterns.is_none()
+            && self.copy_patterns.is_none()
+            && self.upstream_remote.is_none()
+    }
+}
+
+/// Sharing patterns are relative globs with a single `*` or `**` (see `share_files`)
+fn normalize_patterns(patterns: Vec<String>) -> anyhow::Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_start_matches("./");
+        if pattern.is_empty() || normalized.iter().any(|p| p == pattern) {
+            continue;
+        }
+        if pattern.chars().any(char::is_control)
+            || std::path::Path::new(pattern).is_absolute()
+            || pattern.split('/').any(|part| part == ".." || part == ".git")
+        {
+            anyhow::bail!(
+                "pattern must be a path inside the worktree (no .., .git or leading /): {}",
+                pattern
+            );
+        }
+        let stars = pattern.matches('*').count();
+        if stars > 2 || (stars == 2 && !pattern.contains("**")) {
+            anyhow::bail!("pattern may contain a single * or **: {}", pattern);
+        }
+        normalized.push(pattern.to_string());
+    }
+    if normalized.len() > WorktreeConfigUpdate::MAX_PATTERNS {
+        anyhow::bail!(
+            "at most {} patterns per list",
+            WorktreeConfigUpdate::MAX_PATTERNS
+        );
+    }
+    Ok(normalized)
+}
+
+/// Tag and group names in use, with repository counts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LabelCounts {
+    pub tags: std::collections::BTreeMap<String, u32>,
+    pub groups: std::collections::BTreeMap<String, u32>,
+}
+
+/// Which repositories to show (all fields optional; set fields must all match)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepositoryFilter {
+    /// Has this tag
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// In this group
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Only favorites
+    #[serde(default)]
+    pub favorites: bool,
+}
+
+impl RepositoryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none() && self.group.is_none() && !self.favorites
+    }
+
+    pub fn matches(&self, repo: &Repository) -> bool {
+        self.tag.as_ref().is_none_or(|tag| repo.tags.contains(tag))
+            && self
+                .group
+                .as_ref()
+                .is_none_or(|group| repo.group.as_ref() == Some(group))
+            && (!self.favorites || repo.favorite)
+    }
+}
+
 /// Repository with its worktrees (for full state)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RepoWithWorktrees {
     #[serde(flatten)]
     pub repo: Repository,
     pub worktrees: Vec<Worktree>,
+    /// Last computed disk usage of `.bare`
+    #[serde(default)]
+    pub disk_usage: Option<DiskUsage>,
 }
 
 /// Parsed git URL components
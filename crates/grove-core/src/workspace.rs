@@ -0,0 +1,284 @@
+//! Monorepo project discovery
+//!
+//! Finds nested project roots below a worktree and works out which of them are
+//! members of an enclosing workspace (npm/yarn/pnpm workspaces, cargo and uv
+//! workspaces) and so get installed by the workspace root instead.
+
+use crate::disk::is_dependency_dir;
+use std::path::{Path, PathBuf};
+
+/// How deep below the worktree root nested projects are looked for
+pub const MAX_PROJECT_DEPTH: usize = 4;
+
+/// Build output directories never searched for projects
+const SKIP_DIRS: &[&str] = &["target", "dist", "build", "out", "vendor"];
+
+/// Directories below `root` (relative, excluding the root itself) that may hold
+/// projects, down to [`MAX_PROJECT_DEPTH`]
+/// Hidden, dependency and build output directories are skipped.
+pub fn candidate_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    walk(root, Path::new(""), 1, &mut dirs);
+    dirs.sort();
+    dirs
+}
+
+fn walk(root: &Path, rel: &Path, depth: usize, dirs: &mut Vec<PathBuf>) {
+    if depth > MAX_PROJECT_DEPTH {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(root.join(rel)) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.')
+            || SKIP_DIRS.contains(&name.as_ref())
+            || is_dependency_dir(&entry.path())
+        {
+            continue;
+        }
+
+        let child = rel.join(name.as_ref());
+        dirs.push(child.clone());
+        walk(root, &child, depth + 1, dirs);
+    }
+}
+
+/// Member patterns if `dir` is a workspace root for `ecosystem`
+/// Patterns are globs relative to `dir`; a leading `!` excludes.
+pub fn workspace_members(dir: &Path, ecosystem: &str) -> Option<Vec<String>> {
+    match ecosystem {
+        "node" => node_workspaces(dir),
+        "rust" => {
+            let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+            toml_members(&manifest, "workspace")
+        }
+        "python" => {
+            let manifest = std::fs::read_to_string(dir.join("pyproject.toml")).ok()?;
+            toml_members(&manifest, "tool.uv.workspace")
+        }
+        _ => None,
+    }
+}
+
+/// Whether `rel` (relative to the workspace root) is matched by its members
+pub fn is_member(members: &[String], rel: &Path) -> bool {
+    let rel = rel.to_string_lossy();
+    let mut matched = false;
+    for pattern in members {
+        match pattern.strip_prefix('!') {
+            Some(excluded) if glob_match(excluded, &rel) => return false,
+            Some(_) => {}
+            None => matched |= glob_match(pattern, &rel),
+        }
+    }
+    matched
+}
+
+/// `workspaces` in package.json (array or `{ packages: [...] }`) or pnpm-workspace.yaml
+fn node_workspaces(dir: &Path) -> Option<Vec<String>> {
+    if let Ok(yaml) = std::fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return Some(yaml_packages(&yaml));
+    }
+
+    let contents = std::fs::read_to_string(dir.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let workspaces = json.get("workspaces")?;
+    let list = workspaces
+        .as_array()
+        .or_else(|| workspaces.get("packages").and_then(|p| p.as_array()))?;
+
+    Some(
+        list.iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// The `packages:` list of pnpm-workspace.yaml (block style)
+fn yaml_packages(yaml: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed.starts_with("packages:");
+            continue;
+        }
+        if in_packages && let Some(item) = trimmed.strip_prefix('-') {
+            packages.push(unquote(item.trim()).to_string());
+        }
+    }
+    packages
+}
+
+/// `members` minus `exclude` of a TOML table (e.g. `[workspace]`)
+/// Only the array-of-strings form is understood, which is all manifests use.
+fn toml_members(manifest: &str, table: &str) -> Option<Vec<String>> {
+    let header = format!("[{}]", table);
+    let mut in_table = false;
+    let mut found = false;
+    let mut members = Vec::new();
+    let mut key: Option<&str> = None;
+
+    for line in manifest.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.starts_with('[') {
+            in_table = line == header;
+            found |= in_table;
+            key = None;
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+
+        let mut rest = line;
+        if key.is_none()
+            && let Some((k, v)) = line.split_once('=')
+            && matches!(k.trim(), "members" | "exclude")
+        {
+            key = Some(k.trim());
+            rest = v.trim().trim_start_matches('[');
+        }
+        let Some(current) = key else { continue };
+
+        for item in rest.split(',') {
+            let item = unquote(item.trim().trim_end_matches(']').trim());
+            if !item.is_empty() {
+                members.push(match current {
+                    "exclude" => format!("!{}", item),
+                    _ => item.to_string(),
+                });
+            }
+        }
+        if rest.contains(']') {
+            key = None;
+        }
+    }
+
+    found.then_some(members)
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Match a path against a glob: `*` and `?` within a segment, `**` across segments
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_segments(rest, &path[i..])),
+        Some((segment, rest)) => {
+            !path.is_empty() && match_segment(segment, path[0]) && match_segments(rest, &path[1..])
+        }
+    }
+}
+
+fn match_segment(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let (mut star, mut star_n) = (None, 0);
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            star_n = n;
+            p += 1;
+        } else if let Some(s) = star {
+            p = s + 1;
+            star_n += 1;
+            n = star_n;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_segments() {
+        assert!(glob_match("packages/*", "packages/web"));
+        assert!(glob_match("./packages/*/", "packages/web"));
+        assert!(!glob_match("packages/*", "packages/web/src"));
+        assert!(!glob_match("packages/*", "apps/web"));
+        assert!(glob_match("crates/grove-?", "crates/grove-a"));
+        assert!(glob_match("apps/*-ui", "apps/admin-ui"));
+        assert!(!glob_match("apps/*-ui", "apps/admin-api"));
+    }
+
+    #[test]
+    fn glob_match_double_star() {
+        assert!(glob_match("packages/**", "packages/web"));
+        assert!(glob_match("packages/**", "packages/web/nested"));
+        assert!(glob_match("**/app", "services/api/app"));
+        assert!(glob_match("**/app", "app"));
+        assert!(!glob_match("**/app", "services/api"));
+    }
+
+    #[test]
+    fn toml_members_with_exclude() {
+        let manifest = r#"
+[package]
+name = "root"
+members = ["not-this"]
+
+[workspace]
+members = [
+    "crates/*", # every crate
+    'tools/cli',
+]
+exclude = ["crates/legacy"]
+
+[workspace.dependencies]
+serde = "1"
+"#;
+        assert_eq!(
+            toml_members(manifest, "workspace"),
+            Some(vec![
+                "crates/*".to_string(),
+                "tools/cli".to_string(),
+                "!crates/legacy".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn toml_members_single_line_and_missing_table() {
+        let manifest = "[tool.uv.workspace]\nmembers = [\"packages/*\"]\n";
+        assert_eq!(
+            toml_members(manifest, "tool.uv.workspace"),
+            Some(vec!["packages/*".to_string()])
+        );
+        assert_eq!(toml_members(manifest, "workspace"), None);
+    }
+
+    #[test]
+    fn is_member_honors_exclusions() {
+        let members = vec!["crates/*".to_string(), "!crates/legacy".to_string()];
+        assert!(is_member(&members, Path::new("crates/core")));
+        assert!(!is_member(&members, Path::new("crates/legacy")));
+        assert!(!is_member(&members, Path::new("tools/cli")));
+    }
+}
@@ -10,6 +10,8 @@ use tui_textarea::TextArea;
 pub const COMMANDS: &[(&str, &str)] = &[
     ("/clone", "Clone a repository"),
     ("/list", "List repositories"),
+    ("/logs", "Show the latest install log for a worktree"),
+    ("/cancel", "Cancel the install running in a worktree"),
     ("/harvest", "Export repos to seed file"),
     ("/grow", "Import repos from seed file"),
     ("/help", "Show available commands"),
@@ -46,6 +48,10 @@ pub enum Command {
     Clone(String),
     /// List repositories
     List,
+    /// Show the latest install log for a worktree path
+    Logs(String),
+    /// Cancel the install running in a worktree path
+    CancelInstall(String),
     /// Export repositories to seed file
     Harvest(String),
     /// Import repositories from seed file
@@ -150,10 +156,8 @@ impl ChatApp {
                 maybe_event = event_stream.next() => {
                     if let Some(Ok(event)) = maybe_event {
                         match event {
-                            Event::Key(key) => {
-                                if self.handle_key(key).await? {
-                                    break;
-                                }
+                            Event::Key(key) if self.handle_key(key).await? => {
+                                break;
                             }
                             Event::Mouse(mouse) => {
                                 self.handle_mouse(mouse);
@@ -324,7 +328,7 @@ impl ChatApp {
     /// Handle slash command
     async fn handle_command(&mut self, input: &str) -> anyhow::Result<()> {
         let parts: Vec<&str> = input.split_whitespace().collect();
-        let cmd = parts.first().map(|s| *s).unwrap_or("");
+        let cmd = parts.first().copied().unwrap_or("");
 
         match cmd {
             "/help" | "/?" => {
@@ -333,6 +337,8 @@ impl ChatApp {
                     content: r#"Commands:
   /clone <url>           Clone a repository
   /list                  List repositories
+  /logs <path>           Show latest install log for a worktree
+  /cancel <path>         Cancel a running install
   /harvest <file>        Export repos to seed file
   /grow <file>           Import repos from seed file
   /exit                  Exit grove
@@ -359,6 +365,28 @@ Navigation:
             "/list" => {
                 self.command_tx.send(Command::List).await?;
             }
+            "/logs" => {
+                if let Some(path) = parts.get(1) {
+                    self.command_tx.send(Command::Logs(path.to_string())).await?;
+                } else {
+                    self.messages.push(Message {
+                        role: Role::System,
+                        content: "Usage: /logs <path>".to_string(),
+                        timestamp: Local::now(),
+                    });
+                }
+            }
+            "/cancel" => {
+                if let Some(path) = parts.get(1) {
+                    self.command_tx.send(Command::CancelInstall(path.to_string())).await?;
+                } else {
+                    self.messages.push(Message {
+                        role: Role::System,
+                        content: "Usage: /cancel <path>".to_string(),
+                        timestamp: Local::now(),
+                    });
+                }
+            }
             "/harvest" => {
                 let file = parts.get(1).map(|s| s.to_string()).unwrap_or_else(|| "seed.jsonl".to_string());
                 self.command_tx.send(Command::Harvest(file)).await?;
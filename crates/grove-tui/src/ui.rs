@@ -108,11 +108,7 @@ fn render_messages(frame: &mut Frame, app: &ChatApp, area: Rect) {
 
     // scroll_offset is distance from bottom (0 = at bottom)
     // scroll_from_top is what Paragraph expects
-    let scroll_from_top = if app.scroll_offset >= max_scroll {
-        0 // At top
-    } else {
-        max_scroll - app.scroll_offset // Convert to top-based
-    };
+    let scroll_from_top = max_scroll.saturating_sub(app.scroll_offset);
 
     let paragraph = Paragraph::new(lines)
         .scroll((scroll_from_top as u16, 0))
@@ -200,9 +196,7 @@ fn render_autocomplete(frame: &mut Frame, app: &ChatApp, commands: &[(&str, &str
     let total = commands.len();
     let selected = app.autocomplete_index;
 
-    let start = if total <= max_visible {
-        0
-    } else if selected < max_visible / 2 {
+    let start = if total <= max_visible || selected < max_visible / 2 {
         0
     } else if selected >= total - max_visible / 2 {
         total - max_visible